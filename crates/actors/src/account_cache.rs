@@ -1,24 +1,31 @@
-use crate::{helpers::Coerce, process_group_changed, AccountValue, MAX_BATCH_SIZE};
+use crate::{
+    helpers::Coerce, process_group_changed, AccountValue, CacheMetrics, CacheMetricsSnapshot,
+    MAX_BATCH_SIZE,
+};
 use async_trait::async_trait;
 use futures::stream::FuturesUnordered;
 use lasr_messages::{
-    AccountCacheMessage, ActorName, ActorType, RpcMessage, RpcResponseError, SupervisorType,
-    TransactionResponse,
+    AccountCacheMessage, ActorName, ActorType, RpcMessage, RpcResponseError, Shutdown,
+    SupervisorType, TransactionResponse,
 };
-#[cfg(feature = "mock_storage")]
+#[cfg(any(feature = "mock_storage", feature = "test-util"))]
 use lasr_types::MockPersistenceStore;
 use lasr_types::{Account, AccountType, Address, PersistenceStore};
 use ractor::{
     concurrency::OneshotReceiver, Actor, ActorCell, ActorProcessingErr, ActorRef, SupervisionEvent,
 };
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 use thiserror::Error;
 #[cfg(not(feature = "mock_storage"))]
 use tikv_client::RawClient as TikvClient;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::{broadcast, mpsc::Sender};
 
 #[derive(Debug, Clone, Default)]
 pub struct AccountCacheActor;
@@ -52,16 +59,173 @@ impl Default for AccountCacheError {
 pub struct AccountCache<S: PersistenceStore> {
     inner: AccountCacheInner,
     storage: S,
+    snapshot_path: Option<PathBuf>,
 }
 impl<S: PersistenceStore> AccountCache<S> {
     pub fn new(storage: S) -> Self {
         Self {
             inner: AccountCacheInner::new(),
             storage,
+            snapshot_path: None,
+        }
+    }
+
+    /// Restores the cache from the snapshot at `path` if one exists, and
+    /// makes `path` the target of [`AccountCacheMessage::Snapshot`].
+    pub fn with_snapshot_path(mut self, path: PathBuf) -> std::io::Result<Self> {
+        if path.exists() {
+            self.load_from(&path)?;
+            tracing::info!(
+                "restored {} accounts from snapshot {}",
+                self.inner.len(),
+                path.display()
+            );
+        }
+        self.snapshot_path = Some(path);
+        Ok(self)
+    }
+
+    /// Replays the log at `path` if one exists, then appends every later
+    /// cache write and removal to it. Apply after
+    /// [`AccountCache::with_snapshot_path`] so the log replays on top of the
+    /// snapshot it was truncated at.
+    pub fn with_log_path(mut self, path: PathBuf) -> std::io::Result<Self> {
+        if path.exists() {
+            self.replay_log(&path)?;
+        }
+        self.open_log(&path)?;
+        Ok(self)
+    }
+
+    /// Writes every cached account to `path` so the cache can be restored
+    /// with [`AccountCache::load_from`] after a restart.
+    pub fn snapshot_to(&self, path: &Path) -> std::io::Result<()> {
+        self.inner.snapshot_to(path)
+    }
+
+    /// Rehydrates the cache from a snapshot written by
+    /// [`AccountCache::snapshot_to`].
+    pub fn load_from(&mut self, path: &Path) -> std::io::Result<()> {
+        self.inner.load_from(path)
+    }
+
+    /// Appends every subsequent cache write and removal to the log at `path`,
+    /// creating it if needed. Replaying the log with
+    /// [`AccountCache::replay_log`] restores the cache after a crash; taking a
+    /// snapshot first bounds how much of it has to be replayed.
+    pub fn open_log(&mut self, path: &Path) -> std::io::Result<()> {
+        self.inner.open_log(path)
+    }
+
+    /// Applies every operation recorded in the log at `path`, in order. A
+    /// record torn off by a crash mid-append ends the replay.
+    pub fn replay_log(&mut self, path: &Path) -> std::io::Result<()> {
+        self.inner.replay_log(path)
+    }
+
+    pub fn metrics(&self) -> CacheMetricsSnapshot {
+        self.inner.metrics()
+    }
+
+    /// Iterates over every cached account without cloning it or marking it
+    /// as recently used.
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &Account)> {
+        self.inner.iter()
+    }
+
+    /// Clones every cached account, in no particular order.
+    pub fn export(&self) -> Vec<Account> {
+        self.inner.export()
+    }
+
+    /// Publishes a [`CacheEvent`] on `events` whenever an account is written
+    /// to or removed from the cache.
+    pub fn set_event_sender(&mut self, events: broadcast::Sender<CacheEvent>) {
+        self.inner.set_event_sender(events)
+    }
+}
+
+impl AccountCache<StorageRef> {
+    /// Returns the cached account for `address`, falling back to the
+    /// persistence store when it is not cached.
+    pub(crate) async fn read(&mut self, address: &Address) -> Option<Account> {
+        if let Some(account) = self.inner.get(address) {
+            let hex_address = address.to_full_string();
+            tracing::warn!(address = %hex_address, "retrieved account from account cache for address {hex_address}: {account:?}");
+            return Some(account.clone());
+        }
+        AccountCacheActor::read_from_storage(&self.storage, address).await
+    }
+
+    /// Writes every account evicted from the cache to the persistence store,
+    /// so a later read does not fall through to an older stored copy.
+    pub(crate) async fn persist_evicted(&mut self) {
+        for (address, account) in self.inner.take_evicted() {
+            let hex_address = address.to_full_string();
+            let Ok(val) = bincode::serialize(&AccountValue { account }) else {
+                tracing::error!(address = %hex_address, "failed to serialize evicted account {hex_address}");
+                continue;
+            };
+            if PersistenceStore::put(&self.storage, hex_address.clone().into(), val)
+                .await
+                .is_err()
+            {
+                tracing::error!(address = %hex_address, "failed to persist evicted account {hex_address}");
+            }
         }
     }
 }
 
+/// A change to the set of accounts held by the account cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEvent {
+    Written(Address),
+    /// The account was removed from the cache, either explicitly or by
+    /// eviction.
+    Removed(Address),
+}
+
+#[cfg(feature = "test-util")]
+impl AccountCache<MockPersistenceStore<String, Vec<u8>>> {
+    /// Creates a cache backed by an empty in-memory store, so code using the
+    /// cache can be unit tested without spawning the account cache actor.
+    pub fn in_memory() -> Self {
+        Self::new(MockPersistenceStore::default())
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl<S: PersistenceStore> AccountCache<S> {
+    /// Caches `account` under its owner address, or its program address for
+    /// program accounts, replacing any cached account at that address.
+    pub fn insert(&mut self, account: Account) {
+        let address = match account.account_type() {
+            AccountType::Program(program_address) => program_address,
+            AccountType::User => account.owner_address(),
+        };
+        self.inner.insert(address, account);
+    }
+
+    pub fn get(&mut self, address: &Address) -> Option<&Account> {
+        self.inner.get(address)
+    }
+}
+
+/// An operation recorded in the account cache log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CacheOp {
+    Write(Account),
+    Remove(Address),
+}
+
+/// The default number of seconds between account cache snapshots, used when
+/// `ACCOUNT_CACHE_SNAPSHOT_INTERVAL` is not set.
+pub const DEFAULT_ACCOUNT_CACHE_SNAPSHOT_INTERVAL: u64 = 60;
+
+/// The default bound on the number of accounts held in the cache, used when
+/// `ACCOUNT_CACHE_MAX_ENTRIES` is not set.
+pub const DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES: usize = 10_000;
+
 #[allow(unused)]
 #[derive(Debug, Default)]
 pub struct AccountCacheInner {
@@ -69,10 +233,33 @@ pub struct AccountCacheInner {
     receivers: FuturesUnordered<OneshotReceiver<Address>>,
     batch_interval: Duration,
     last_batch: Option<Instant>,
+    max_entries: usize,
+    /// Last access tick of every cached address, and the reverse mapping
+    /// ordered from least to most recently used.
+    recency: HashMap<Address, u64>,
+    access_order: BTreeMap<u64, Address>,
+    tick: u64,
+    metrics: CacheMetrics,
+    events: Option<broadcast::Sender<CacheEvent>>,
+    log: Option<File>,
+    /// Accounts evicted since the last call to `take_evicted`. Cached
+    /// accounts are only persisted when a batch is built, so these are
+    /// written through to storage before anything reads them back.
+    evicted: Vec<(Address, Account)>,
 }
 
 impl AccountCacheInner {
     pub fn new() -> Self {
+        let max_entries = std::env::var("ACCOUNT_CACHE_MAX_ENTRIES")
+            .unwrap_or_else(|_| DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES.to_string())
+            .parse::<usize>()
+            .unwrap_or(DEFAULT_ACCOUNT_CACHE_MAX_ENTRIES);
+        Self::with_max_entries(max_entries)
+    }
+
+    /// Creates a cache holding at most `max_entries` accounts, evicting the
+    /// least recently used account when a new one is written while full.
+    pub fn with_max_entries(max_entries: usize) -> Self {
         let batch_interval_secs = std::env::var("BATCH_INTERVAL")
             .unwrap_or_else(|_| "180".to_string())
             .parse::<u64>()
@@ -82,31 +269,236 @@ impl AccountCacheInner {
             receivers: FuturesUnordered::new(),
             batch_interval: Duration::from_secs(batch_interval_secs),
             last_batch: None,
+            max_entries: max_entries.max(1),
+            recency: HashMap::new(),
+            access_order: BTreeMap::new(),
+            tick: 0,
+            metrics: CacheMetrics::new(),
+            events: None,
+            log: None,
+            evicted: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn metrics(&self) -> CacheMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &Account)> {
+        self.cache.iter()
+    }
+
+    pub fn export(&self) -> Vec<Account> {
+        self.cache.values().cloned().collect()
+    }
+
+    pub fn set_event_sender(&mut self, events: broadcast::Sender<CacheEvent>) {
+        self.events = Some(events);
+    }
+
+    fn notify(&self, event: CacheEvent) {
+        if let Some(events) = &self.events {
+            // Sending only fails while there are no subscribers.
+            events.send(event).ok();
+        }
+    }
+
+    /// Marks `address` as the most recently used entry.
+    fn touch(&mut self, address: &Address) {
+        self.tick += 1;
+        if let Some(previous) = self.recency.insert(*address, self.tick) {
+            self.access_order.remove(&previous);
+        }
+        self.access_order.insert(self.tick, *address);
+    }
+
+    fn forget(&mut self, address: &Address) {
+        if let Some(previous) = self.recency.remove(address) {
+            self.access_order.remove(&previous);
         }
     }
 
-    pub(crate) fn get(&self, address: &Address) -> Option<&Account> {
-        if let Some(account) = self.cache.get(address) {
-            return Some(account);
+    /// Evicts least recently used accounts until there is room for one more.
+    fn evict_to_fit(&mut self) {
+        while self.cache.len() >= self.max_entries {
+            let Some((_, address)) = self.access_order.pop_first() else {
+                break;
+            };
+            self.recency.remove(&address);
+            if let Some(account) = self.cache.remove(&address) {
+                self.evicted.push((address, account));
+            }
+            self.notify(CacheEvent::Removed(address));
+            tracing::info!(
+                address = %address.to_full_string(),
+                "Evicted least recently used account: 0x{:x}",
+                &address
+            );
         }
+    }
+
+    /// Takes the accounts evicted since the last call, which still have to be
+    /// written to storage.
+    pub(crate) fn take_evicted(&mut self) -> Vec<(Address, Account)> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    fn insert(&mut self, address: Address, account: Account) {
+        if !self.cache.contains_key(&address) {
+            self.evict_to_fit();
+        }
+        self.touch(&address);
+        self.cache.insert(address, account);
+        self.notify(CacheEvent::Written(address));
+    }
+
+    pub(crate) fn get(&mut self, address: &Address) -> Option<&Account> {
+        if self.cache.contains_key(address) {
+            self.metrics.record_hit();
+            self.touch(address);
+            return self.cache.get(address);
+        }
+        self.metrics.record_miss();
         None
     }
 
+    /// Looks up every address in the cache, returning one entry per address
+    /// in the order given. Hits are marked as recently used.
+    pub(crate) fn handle_cache_check_many(
+        &mut self,
+        addresses: &[Address],
+    ) -> Vec<(Address, Option<Account>)> {
+        addresses
+            .iter()
+            .map(|address| (*address, self.get(address).cloned()))
+            .collect()
+    }
+
+    /// Writes the snapshot to a temporary file next to `path` and renames it
+    /// into place, so a crash mid-write leaves the previous snapshot intact.
+    pub(crate) fn snapshot_to(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = bincode::serialize(&self.cache)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    }
+
+    pub(crate) fn load_from(&mut self, path: &Path) -> std::io::Result<()> {
+        let bytes = std::fs::read(path)?;
+        let cache: HashMap<Address, Account> = bincode::deserialize(&bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        for (address, account) in cache {
+            self.insert(address, account);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn open_log(&mut self, path: &Path) -> std::io::Result<()> {
+        self.log = Some(OpenOptions::new().create(true).append(true).open(path)?);
+        Ok(())
+    }
+
+    /// Appends `ops` to the log, each as a little endian `u32` length
+    /// followed by its bincode encoding, and syncs the log to disk once they
+    /// are all written.
+    fn append_ops(&mut self, ops: &[CacheOp]) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let Some(log) = self.log.as_mut() else {
+            return Ok(());
+        };
+        let mut records = Vec::new();
+        for op in ops {
+            let bytes =
+                bincode::serialize(op).map_err(|e| e as Box<dyn std::error::Error + Send>)?;
+            records.extend((bytes.len() as u32).to_le_bytes());
+            records.extend(bytes);
+        }
+        log.write_all(&records)
+            .and_then(|()| log.sync_data())
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)
+    }
+
+    fn append_op(&mut self, op: CacheOp) -> Result<(), Box<dyn std::error::Error + Send>> {
+        self.append_ops(&[op])
+    }
+
+    /// Empties the log once a snapshot holds everything recorded in it.
+    pub(crate) fn truncate_log(&mut self) -> std::io::Result<()> {
+        if let Some(log) = self.log.as_mut() {
+            log.set_len(0)?;
+            log.sync_data()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn replay_log(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut log = File::open(path)?;
+        let mut len = [0u8; 4];
+        loop {
+            match log.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+            match log.read_exact(&mut bytes) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    tracing::warn!("account cache log ends with a partial record, ignoring it");
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+            let op: CacheOp = bincode::deserialize(&bytes)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            match op {
+                CacheOp::Write(account) => self.write_account(account),
+                CacheOp::Remove(address) => self.remove_account(&address),
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn remove(
         &mut self,
         address: &Address,
     ) -> Result<(), Box<dyn std::error::Error + Send>> {
-        self.cache.remove(address);
+        self.append_op(CacheOp::Remove(*address))?;
+        self.remove_account(address);
         Ok(())
     }
 
+    fn remove_account(&mut self, address: &Address) {
+        self.forget(address);
+        if self.cache.remove(address).is_some() {
+            self.metrics.record_removal();
+            self.notify(CacheEvent::Removed(*address));
+        }
+    }
+
     pub(crate) fn update(
         &mut self,
         account: Account,
     ) -> Result<(), Box<dyn std::error::Error + Send>> {
         let addr = account.owner_address();
+        if self.cache.contains_key(&addr) {
+            self.append_op(CacheOp::Write(account.clone()))?;
+        }
         if let Some(a) = self.cache.get_mut(&addr) {
             *a = account;
+            self.metrics.record_write();
+            self.touch(&addr);
+            self.notify(CacheEvent::Written(addr));
             return Ok(());
         }
 
@@ -120,19 +512,53 @@ impl AccountCacheInner {
         &mut self,
         account: Account,
     ) -> Result<(), Box<dyn std::error::Error + Send>> {
+        self.append_op(CacheOp::Write(account.clone()))?;
+        self.metrics.record_write();
+        self.write_account(account);
+        self.check_build_batch()?;
+
+        Ok(())
+    }
+
+    /// Writes every account in `accounts` to the cache, checking whether a
+    /// batch should be built once for the whole set rather than per account.
+    pub(crate) fn handle_cache_write_batch(
+        &mut self,
+        accounts: Vec<Account>,
+    ) -> Result<(), Box<dyn std::error::Error + Send>> {
+        let ops: Vec<CacheOp> = accounts.iter().cloned().map(CacheOp::Write).collect();
+        self.append_ops(&ops)?;
+        for account in accounts {
+            self.metrics.record_write();
+            self.write_account(account);
+        }
+        self.check_build_batch()?;
+
+        Ok(())
+    }
+
+    fn write_account(&mut self, account: Account) {
         match account.account_type() {
             AccountType::User => {
                 let address = account.owner_address();
                 if let Some(entry) = self.cache.get_mut(&address) {
-                    tracing::info!("Found account: 0x{:x} in cache, updating...", &address);
+                    tracing::info!(
+                        address = %address.to_full_string(),
+                        "Found account: 0x{:x} in cache, updating...",
+                        &address
+                    );
                     *entry = account;
+                    self.touch(&address);
+                    self.notify(CacheEvent::Written(address));
                 } else {
                     tracing::info!(
+                        address = %address.to_full_string(),
                         "Did not find account: 0x{:x} in cache, inserting...",
                         &address
                     );
-                    self.cache.insert(address, account);
+                    self.insert(address, account);
                     tracing::info!(
+                        address = %address.to_full_string(),
                         "Inserted account: 0x{:x} in cache, cache.len(): {}",
                         &address,
                         self.cache.len()
@@ -142,17 +568,22 @@ impl AccountCacheInner {
             AccountType::Program(program_address) => {
                 if let Some(entry) = self.cache.get_mut(&program_address) {
                     tracing::info!(
+                        address = %program_address.to_full_string(),
                         "Found program_account: 0x{:x} in cache, updating...",
                         &program_address
                     );
                     *entry = account;
+                    self.touch(&program_address);
+                    self.notify(CacheEvent::Written(program_address));
                 } else {
                     tracing::info!(
+                        address = %program_address.to_full_string(),
                         "Did not find account: 0x{:x} in cache, inserting...",
                         &program_address
                     );
-                    self.cache.insert(program_address, account);
+                    self.insert(program_address, account);
                     tracing::info!(
+                        address = %program_address.to_full_string(),
                         "Inserted account: 0x{:x} in cache, cache.len(): {}",
                         &program_address,
                         self.cache.len()
@@ -160,10 +591,6 @@ impl AccountCacheInner {
                 }
             }
         }
-
-        self.check_build_batch()?;
-
-        Ok(())
     }
 
     fn check_build_batch(&mut self) -> Result<(), Box<dyn std::error::Error + Send>> {
@@ -192,6 +619,40 @@ impl AccountCacheActor {
     pub fn new() -> Self {
         Self
     }
+
+    /// Pulls the account for `address` from the persistence store, used
+    /// when it is not present in the cache.
+    async fn read_from_storage(
+        storage: &<Self as Actor>::Arguments,
+        address: &Address,
+    ) -> Option<Account> {
+        let hex_address = &address.to_full_string();
+        // Pass to persistence store
+        tracing::warn!(
+            address = %hex_address,
+            "Account not found in AccountCache for address {hex_address}, connecting to persistence store."
+        );
+        let acc_key = address.to_full_string();
+
+        // Pull `Account` data from persistence store
+        PersistenceStore::get(
+            storage,
+            acc_key.to_owned().into()
+        )
+        .await
+        .typecast()
+        .log_err(|e| AccountCacheError::Custom(format!("failed to find Account with address: {hex_address} in persistence store: {e:?}")))
+        .flatten()
+        .and_then(|returned_data| {
+            AccountValue::decode(&returned_data)
+                .typecast()
+                .log_err(|e| e)
+                .and_then(|AccountValue { account }| {
+                    tracing::debug!(address = %hex_address, "retrieved account from persistence store for address {hex_address}: {account:?}");
+                    Some(account)
+                })
+        })
+    }
 }
 
 #[async_trait]
@@ -208,16 +669,27 @@ impl Actor for AccountCacheActor {
         _myself: ActorRef<Self::Msg>,
         args: Self::Arguments,
     ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(AccountCache::new(args))
+        let mut cache = AccountCache::new(args);
+        if let Ok(path) = std::env::var("ACCOUNT_CACHE_SNAPSHOT_PATH") {
+            cache = cache.with_snapshot_path(PathBuf::from(path))?;
+        }
+        if let Ok(path) = std::env::var("ACCOUNT_CACHE_LOG_PATH") {
+            cache = cache.with_log_path(PathBuf::from(path))?;
+        }
+        cache.persist_evicted().await;
+        Ok(cache)
     }
 
     async fn handle(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            AccountCacheMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::AccountCache);
+            }
             AccountCacheMessage::Write {
                 account,
                 who,
@@ -225,58 +697,97 @@ impl Actor for AccountCacheActor {
             } => {
                 let owner = &account.owner_address().to_full_string();
                 tracing::warn!(
+                    address = %owner,
                     "Received account cache write request from {} for address {}: WHERE: {}",
                     who.to_string(),
                     owner,
                     location
                 );
-                let _ = state.inner.handle_cache_write(account.clone());
-                tracing::info!("Account written to for address {owner}: {:?}", &account);
+                if state
+                    .inner
+                    .handle_cache_write(account.clone())
+                    .typecast()
+                    .log_err(|e| {
+                        AccountCacheError::Custom(format!(
+                            "failed to write account {owner} to cache: {e:?}"
+                        ))
+                    })
+                    .is_some()
+                {
+                    tracing::info!(
+                        address = %owner,
+                        "Account written to for address {owner}: {:?}",
+                        &account
+                    );
+                }
+            }
+            AccountCacheMessage::WriteBatch {
+                accounts,
+                who,
+                location,
+            } => {
+                tracing::warn!(
+                    "Received account cache batch write request from {} for {} accounts: WHERE: {}",
+                    who.to_string(),
+                    accounts.len(),
+                    location
+                );
+                state
+                    .inner
+                    .handle_cache_write_batch(accounts)
+                    .typecast()
+                    .log_err(|e| {
+                        AccountCacheError::Custom(format!(
+                            "failed to write account batch to cache: {e:?}"
+                        ))
+                    });
             }
             AccountCacheMessage::Read { address, tx, who } => {
                 let hex_address = &address.to_full_string();
                 tracing::warn!(
+                    address = %hex_address,
                     "Recieved account cache read request from {} for address: {}",
                     who.to_string(),
                     hex_address
                 );
-                let account = if let Some(account) = state.inner.get(&address) {
-                    tracing::warn!("retrieved account from account cache for address {hex_address}: {account:?}");
-                    Some(account.clone())
-                } else {
-                    // Pass to persistence store
-                    tracing::warn!(
-                        "Account not found in AccountCache for address {hex_address}, connecting to persistence store."
-                    );
-                    let acc_key = address.to_full_string();
-
-                    // Pull `Account` data from persistence store
-                    PersistenceStore::get(
-                        &state.storage,
-                        acc_key.to_owned().into()
-                    )
-                    .await
-                    .typecast()
-                    .log_err(|e| AccountCacheError::Custom(format!("failed to find Account with address: {hex_address} in persistence store: {e:?}")))
-                    .flatten()
-                    .and_then(|returned_data| {
-                        bincode::deserialize(&returned_data)
-                            .typecast()
-                            .log_err(|e| e)
-                            .and_then(|AccountValue { account }| {
-                                tracing::debug!("retrieved account from persistence store for address {hex_address}: {account:?}");
-                                Some(account)
-                            })
-                    })
-                };
+                let account = state.read(&address).await;
                 let _ = tx.send(account);
             }
+            AccountCacheMessage::ReadMany { addresses, tx, who } => {
+                tracing::warn!(
+                    "Recieved account cache read request from {} for {} addresses",
+                    who.to_string(),
+                    addresses.len()
+                );
+                let mut accounts = state.inner.handle_cache_check_many(&addresses);
+                for (address, account) in accounts.iter_mut() {
+                    if account.is_none() {
+                        *account = Self::read_from_storage(&state.storage, address).await;
+                    }
+                }
+                let _ = tx.send(accounts);
+            }
             AccountCacheMessage::Remove { address } => {
-                let _ = state.inner.remove(&address);
+                state.inner.remove(&address).typecast().log_err(|e| {
+                    AccountCacheError::Custom(format!(
+                        "failed to remove account {} from cache: {e:?}",
+                        address.to_full_string()
+                    ))
+                });
             }
             AccountCacheMessage::Update { account } => {
-                if let Err(_e) = state.inner.update(account.clone()) {
-                    let _ = state.inner.handle_cache_write(account.clone());
+                if let Err(e) = state.inner.update(account.clone()) {
+                    tracing::info!("{e}, writing account to cache instead");
+                    state
+                        .inner
+                        .handle_cache_write(account.clone())
+                        .typecast()
+                        .log_err(|e| {
+                            AccountCacheError::Custom(format!(
+                                "failed to write account {} to cache: {e:?}",
+                                account.owner_address().to_full_string()
+                            ))
+                        });
                 }
             }
             AccountCacheMessage::TryGetAccount { address, reply } => {
@@ -297,11 +808,72 @@ impl Actor for AccountCacheActor {
                     });
                 }
             }
+            AccountCacheMessage::Snapshot => {
+                if let Some(path) = state.snapshot_path.clone() {
+                    let snapshot = state
+                        .snapshot_to(&path)
+                        .and_then(|()| state.inner.truncate_log());
+                    snapshot.typecast().log_err(|e| {
+                        AccountCacheError::Custom(format!(
+                            "failed to snapshot account cache to {}: {e:?}",
+                            path.display()
+                        ))
+                    });
+                }
+            }
+            AccountCacheMessage::Drain => {
+                tracing::warn!("AccountCache drained, stopping");
+                myself.stop(Some("drained".to_string()));
+            }
         }
+        state.persist_evicted().await;
         Ok(())
     }
 }
 
+/// Stops the `AccountCacheActor`.
+///
+/// `Shutdown::Drain` queues the stop behind any buffered messages, so writes
+/// sent before shutdown are applied to the cache before the actor exits.
+/// `Shutdown::Immediate` stops the actor without handling them.
+pub fn shutdown_account_cache(mode: Shutdown) -> Result<(), AccountCacheError> {
+    let actor: ActorRef<AccountCacheMessage> =
+        ractor::registry::where_is(ActorType::AccountCache.to_string())
+            .ok_or(AccountCacheError::RactorRegistryError)?
+            .into();
+
+    match mode {
+        Shutdown::Drain => actor
+            .cast(AccountCacheMessage::Drain)
+            .map_err(|e| AccountCacheError::Custom(format!("failed to drain AccountCache: {e:?}"))),
+        Shutdown::Immediate => {
+            actor.stop(Some("immediate shutdown".to_string()));
+            Ok(())
+        }
+    }
+}
+
+/// Asks the `AccountCacheActor` to snapshot itself every
+/// `ACCOUNT_CACHE_SNAPSHOT_INTERVAL` seconds.
+pub async fn account_cache_snapshotter() -> std::io::Result<()> {
+    let account_cache: ActorRef<AccountCacheMessage> =
+        ractor::registry::where_is(ActorType::AccountCache.to_string())
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "unable to acquire AccountCache Actor",
+            ))?
+            .into();
+    let interval_secs = std::env::var("ACCOUNT_CACHE_SNAPSHOT_INTERVAL")
+        .unwrap_or_else(|_| DEFAULT_ACCOUNT_CACHE_SNAPSHOT_INTERVAL.to_string())
+        .parse::<u64>()
+        .unwrap_or(DEFAULT_ACCOUNT_CACHE_SNAPSHOT_INTERVAL);
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+        let _ = account_cache.cast(AccountCacheMessage::Snapshot);
+    }
+}
+
 pub struct AccountCacheSupervisor {
     panic_tx: Sender<ActorCell>,
 }
@@ -368,3 +940,459 @@ impl Actor for AccountCacheSupervisor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod account_cache_tests {
+    #[cfg(feature = "test-util")]
+    use super::AccountCache;
+    use super::{AccountCacheInner, CacheEvent};
+    use crate::CacheMetricsSnapshot;
+    use lasr_types::{Account, AccountType, Address};
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::{layer::Context, prelude::*, Layer, Registry};
+
+    fn user_account(n: u8) -> Account {
+        Account::new(AccountType::User, None, Address::new([n; 20]), None)
+    }
+
+    /// Records the fields of every event, including the rendered message.
+    #[derive(Clone, Default)]
+    struct FieldCapture(Arc<Mutex<Vec<BTreeMap<&'static str, String>>>>);
+
+    #[derive(Default)]
+    struct FieldVisitor(BTreeMap<&'static str, String>);
+
+    impl Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for FieldCapture {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = FieldVisitor::default();
+            event.record(&mut visitor);
+            self.0.lock().unwrap().push(visitor.0);
+        }
+    }
+
+    #[test]
+    fn test_account_cache_never_exceeds_max_entries() {
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        for n in 0..64u8 {
+            cache.handle_cache_write(user_account(n)).unwrap();
+            assert!(cache.len() <= 8);
+        }
+        assert_eq!(cache.len(), 8);
+        assert!(cache.get(&Address::new([63; 20])).is_some());
+        assert!(cache.get(&Address::new([0; 20])).is_none());
+    }
+
+    #[test]
+    fn test_account_cache_logs_address_field() {
+        let capture = FieldCapture::default();
+        let subscriber = Registry::default().with(capture.clone());
+        let mut cache = AccountCacheInner::with_max_entries(1);
+        tracing::subscriber::with_default(subscriber, || {
+            cache.handle_cache_write(user_account(1)).unwrap();
+            cache.handle_cache_write(user_account(2)).unwrap();
+        });
+
+        let first = Address::new([1; 20]).to_full_string();
+        let second = Address::new([2; 20]).to_full_string();
+        let events = capture.0.lock().unwrap();
+        let logged = |message: String, address: &str| {
+            events.iter().any(|fields| {
+                fields.get("message") == Some(&message)
+                    && fields.get("address").map(String::as_str) == Some(address)
+            })
+        };
+        assert!(logged(
+            format!("Inserted account: {first} in cache, cache.len(): 1"),
+            &first
+        ));
+        assert!(logged(
+            format!("Evicted least recently used account: {first}"),
+            &first
+        ));
+        assert!(logged(
+            format!("Did not find account: {second} in cache, inserting..."),
+            &second
+        ));
+    }
+
+    #[test]
+    fn test_account_cache_write_batch() {
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        cache
+            .handle_cache_write_batch((1..=3).map(user_account).collect())
+            .unwrap();
+
+        assert_eq!(cache.len(), 3);
+        for n in 1..=3u8 {
+            assert_eq!(cache.get(&Address::new([n; 20])), Some(&user_account(n)));
+        }
+        assert_eq!(cache.metrics().writes, 3);
+    }
+
+    #[test]
+    fn test_account_cache_evicts_least_recently_used() {
+        let mut cache = AccountCacheInner::with_max_entries(2);
+        cache.handle_cache_write(user_account(1)).unwrap();
+        cache.handle_cache_write(user_account(2)).unwrap();
+        assert!(cache.get(&Address::new([1; 20])).is_some());
+
+        cache.handle_cache_write(user_account(3)).unwrap();
+        assert!(cache.get(&Address::new([1; 20])).is_some());
+        assert!(cache.get(&Address::new([2; 20])).is_none());
+        assert!(cache.get(&Address::new([3; 20])).is_some());
+    }
+
+    #[test]
+    fn test_account_cache_check_many() {
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        cache.handle_cache_write(user_account(1)).unwrap();
+        cache.handle_cache_write(user_account(3)).unwrap();
+
+        let addresses = [1u8, 2, 3].map(|n| Address::new([n; 20]));
+        let results = cache.handle_cache_check_many(&addresses);
+        assert_eq!(
+            results,
+            vec![
+                (addresses[0], Some(user_account(1))),
+                (addresses[1], None),
+                (addresses[2], Some(user_account(3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_cache_snapshot_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "lasr_account_cache_snapshot_{}.bin",
+            std::process::id()
+        ));
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        for n in 1..=4u8 {
+            cache.handle_cache_write(user_account(n)).unwrap();
+        }
+        cache.snapshot_to(&path).unwrap();
+        assert!(!path.with_extension("tmp").exists());
+
+        let mut restored = AccountCacheInner::with_max_entries(8);
+        restored.load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.len(), 4);
+        for n in 1..=4u8 {
+            assert_eq!(restored.get(&Address::new([n; 20])), Some(&user_account(n)));
+        }
+    }
+
+    #[test]
+    fn test_account_cache_log_replay() {
+        let path =
+            std::env::temp_dir().join(format!("lasr_account_cache_log_{}.bin", std::process::id()));
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        cache.open_log(&path).unwrap();
+        for n in 1..=4u8 {
+            cache.handle_cache_write(user_account(n)).unwrap();
+        }
+        cache
+            .handle_cache_write_batch((5..=6).map(user_account).collect())
+            .unwrap();
+        cache.remove(&Address::new([2; 20])).unwrap();
+
+        let mut restored = AccountCacheInner::with_max_entries(8);
+        restored.replay_log(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let sorted = |cache: &AccountCacheInner| {
+            let mut accounts = cache.export();
+            accounts.sort_by_key(|account| account.owner_address());
+            accounts
+        };
+        assert_eq!(restored.len(), 5);
+        assert_eq!(sorted(&restored), sorted(&cache));
+        assert!(restored.get(&Address::new([2; 20])).is_none());
+    }
+
+    #[test]
+    fn test_account_cache_metrics() {
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        cache.handle_cache_write(user_account(1)).unwrap();
+        cache.handle_cache_write(user_account(2)).unwrap();
+
+        assert!(cache.get(&Address::new([1; 20])).is_some());
+        assert!(cache.get(&Address::new([2; 20])).is_some());
+        assert!(cache.get(&Address::new([3; 20])).is_none());
+        cache.remove(&Address::new([1; 20])).unwrap();
+        assert!(cache.get(&Address::new([1; 20])).is_none());
+
+        assert_eq!(
+            cache.metrics(),
+            CacheMetricsSnapshot {
+                hits: 2,
+                misses: 2,
+                writes: 2,
+                removals: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_account_cache_export() {
+        let mut cache = AccountCacheInner::with_max_entries(8);
+        for n in 1..=4u8 {
+            cache.handle_cache_write(user_account(n)).unwrap();
+        }
+
+        let mut addresses: Vec<Address> = cache.iter().map(|(address, _)| *address).collect();
+        addresses.sort();
+        assert_eq!(
+            addresses,
+            (1..=4u8).map(|n| Address::new([n; 20])).collect::<Vec<_>>()
+        );
+
+        let mut exported = cache.export();
+        exported.sort_by_key(|account| account.owner_address());
+        assert_eq!(exported, (1..=4).map(user_account).collect::<Vec<_>>());
+        assert_eq!(cache.metrics().hits, 0);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn test_in_memory_account_cache() {
+        let mut cache = AccountCache::in_memory();
+        assert!(cache.get(&Address::new([1; 20])).is_none());
+
+        cache.insert(user_account(1));
+        let program = Account::new(
+            AccountType::Program(Address::new([3; 20])),
+            None,
+            Address::new([2; 20]),
+            None,
+        );
+        cache.insert(program.clone());
+
+        assert_eq!(cache.get(&Address::new([1; 20])), Some(&user_account(1)));
+        assert_eq!(cache.get(&Address::new([3; 20])), Some(&program));
+        assert!(cache.get(&Address::new([2; 20])).is_none());
+    }
+
+    #[test]
+    fn test_account_cache_publishes_events() {
+        let (events, mut subscriber) = tokio::sync::broadcast::channel(16);
+        let mut cache = AccountCacheInner::with_max_entries(1);
+        cache.set_event_sender(events);
+
+        cache.handle_cache_write(user_account(1)).unwrap();
+        cache.handle_cache_write(user_account(1)).unwrap();
+        cache.handle_cache_write(user_account(2)).unwrap();
+        cache.remove(&Address::new([2; 20])).unwrap();
+        cache.remove(&Address::new([2; 20])).unwrap();
+
+        let received: Vec<CacheEvent> = std::iter::from_fn(|| subscriber.try_recv().ok()).collect();
+        assert_eq!(
+            received,
+            vec![
+                CacheEvent::Written(Address::new([1; 20])),
+                CacheEvent::Written(Address::new([1; 20])),
+                CacheEvent::Removed(Address::new([1; 20])),
+                CacheEvent::Written(Address::new([2; 20])),
+                CacheEvent::Removed(Address::new([2; 20])),
+            ]
+        );
+    }
+
+    #[cfg(feature = "mock_storage")]
+    #[tokio::test]
+    async fn test_account_cache_actor_reads_written_account() {
+        use super::AccountCacheActor;
+        use lasr_messages::{AccountCacheMessage, ActorType};
+        use lasr_types::MockPersistenceStore;
+        use ractor::{concurrency::oneshot, Actor};
+
+        let (actor, handle) = Actor::spawn(
+            None,
+            AccountCacheActor::new(),
+            MockPersistenceStore::default(),
+        )
+        .await
+        .unwrap();
+        actor
+            .cast(AccountCacheMessage::Write {
+                account: user_account(1),
+                who: ActorType::Engine,
+                location: "test".to_string(),
+            })
+            .unwrap();
+
+        let (tx, rx) = oneshot();
+        actor
+            .cast(AccountCacheMessage::Read {
+                address: Address::new([1; 20]),
+                tx,
+                who: ActorType::Engine,
+            })
+            .unwrap();
+        assert_eq!(rx.await.unwrap(), Some(user_account(1)));
+
+        actor.stop(None);
+        handle.await.unwrap();
+    }
+
+    #[cfg(feature = "mock_storage")]
+    #[test]
+    fn test_account_cache_restores_configured_snapshot() {
+        use super::AccountCache;
+        use lasr_types::MockPersistenceStore;
+
+        let path = std::env::temp_dir().join(format!(
+            "lasr_account_cache_configured_snapshot_{}.bin",
+            std::process::id()
+        ));
+        let mut cache = AccountCache::new(MockPersistenceStore::<String, Vec<u8>>::default())
+            .with_snapshot_path(path.clone())
+            .unwrap();
+        assert_eq!(cache.inner.len(), 0);
+        for n in 1..=3u8 {
+            cache.inner.handle_cache_write(user_account(n)).unwrap();
+        }
+        cache.snapshot_to(&path).unwrap();
+
+        let mut restored = AccountCache::new(MockPersistenceStore::<String, Vec<u8>>::default())
+            .with_snapshot_path(path.clone())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(restored.inner.len(), 3);
+        for n in 1..=3u8 {
+            assert_eq!(
+                restored.inner.get(&Address::new([n; 20])),
+                Some(&user_account(n))
+            );
+        }
+    }
+
+    #[cfg(feature = "mock_storage")]
+    #[test]
+    fn test_account_cache_log_replays_on_top_of_snapshot() {
+        use super::AccountCache;
+        use lasr_types::MockPersistenceStore;
+
+        let dir = std::env::temp_dir();
+        let snapshot = dir.join(format!(
+            "lasr_account_cache_wal_snapshot_{}.bin",
+            std::process::id()
+        ));
+        let log = dir.join(format!("lasr_account_cache_wal_{}.bin", std::process::id()));
+        let open = || {
+            AccountCache::new(MockPersistenceStore::<String, Vec<u8>>::default())
+                .with_snapshot_path(snapshot.clone())
+                .unwrap()
+                .with_log_path(log.clone())
+                .unwrap()
+        };
+
+        let mut cache = open();
+        cache.inner.handle_cache_write(user_account(1)).unwrap();
+        cache.inner.handle_cache_write(user_account(2)).unwrap();
+        cache.snapshot_to(&snapshot).unwrap();
+        cache.inner.truncate_log().unwrap();
+        assert_eq!(std::fs::metadata(&log).unwrap().len(), 0);
+        cache.inner.handle_cache_write(user_account(3)).unwrap();
+        cache.inner.remove(&Address::new([1; 20])).unwrap();
+        drop(cache);
+
+        let mut restored = open();
+        std::fs::remove_file(&snapshot).unwrap();
+        std::fs::remove_file(&log).unwrap();
+        assert_eq!(restored.inner.len(), 2);
+        assert!(restored.inner.get(&Address::new([1; 20])).is_none());
+        for n in 2..=3u8 {
+            assert_eq!(
+                restored.inner.get(&Address::new([n; 20])),
+                Some(&user_account(n))
+            );
+        }
+    }
+
+    #[cfg(feature = "mock_storage")]
+    #[tokio::test]
+    async fn test_evicted_account_reads_back_latest_state() {
+        use super::AccountCache;
+        use lasr_types::MockPersistenceStore;
+
+        let mut cache = AccountCache {
+            inner: AccountCacheInner::with_max_entries(1),
+            storage: MockPersistenceStore::default(),
+            snapshot_path: None,
+        };
+        let mut latest = user_account(1);
+        cache.inner.handle_cache_write(latest.clone()).unwrap();
+        latest.increment_nonce().unwrap();
+        cache.inner.handle_cache_write(latest.clone()).unwrap();
+        cache.inner.handle_cache_write(user_account(2)).unwrap();
+        cache.persist_evicted().await;
+
+        let address = Address::new([1; 20]);
+        assert!(cache.inner.get(&address).is_none());
+        assert_eq!(cache.read(&address).await, Some(latest));
+    }
+
+    #[cfg(feature = "mock_storage")]
+    #[tokio::test]
+    async fn test_account_cache_actor_applies_write_behind_flood_of_reads() {
+        use super::AccountCacheActor;
+        use lasr_messages::{AccountCacheMessage, ActorType};
+        use lasr_types::MockPersistenceStore;
+        use ractor::{concurrency::oneshot, Actor};
+
+        let (actor, handle) = Actor::spawn(
+            None,
+            AccountCacheActor::new(),
+            MockPersistenceStore::default(),
+        )
+        .await
+        .unwrap();
+        let mut flood = Vec::new();
+        for _ in 0..1_000 {
+            let (tx, rx) = oneshot();
+            actor
+                .cast(AccountCacheMessage::Read {
+                    address: Address::new([2; 20]),
+                    tx,
+                    who: ActorType::Engine,
+                })
+                .unwrap();
+            flood.push(rx);
+        }
+        actor
+            .cast(AccountCacheMessage::Write {
+                account: user_account(1),
+                who: ActorType::Engine,
+                location: "test".to_string(),
+            })
+            .unwrap();
+
+        let (tx, rx) = oneshot();
+        actor
+            .cast(AccountCacheMessage::Read {
+                address: Address::new([1; 20]),
+                tx,
+                who: ActorType::Engine,
+            })
+            .unwrap();
+        assert_eq!(rx.await.unwrap(), Some(user_account(1)));
+        for rx in flood {
+            assert_eq!(rx.await.unwrap(), None);
+        }
+
+        actor.stop(None);
+        handle.await.unwrap();
+    }
+}
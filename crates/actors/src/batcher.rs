@@ -46,7 +46,7 @@ use crate::{
 };
 use lasr_messages::{
     AccountCacheMessage, ActorName, ActorType, BatcherMessage, DaClientMessage, EoMessage,
-    PendingTransactionMessage, SchedulerMessage, SupervisorType,
+    PendingTransactionMessage, RequestId, SchedulerMessage, SupervisorType,
 };
 
 use lasr_contract::create_program_id;
@@ -54,7 +54,7 @@ use lasr_contract::create_program_id;
 use lasr_types::{
     Account, AccountBuilder, AccountType, Address, AddressOrNamespace, ArbitraryData,
     BurnInstruction, ContractLogType, CreateInstruction, Instruction, Metadata, MetadataValue,
-    Namespace, Outputs, PersistenceStore, ProgramAccount, ProgramUpdate, TokenDistribution,
+    Namespace, Outputs, PersistenceStore, ProgramAccount, ProgramUpdate, Token, TokenDistribution,
     TokenOrProgramUpdate, TokenUpdate, Transaction, TransactionType, TransferInstruction,
     UpdateInstruction, U256,
 };
@@ -64,7 +64,7 @@ use derive_builder::Builder;
 pub const VERSE_ADDR: Address = Address::verse_addr();
 pub const ETH_ADDR: Address = Address::eth_addr();
 // const BATCH_INTERVAL: u64 = 180;
-pub type PendingReceivers = FuturesUnordered<OneshotReceiver<(String, BlobVerificationProof)>>;
+pub type PendingReceivers = FuturesUnordered<OneshotReceiver<(RequestId, BlobVerificationProof)>>;
 
 #[derive(Debug, Error)]
 pub enum BatcherError {
@@ -128,6 +128,21 @@ pub struct AccountValue {
     pub account: Account,
 }
 
+impl AccountValue {
+    /// Decodes a value persisted with either the current account layout or
+    /// one of those used before addresses were encoded as raw bytes, with or
+    /// without the account hash. The older layouts are tried first: its length
+    /// prefixed hex addresses cannot be mistaken for raw address bytes,
+    /// while reading it as the current layout could silently misread it.
+    /// Legacy values are rewritten in the current layout the next time the
+    /// account is persisted.
+    pub fn decode(bytes: &[u8]) -> bincode::Result<Self> {
+        Account::decode_legacy_bincode(bytes)
+            .or_else(|_| bincode::deserialize::<Account>(bytes))
+            .map(|account| AccountValue { account })
+    }
+}
+
 // // Structure for persistence store `Transaction` values
 // #[derive(Debug, Hash, Clone, Serialize, Deserialize, PartialEq, Eq)]
 // pub struct TransactionValue {
@@ -320,12 +335,12 @@ pub struct Batcher {
     parent: Batch,
     children: VecDeque<Batch>,
     cache: HashMap<String /* request_id*/, Batch>,
-    receiver_thread_tx: Sender<OneshotReceiver<(String, BlobVerificationProof)>>,
+    receiver_thread_tx: Sender<OneshotReceiver<(RequestId, BlobVerificationProof)>>,
 }
 
 impl Batcher {
     pub async fn run_receivers(
-        mut receiver: Receiver<OneshotReceiver<(String, BlobVerificationProof)>>,
+        mut receiver: Receiver<OneshotReceiver<(RequestId, BlobVerificationProof)>>,
     ) -> Result<(), BatcherError> {
         let mut pending_receivers: PendingReceivers = FuturesUnordered::new();
         println!("in run receivers");
@@ -343,7 +358,7 @@ impl Batcher {
                         tracing::info!("batcher received blob verification proof");
                         if let Some(batcher) = get_actor_ref::<BatcherMessage, BatcherError>(ActorType::Batcher) {
                             let message = BatcherMessage::BlobVerificationProof {
-                                request_id,
+                                request_id: request_id.to_string(),
                                 proof
                             };
 
@@ -358,7 +373,7 @@ impl Batcher {
     }
 
     pub fn new(
-        receiver_thread_tx: Sender<OneshotReceiver<(String, BlobVerificationProof)>>,
+        receiver_thread_tx: Sender<OneshotReceiver<(RequestId, BlobVerificationProof)>>,
     ) -> Self {
         Self {
             parent: Batch::new(),
@@ -446,6 +461,12 @@ impl Batcher {
         batcher: Arc<Mutex<Batcher>>,
         transaction: Transaction,
     ) -> Result<(), BatcherError> {
+        if !transaction.transaction_type().is_bridge_in() && transaction.to() != transaction.from()
+        {
+            let (batch_buffer, token) = Batcher::apply_send_transfer(&transaction).await?;
+            return Batcher::commit_send(batcher, transaction, batch_buffer, token).await;
+        }
+
         let mut batch_buffer = HashMap::new();
         tracing::warn!(
             "checking account cache for account associated with address {:?} to add transaction: {:?}",
@@ -455,13 +476,21 @@ impl Batcher {
         let mut from_account = get_account(transaction.from(), ActorType::Batcher).await;
         let (from_account, token) = if let Some(mut account) = from_account {
             tracing::warn!("found account, token pair");
-            account.increment_nonce();
+            if !transaction.transaction_type().is_bridge_in() {
+                Batcher::charge_fee(&mut account, &transaction)?;
+            }
             let token = account
                 .apply_send_transaction(transaction.clone(), None)
                 .map_err(|e| BatcherError::FailedTransaction {
                     msg: e.to_string(),
                     txn: Box::new(transaction.clone()),
                 })?;
+            account
+                .increment_nonce()
+                .map_err(|e| BatcherError::FailedTransaction {
+                    msg: e.to_string(),
+                    txn: Box::new(transaction.clone()),
+                })?;
             batch_buffer.insert(transaction.from().to_full_string(), account.clone());
             (account, token)
         } else {
@@ -695,6 +724,102 @@ impl Batcher {
             batch_buffer.insert(transaction.to().to_full_string(), to_account.clone());
         }
 
+        Batcher::commit_send(batcher, transaction, batch_buffer, token).await
+    }
+
+    /// Applies a `Send` between two different accounts with
+    /// [`Account::transfer`], so the debit and credit are validated together
+    /// and either both happen or neither does. The recipient token picks up
+    /// the program account's metadata and data. Returns the updated accounts
+    /// keyed by address and the sender's token for the program.
+    async fn apply_send_transfer(
+        transaction: &Transaction,
+    ) -> Result<(HashMap<String, Account>, Token), BatcherError> {
+        let failed = |msg: String| BatcherError::FailedTransaction {
+            msg,
+            txn: Box::new(transaction.clone()),
+        };
+        let program_id = transaction.program_id();
+        let mut from_account = get_account(transaction.from(), ActorType::Batcher)
+            .await
+            .ok_or_else(|| failed("sender account does not exist".to_string()))?;
+        if !from_account.has_program(&program_id) {
+            return Err(failed(format!(
+                "sender does not hold program {}",
+                program_id.to_full_string()
+            )));
+        }
+        let program_account = get_account(program_id, ActorType::Batcher).await;
+        if program_account.is_none() && program_id != ETH_ADDR && program_id != VERSE_ADDR {
+            return Err(failed(format!(
+                "program account {} does not exist",
+                program_id.to_full_string()
+            )));
+        }
+        let mut to_account = match get_account(transaction.to(), ActorType::Batcher).await {
+            Some(account) => account,
+            None => {
+                tracing::warn!(
+                    "first transaction send to account {} building account",
+                    transaction.to().to_full_string()
+                );
+                Account::new(AccountType::User, None, transaction.to(), None)
+            }
+        };
+
+        Batcher::charge_fee(&mut from_account, transaction)?;
+        from_account
+            .transfer(
+                &program_id,
+                &mut to_account,
+                &program_id,
+                transaction.value(),
+            )
+            .map_err(|e| failed(e.to_string()))?;
+        if let Some(program_account) = &program_account {
+            if let Some(token) = to_account.programs_mut().get_mut(&program_id) {
+                token
+                    .metadata_mut()
+                    .extend(program_account.program_account_metadata().inner().clone());
+                token
+                    .data_mut()
+                    .extend(program_account.program_account_data().inner().clone());
+            }
+        }
+        from_account
+            .increment_nonce()
+            .map_err(|e| failed(e.to_string()))?;
+
+        let token = from_account.programs()[&program_id].clone();
+        let mut batch_buffer = HashMap::new();
+        batch_buffer.insert(transaction.from().to_full_string(), from_account);
+        batch_buffer.insert(transaction.to().to_full_string(), to_account);
+        Ok((batch_buffer, token))
+    }
+
+    /// Debits the transaction fee from `account`, the signer, in the
+    /// transaction's program. A zero fee is not charged, so accounts that do
+    /// not hold the program can still be sent fee-less transactions.
+    fn charge_fee(account: &mut Account, transaction: &Transaction) -> Result<(), BatcherError> {
+        if transaction.fee().is_zero() {
+            return Ok(());
+        }
+        account
+            .deduct_fee(&transaction.program_id(), transaction.fee())
+            .map_err(|e| BatcherError::FailedTransaction {
+                msg: e.to_string(),
+                txn: Box::new(transaction.clone()),
+            })
+    }
+
+    /// Batches the accounts changed by an applied `Send` and tells the
+    /// scheduler and pending transactions actor that it was applied.
+    async fn commit_send(
+        batcher: Arc<Mutex<Batcher>>,
+        transaction: Transaction,
+        batch_buffer: HashMap<String, Account>,
+        token: Token,
+    ) -> Result<(), BatcherError> {
         for (_, account) in batch_buffer {
             tracing::info!("adding account to batch");
             Batcher::add_account_to_batch(
@@ -1417,7 +1542,12 @@ impl Batcher {
                 txn: Box::new(transaction.clone()),
             })?;
 
-            account.increment_nonce();
+            account
+                .increment_nonce()
+                .map_err(|e| BatcherError::FailedTransaction {
+                    msg: e.to_string(),
+                    txn: Box::new(transaction.clone()),
+                })?;
 
             Batcher::add_account_to_batch(
                 &batcher,
@@ -1509,7 +1639,24 @@ impl Batcher {
                 txn: Box::new(transaction.clone()),
             })?;
 
-        caller.increment_nonce();
+        caller
+            .increment_nonce()
+            .map_err(|e| BatcherError::FailedTransaction {
+                msg: e.to_string(),
+                txn: Box::new(transaction.clone()),
+            })?;
+        if transaction.from() == transaction.to() {
+            Batcher::charge_fee(&mut caller, &transaction)?;
+        } else if !transaction.fee().is_zero() {
+            let mut signer = get_account(transaction.from(), ActorType::Batcher)
+                .await
+                .ok_or(BatcherError::FailedTransaction {
+                    msg: "signer account does not exist".to_string(),
+                    txn: Box::new(transaction.clone()),
+                })?;
+            Batcher::charge_fee(&mut signer, &transaction)?;
+            Batcher::add_account_to_batch_buffer(&mut batch_buffer, signer);
+        }
 
         Batcher::add_account_to_batch(
             &batcher,
@@ -1782,7 +1929,7 @@ impl Batcher {
                         Err(e) => Err(Box::new(e) as Box<dyn std::error::Error>),
                     };
 
-                    let blob_response = handle_actor_response(rx, handler)
+                    let blob_response = handle_actor_response(rx, ActorType::DaClient, handler)
                         .await
                         .map_err(|e| BatcherError::Custom(e.to_string()))?;
 
@@ -1818,6 +1965,13 @@ impl Batcher {
     }
 
     async fn request_blob_validation(batcher: Arc<Mutex<Batcher>>, request_id: String) {
+        let request_id: RequestId = match request_id.parse() {
+            Ok(request_id) => request_id,
+            Err(err) => {
+                tracing::error!("DA client returned invalid request id {request_id:?}: {err}");
+                return;
+            }
+        };
         let (tx, rx) = oneshot();
         {
             let guard = batcher.lock().await;
@@ -1906,6 +2060,9 @@ impl Actor for BatcherActor {
     ) -> Result<(), ActorProcessingErr> {
         let batcher_ptr = Arc::clone(state);
         match message {
+            BatcherMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Batcher);
+            }
             BatcherMessage::GetNextBatch { storage_ref } => {
                 Batcher::handle_next_batch_request(batcher_ptr, storage_ref).await?;
                 // let mut guard = self.future_pool.lock().await;
@@ -2091,7 +2248,10 @@ mod batcher_tests {
     use anyhow::Result;
     use eigenda_client::proof::BlobVerificationProof;
     use futures::{FutureExt, StreamExt};
-    use lasr_types::TransactionType;
+    use lasr_messages::ActorType;
+    use lasr_types::{
+        Account, AccountType, Address, PayloadBuilder, Token, Transaction, TransactionType, U256,
+    };
     use std::sync::Arc;
     use tokio::sync::Mutex;
 
@@ -2109,6 +2269,9 @@ mod batcher_tests {
         async fn handle(&self, message: Self::Msg, state: &mut Self::State) -> Result<()> {
             let batcher_ptr = Arc::clone(state);
             match message {
+                BatcherMessage::Ping { reply } => {
+                    let _ = reply.send(ActorType::Batcher);
+                }
                 BatcherMessage::GetNextBatch { storage_ref } => {
                     let fut = Batcher::handle_next_batch_request(batcher_ptr, storage_ref);
                     let mut guard = self.future_pool.lock().await;
@@ -2207,4 +2370,39 @@ mod batcher_tests {
             interval.tick().await;
         }
     }
+
+    fn send_with_fee(program_id: Address, fee: u64) -> Transaction {
+        PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(0)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id(program_id.inner())
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(1))
+            .nonce(U256::from(0))
+            .fee(U256::from(fee))
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_charge_fee_debits_signer() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([3u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        let mut token = Token::empty(program_id, owner);
+        token.update_balance(U256::from(10), U256::from(0)).unwrap();
+        account.insert_program(&program_id, token).unwrap();
+
+        Batcher::charge_fee(&mut account, &send_with_fee(program_id, 4)).unwrap();
+        assert_eq!(account.balance(&program_id), U256::from(6));
+
+        assert!(Batcher::charge_fee(&mut account, &send_with_fee(program_id, 7)).is_err());
+        assert_eq!(account.balance(&program_id), U256::from(6));
+
+        let mut empty = Account::new(AccountType::User, None, owner, None);
+        Batcher::charge_fee(&mut empty, &send_with_fee(program_id, 0)).unwrap();
+    }
 }
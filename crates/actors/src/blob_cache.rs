@@ -1,9 +1,12 @@
 use async_trait::async_trait;
 use eigenda_client::proof::BlobVerificationProof;
 use eigenda_client::response::BlobResponse;
-use futures::stream::FuturesUnordered;
+use ethereum_types::H256;
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
 use lasr_messages::{ActorName, SupervisorType};
-use lasr_messages::{ActorType, BlobCacheMessage, DaClientMessage};
+use lasr_messages::{ActorType, BlobCacheMessage, DaClientMessage, RequestId, TIMEOUT_DURATION};
 use lasr_types::{Address, Transaction};
 use ractor::ActorRef;
 use ractor::SupervisionEvent;
@@ -12,19 +15,100 @@ use ractor::{
     ActorProcessingErr,
 };
 use ractor::{Actor, ActorCell};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::sync::mpsc::Sender;
 
-use crate::{process_group_changed, Coerce};
+use crate::{process_group_changed, CacheMetrics, CacheMetricsSnapshot, Coerce};
 
+/// Tracks the blobs dispersed to the DA layer until they are validated.
+///
+/// The queue holds at most `capacity` blobs. Writing a new blob into a full
+/// queue first collects the validations that have already resolved, and
+/// fails with [`BlobQueueFull`] if that frees no space, rather than growing
+/// the queue or holding up the actor's mailbox until a validation completes.
+/// Writers are expected to back off and retry a rejected write.
 #[derive(Debug, Default)]
 pub struct PendingBlobCache {
-    //TODO(asmith) create an ergonimical RequestId struct for EigenDa
-    //Blob responses
-    queue: HashMap<String /*request_id*/, (HashSet<Address>, HashSet<Transaction>)>,
-    receivers: FuturesUnordered<OneshotReceiver<(String /*request_id*/, BlobVerificationProof)>>,
+    queue: HashMap<RequestId, (HashSet<Address>, HashSet<Transaction>)>,
+    capacity: usize,
+    receivers: FuturesUnordered<BoxFuture<'static, BlobValidationEvent>>,
+    metrics: CacheMetrics,
+    max_cast_attempts: u32,
+    validation_timeout: Duration,
+    /// Request ids still in the queue whose validation request could not be
+    /// delivered to the DA client.
+    unsent: VecDeque<RequestId>,
+    /// Request ids waiting to be validated together with other blobs under
+    /// the same batch header, along with when the first one was queued.
+    batches: HashMap<H256, (Instant, Vec<RequestId>)>,
+    batch_window: Duration,
+    batch_threshold: usize,
+    poll_interval: Duration,
+    retry_interval: Duration,
+    /// Backed off retries scheduled since every validation request was last
+    /// delivered.
+    retry_attempts: u32,
+    /// Whether a backed off [`BlobCacheMessage::RetryUnsent`] is on its way.
+    retry_scheduled: bool,
+}
+
+/// The default number of attempts made to cast to the DA client before a
+/// blob is set aside for a later retry, used when `DA_CAST_MAX_ATTEMPTS` is
+/// not set.
+pub const DEFAULT_DA_CAST_MAX_ATTEMPTS: u32 = 5;
+
+/// How long to wait for the DA client to validate a blob before it is set
+/// aside for a retry, used when `BLOB_VALIDATION_TIMEOUT_SECS` is not set.
+pub const DEFAULT_BLOB_VALIDATION_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
+/// How long blobs sharing a batch header are held back so they can be
+/// validated together, used when `BLOB_BATCH_WINDOW_MILLIS` is not set.
+pub const DEFAULT_BLOB_BATCH_WINDOW: Duration = Duration::from_millis(500);
+
+/// How many blobs sharing a batch header are collected before they are sent
+/// for validation early, used when `BLOB_BATCH_THRESHOLD` is not set.
+pub const DEFAULT_BLOB_BATCH_THRESHOLD: usize = 16;
+
+/// How many blobs may wait for validation at once, used when
+/// `BLOB_QUEUE_CAPACITY` is not set.
+pub const DEFAULT_BLOB_QUEUE_CAPACITY: usize = 4096;
+
+/// How often resolved validations are collected, used when
+/// `BLOB_POLL_INTERVAL_MILLIS` is not set.
+pub const DEFAULT_BLOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often undelivered validation requests are retried, used when
+/// `BLOB_RETRY_INTERVAL_SECS` is not set.
+pub const DEFAULT_BLOB_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The outcome of waiting on the DA client to validate a queued blob.
+#[derive(Debug)]
+pub enum BlobValidationEvent {
+    Validated {
+        request_id: RequestId,
+        proof: BlobVerificationProof,
+    },
+    /// The DA client did not respond within the validation timeout.
+    BlobTimeout(RequestId),
+    /// The DA client dropped the reply channel without responding.
+    Dropped(RequestId),
+}
+
+/// Waits on `rx` for at most `timeout`, tagging the outcome with `request_id`
+/// so that a timed out validation can still be traced back to its blob.
+async fn await_validation(
+    request_id: RequestId,
+    rx: OneshotReceiver<(RequestId, BlobVerificationProof)>,
+    timeout: Duration,
+) -> BlobValidationEvent {
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok((_, proof))) => BlobValidationEvent::Validated { request_id, proof },
+        Ok(Err(_)) => BlobValidationEvent::Dropped(request_id),
+        Err(_) => BlobValidationEvent::BlobTimeout(request_id),
+    }
 }
 
 #[derive(Debug, Clone, Error)]
@@ -36,11 +120,143 @@ impl Display for PendingBlobError {
     }
 }
 
+/// A blob could not be queued because the queue is at capacity. The write
+/// may be retried once pending validations have freed space.
+#[derive(Debug, Clone, Error)]
+#[error("blob queue is full with {0} blobs awaiting validation")]
+pub struct BlobQueueFull(pub usize);
+
 impl PendingBlobCache {
     pub fn new() -> Self {
+        let max_cast_attempts = std::env::var("DA_CAST_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| DEFAULT_DA_CAST_MAX_ATTEMPTS.to_string())
+            .parse::<u32>()
+            .unwrap_or(DEFAULT_DA_CAST_MAX_ATTEMPTS);
+        Self::with_max_cast_attempts(max_cast_attempts)
+    }
+
+    /// Creates a cache that attempts each cast to the DA client at most
+    /// `max_cast_attempts` times, backing off between attempts, before
+    /// leaving the blob to the periodic retry.
+    pub fn with_max_cast_attempts(max_cast_attempts: u32) -> Self {
         let queue = HashMap::new();
         let receivers = FuturesUnordered::new();
-        Self { queue, receivers }
+        Self {
+            queue,
+            capacity: std::env::var("BLOB_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|capacity| capacity.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_BLOB_QUEUE_CAPACITY)
+                .max(1),
+            receivers,
+            metrics: CacheMetrics::new(),
+            max_cast_attempts: max_cast_attempts.max(1),
+            validation_timeout: std::env::var("BLOB_VALIDATION_TIMEOUT_SECS")
+                .ok()
+                .and_then(|secs| secs.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_BLOB_VALIDATION_TIMEOUT),
+            unsent: VecDeque::new(),
+            batches: HashMap::new(),
+            batch_window: std::env::var("BLOB_BATCH_WINDOW_MILLIS")
+                .ok()
+                .and_then(|millis| millis.parse::<u64>().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_BLOB_BATCH_WINDOW),
+            batch_threshold: std::env::var("BLOB_BATCH_THRESHOLD")
+                .ok()
+                .and_then(|threshold| threshold.parse::<usize>().ok())
+                .unwrap_or(DEFAULT_BLOB_BATCH_THRESHOLD)
+                .max(1),
+            poll_interval: std::env::var("BLOB_POLL_INTERVAL_MILLIS")
+                .ok()
+                .and_then(|millis| millis.parse::<u64>().ok())
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_BLOB_POLL_INTERVAL),
+            retry_interval: std::env::var("BLOB_RETRY_INTERVAL_SECS")
+                .ok()
+                .and_then(|secs| secs.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_BLOB_RETRY_INTERVAL),
+            retry_attempts: 0,
+            retry_scheduled: false,
+        }
+    }
+
+    /// Caps the queue at `capacity` blobs and allocates room for that many
+    /// up front, so a queue expected to fill doesn't reallocate as it grows.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self.queue
+            .reserve(self.capacity.saturating_sub(self.queue.len()));
+        self
+    }
+
+    /// Sets how long to wait for the DA client to validate a blob.
+    pub fn with_validation_timeout(mut self, validation_timeout: Duration) -> Self {
+        self.validation_timeout = validation_timeout;
+        self
+    }
+
+    /// Sets how long blobs sharing a batch header are held back, and how
+    /// many of them may collect before they are validated early.
+    pub fn with_batching(mut self, batch_window: Duration, batch_threshold: usize) -> Self {
+        self.batch_window = batch_window;
+        self.batch_threshold = batch_threshold.max(1);
+        self
+    }
+
+    /// Waits for the next pending validation to resolve. Blobs whose
+    /// validation timed out or was dropped stay queued and are set aside for
+    /// [`PendingBlobCache::retry_unsent`]. Returns `None` when no validation
+    /// is pending.
+    async fn next_validation(&mut self) -> Option<BlobValidationEvent> {
+        let event = self.receivers.next().await?;
+        match &event {
+            BlobValidationEvent::Validated { .. } => {}
+            BlobValidationEvent::BlobTimeout(request_id)
+            | BlobValidationEvent::Dropped(request_id) => {
+                tracing::warn!(
+                    request_id = %request_id,
+                    "validation of blob {request_id} did not complete: {event:?}"
+                );
+                self.unsent.push_back(request_id.clone());
+            }
+        }
+        Some(event)
+    }
+
+    /// Makes room for one more blob by removing the blobs whose validations
+    /// have already completed. Fails without waiting if the queue is still
+    /// full.
+    fn reserve_slot(&mut self) -> Result<(), BlobQueueFull> {
+        if self.queue.len() >= self.capacity {
+            self.poll_validations();
+        }
+        if self.queue.len() >= self.capacity {
+            tracing::warn!(
+                "blob queue is full with {} blobs, rejecting write",
+                self.queue.len()
+            );
+            return Err(BlobQueueFull(self.queue.len()));
+        }
+        Ok(())
+    }
+
+    /// Handles every validation that has already resolved without waiting
+    /// on the rest, removing validated blobs from the queue.
+    fn poll_validations(&mut self) {
+        while let Some(Some(event)) = self.next_validation().now_or_never() {
+            if let BlobValidationEvent::Validated { request_id, .. } = event {
+                if self.queue.remove(&request_id).is_some() {
+                    self.metrics.record_removal();
+                }
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> CacheMetricsSnapshot {
+        self.metrics.snapshot()
     }
 
     #[allow(unused)]
@@ -49,35 +265,192 @@ impl PendingBlobCache {
         response: BlobResponse,
         proof: BlobVerificationProof,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.queue.remove(&response.request_id());
+        let request_id: RequestId = response.request_id().parse()?;
+        if self.queue.remove(&request_id).is_some() {
+            self.metrics.record_hit();
+            self.metrics.record_removal();
+        } else {
+            self.metrics.record_miss();
+        }
+        Ok(())
+    }
+
+    /// Tracks the accounts and transactions of the blob with `request_id`.
+    /// Blobs are keyed by request id, so blobs touching the same accounts
+    /// are tracked separately; only a rewrite of the same blob replaces its
+    /// entry.
+    fn enqueue(
+        &mut self,
+        request_id: RequestId,
+        accounts: HashSet<Address>,
+        transactions: HashSet<Transaction>,
+    ) -> Result<(), BlobQueueFull> {
+        if let Some(entry) = self.queue.get_mut(&request_id) {
+            *entry = (accounts, transactions);
+        } else {
+            self.reserve_slot()?;
+            self.queue.insert(request_id, (accounts, transactions));
+        }
         Ok(())
     }
 
-    #[allow(unused)]
     fn handle_queue_write(
         &mut self,
         response: BlobResponse,
         accounts: HashSet<Address>,
         transactions: HashSet<Transaction>,
+        batch_header_hash: Option<H256>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(entry) = self.queue.get_mut(&response.request_id()) {
-            *entry = (accounts, transactions);
-        } else {
-            self.queue
-                .insert(response.request_id(), (accounts, transactions));
+        let request_id: RequestId = response.request_id().parse()?;
+        self.enqueue(request_id.clone(), accounts, transactions)?;
+        self.metrics.record_write();
+        match (Self::da_actor(), batch_header_hash) {
+            (Ok(da_actor), Some(batch_header_hash)) => {
+                self.batch_validation(&da_actor, batch_header_hash, request_id)
+            }
+            (Ok(da_actor), None) => self.request_validation(&da_actor, request_id),
+            (Err(e), _) => {
+                tracing::warn!(
+                    request_id = %request_id,
+                    "DA client unavailable, deferring validation of blob {request_id}: {e}"
+                );
+                self.unsent.push_back(request_id);
+            }
         }
+
+        Ok(())
+    }
+
+    /// Holds `request_id` back to be validated with the other blobs under
+    /// `batch_header_hash`, sending the whole group once it reaches the batch
+    /// threshold.
+    fn batch_validation(
+        &mut self,
+        da_actor: &ActorRef<DaClientMessage>,
+        batch_header_hash: H256,
+        request_id: RequestId,
+    ) {
+        let (_, request_ids) = self
+            .batches
+            .entry(batch_header_hash)
+            .or_insert_with(|| (Instant::now(), Vec::new()));
+        request_ids.push(request_id);
+        if request_ids.len() >= self.batch_threshold {
+            if let Some((_, request_ids)) = self.batches.remove(&batch_header_hash) {
+                self.request_batch_validation(da_actor, batch_header_hash, request_ids);
+            }
+        }
+    }
+
+    /// Sends every held back batch whose window has elapsed to the DA client.
+    fn flush_expired_batches(&mut self, da_actor: &ActorRef<DaClientMessage>) {
+        let expired: Vec<H256> = self
+            .batches
+            .iter()
+            .filter(|(_, (started, _))| started.elapsed() >= self.batch_window)
+            .map(|(batch_header_hash, _)| *batch_header_hash)
+            .collect();
+        for batch_header_hash in expired {
+            if let Some((_, request_ids)) = self.batches.remove(&batch_header_hash) {
+                self.request_batch_validation(da_actor, batch_header_hash, request_ids);
+            }
+        }
+    }
+
+    /// Retries every validation request that could not be delivered to the
+    /// DA client.
+    fn retry_unsent(&mut self, da_actor: &ActorRef<DaClientMessage>) {
+        for request_id in std::mem::take(&mut self.unsent) {
+            if self.queue.contains_key(&request_id) {
+                self.request_validation(da_actor, request_id);
+            }
+        }
+    }
+
+    /// Returns how long to wait before retrying the undelivered validation
+    /// requests. The delay starts at `TIMEOUT_DURATION` and doubles with
+    /// every retry, until `max_cast_attempts` casts have been made and the
+    /// requests are left to the periodic retry. Returns `None` if nothing is
+    /// undelivered or a retry is already scheduled.
+    fn schedule_retry(&mut self) -> Option<Duration> {
+        if self.unsent.is_empty() {
+            self.retry_attempts = 0;
+            return None;
+        }
+        if self.retry_scheduled || self.retry_attempts + 1 >= self.max_cast_attempts {
+            return None;
+        }
+        let delay = TIMEOUT_DURATION * 2u32.pow(self.retry_attempts);
+        self.retry_attempts += 1;
+        self.retry_scheduled = true;
+        Some(delay)
+    }
+
+    fn da_actor() -> Result<ActorRef<DaClientMessage>, Box<dyn std::error::Error>> {
+        Ok(ractor::registry::where_is(ActorType::DaClient.to_string())
+            .ok_or(Box::new(PendingBlobError) as Box<dyn std::error::Error>)?
+            .into())
+    }
+
+    /// Asks the DA client to validate the blob for `request_id`. If the cast
+    /// fails the request id is set aside for
+    /// [`PendingBlobCache::retry_unsent`] so the blob isn't lost.
+    fn request_validation(&mut self, da_actor: &ActorRef<DaClientMessage>, request_id: RequestId) {
         let (tx, rx) = oneshot();
-        self.receivers.push(rx);
-        let da_actor: ActorRef<DaClientMessage> =
-            ractor::registry::where_is(ActorType::DaClient.to_string())
-                .ok_or(Box::new(PendingBlobError) as Box<dyn std::error::Error>)?
-                .into();
-        da_actor.cast(DaClientMessage::ValidateBlob {
-            request_id: response.request_id(),
+        let message = DaClientMessage::ValidateBlob {
+            request_id: request_id.clone(),
             tx,
-        })?;
+        };
+        match da_actor.cast(message) {
+            Ok(()) => self
+                .receivers
+                .push(await_validation(request_id, rx, self.validation_timeout).boxed()),
+            Err(e) => {
+                tracing::warn!(
+                    request_id = %request_id,
+                    "failed to request validation of blob {request_id}: {e:?}"
+                );
+                self.unsent.push_back(request_id);
+            }
+        }
+    }
 
-        Ok(())
+    /// Asks the DA client to validate every blob in `request_ids` with a
+    /// single message. If the cast fails the request ids are set aside for
+    /// [`PendingBlobCache::retry_unsent`], which retries them one by one.
+    fn request_batch_validation(
+        &mut self,
+        da_actor: &ActorRef<DaClientMessage>,
+        batch_header_hash: H256,
+        request_ids: Vec<RequestId>,
+    ) {
+        let (blobs, receivers): (Vec<_>, Vec<_>) = request_ids
+            .iter()
+            .map(|request_id| {
+                let (tx, rx) = oneshot();
+                ((request_id.clone(), tx), (request_id.clone(), rx))
+            })
+            .unzip();
+        let message = DaClientMessage::ValidateBlobBatch {
+            batch_header_hash,
+            blobs,
+        };
+        match da_actor.cast(message) {
+            Ok(()) => {
+                for (request_id, rx) in receivers {
+                    self.receivers
+                        .push(await_validation(request_id, rx, self.validation_timeout).boxed());
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    batch_header_hash = ?batch_header_hash,
+                    "failed to request validation of {} blobs under batch header {batch_header_hash:?}: {e:?}",
+                    request_ids.len(),
+                );
+                self.unsent.extend(request_ids);
+            }
+        }
     }
 }
 
@@ -103,18 +476,76 @@ impl Actor for BlobCacheActor {
 
     async fn pre_start(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         _: (),
     ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(PendingBlobCache::new())
+        let cache = PendingBlobCache::new();
+        // Held back batches are flushed on a timer, so a batch that never
+        // reaches the threshold is still validated once its window is up.
+        myself.send_interval(cache.batch_window.max(Duration::from_millis(10)), || {
+            BlobCacheMessage::FlushBatches
+        });
+        myself.send_interval(cache.poll_interval.max(Duration::from_millis(10)), || {
+            BlobCacheMessage::PollValidations
+        });
+        myself.send_interval(cache.retry_interval.max(Duration::from_millis(10)), || {
+            BlobCacheMessage::RetryUnsent
+        });
+        Ok(cache)
     }
 
     async fn handle(
         &self,
-        _myself: ActorRef<Self::Msg>,
-        _message: Self::Msg,
-        _state: &mut Self::State,
+        myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
+        match message {
+            BlobCacheMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::BlobCache);
+            }
+            BlobCacheMessage::Cache {
+                blob_response,
+                accounts,
+                transactions,
+                batch_header_hash,
+            } => {
+                state
+                    .handle_queue_write(blob_response, accounts, transactions, batch_header_hash)
+                    .typecast()
+                    .log_err(|e| format!("failed to queue blob for validation: {e}"));
+            }
+            BlobCacheMessage::FlushBatches if !state.batches.is_empty() => {
+                let da_actor = PendingBlobCache::da_actor()
+                    .typecast()
+                    .log_err(|e| e.to_string());
+                if let Some(da_actor) = da_actor {
+                    state.flush_expired_batches(&da_actor);
+                }
+            }
+            BlobCacheMessage::PollValidations => state.poll_validations(),
+            BlobCacheMessage::RetryUnsent => {
+                state.retry_scheduled = false;
+                if !state.unsent.is_empty() {
+                    // Resolved on every retry so a restarted DA client is
+                    // picked up.
+                    let da_actor = PendingBlobCache::da_actor()
+                        .typecast()
+                        .log_err(|e| e.to_string());
+                    if let Some(da_actor) = da_actor {
+                        state.retry_unsent(&da_actor);
+                    }
+                }
+            }
+            BlobCacheMessage::Drain => {
+                tracing::warn!("BlobCache drained, stopping");
+                myself.stop(Some("drained".to_string()));
+            }
+            _ => {}
+        }
+        if let Some(delay) = state.schedule_retry() {
+            myself.send_after(delay, || BlobCacheMessage::RetryUnsent);
+        }
         Ok(())
     }
 }
@@ -185,3 +616,313 @@ impl Actor for BlobCacheSupervisor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod blob_cache_tests {
+    use super::{await_validation, BlobQueueFull, BlobValidationEvent, PendingBlobCache};
+    use async_trait::async_trait;
+    use ethereum_types::H256;
+    use futures::FutureExt;
+    use lasr_messages::{DaClientMessage, RequestId, TIMEOUT_DURATION};
+    use lasr_types::Address;
+    use ractor::{concurrency::oneshot, Actor, ActorProcessingErr, ActorRef};
+    use std::collections::HashSet;
+    use std::time::Duration;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+    struct StubDaClient;
+
+    #[async_trait]
+    impl Actor for StubDaClient {
+        type Msg = DaClientMessage;
+        type State = ();
+        type Arguments = ();
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            _: (),
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(())
+        }
+    }
+
+    /// A DA client stub that has already stopped, so every cast fails.
+    async fn stopped_da_client() -> ActorRef<DaClientMessage> {
+        let (actor, handle) = Actor::spawn(None, StubDaClient, ()).await.unwrap();
+        actor.stop(None);
+        handle.await.unwrap();
+        actor
+    }
+
+    /// A DA client stub that reports the request ids of every validation
+    /// message it receives, one report per message.
+    struct RecordingDaClient;
+
+    #[async_trait]
+    impl Actor for RecordingDaClient {
+        type Msg = DaClientMessage;
+        type State = UnboundedSender<Vec<RequestId>>;
+        type Arguments = UnboundedSender<Vec<RequestId>>;
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            calls: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(calls)
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            message: Self::Msg,
+            calls: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            match message {
+                DaClientMessage::ValidateBlob { request_id, .. } => {
+                    calls.send(vec![request_id])?;
+                }
+                DaClientMessage::ValidateBlobBatch { blobs, .. } => {
+                    calls.send(
+                        blobs
+                            .into_iter()
+                            .map(|(request_id, _)| request_id)
+                            .collect(),
+                    )?;
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_back_off_until_attempts_run_out() {
+        let stopped = stopped_da_client().await;
+        let mut cache = PendingBlobCache::with_max_cast_attempts(3);
+        let request_id: RequestId = "request".parse().unwrap();
+        cache
+            .queue
+            .insert(request_id.clone(), (HashSet::new(), HashSet::new()));
+
+        cache.request_validation(&stopped, request_id.clone());
+        assert_eq!(cache.schedule_retry(), Some(TIMEOUT_DURATION));
+        assert_eq!(cache.schedule_retry(), None);
+
+        cache.retry_scheduled = false;
+        cache.retry_unsent(&stopped);
+        assert_eq!(cache.schedule_retry(), Some(TIMEOUT_DURATION * 2));
+
+        cache.retry_scheduled = false;
+        cache.retry_unsent(&stopped);
+        assert_eq!(cache.schedule_retry(), None);
+        assert_eq!(cache.unsent, vec![request_id.clone()]);
+
+        let (calls_tx, mut calls_rx) = unbounded_channel();
+        let (restarted, handle) = Actor::spawn(None, RecordingDaClient, calls_tx)
+            .await
+            .unwrap();
+        cache.retry_unsent(&restarted);
+        assert_eq!(cache.schedule_retry(), None);
+        assert_eq!(cache.retry_attempts, 0);
+        assert_eq!(cache.receivers.len(), 1);
+
+        assert_eq!(calls_rx.recv().await, Some(vec![request_id]));
+        restarted.stop(None);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_validation_request_is_requeued() {
+        let da_actor = stopped_da_client().await;
+        let mut cache = PendingBlobCache::with_max_cast_attempts(2);
+        let request_id: RequestId = "request".parse().unwrap();
+        cache
+            .queue
+            .insert(request_id.clone(), (HashSet::new(), HashSet::new()));
+
+        cache.request_validation(&da_actor, request_id.clone());
+        assert!(cache.receivers.is_empty());
+        assert_eq!(cache.unsent, vec![request_id.clone()]);
+        assert!(cache.queue.contains_key(&request_id));
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_validation_times_out() {
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1)
+            .with_validation_timeout(Duration::from_millis(10));
+        let request_id: RequestId = "request".parse().unwrap();
+        let (_tx, rx) = oneshot();
+        cache
+            .receivers
+            .push(await_validation(request_id.clone(), rx, cache.validation_timeout).boxed());
+
+        assert!(matches!(
+            cache.next_validation().await,
+            Some(BlobValidationEvent::BlobTimeout(id)) if id == request_id
+        ));
+        assert_eq!(cache.unsent, vec![request_id]);
+        assert!(cache.next_validation().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_poll_removes_validated_blobs_without_waiting() {
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1);
+        let validated: RequestId = "validated".parse().unwrap();
+        let pending: RequestId = "pending".parse().unwrap();
+        for request_id in [&validated, &pending] {
+            cache
+                .queue
+                .insert(request_id.clone(), (HashSet::new(), HashSet::new()));
+        }
+        let (validated_tx, validated_rx) = oneshot();
+        let (_pending_tx, pending_rx) = oneshot();
+        cache.receivers.push(
+            await_validation(validated.clone(), validated_rx, cache.validation_timeout).boxed(),
+        );
+        cache
+            .receivers
+            .push(await_validation(pending.clone(), pending_rx, cache.validation_timeout).boxed());
+        validated_tx
+            .send((validated.clone(), Default::default()))
+            .unwrap();
+
+        cache.poll_validations();
+        assert!(!cache.queue.contains_key(&validated));
+        assert!(cache.queue.contains_key(&pending));
+        assert_eq!(cache.receivers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_unsent_resends_queued_blobs() {
+        let (calls_tx, mut calls_rx) = unbounded_channel();
+        let (da_actor, handle) = Actor::spawn(None, RecordingDaClient, calls_tx)
+            .await
+            .unwrap();
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1);
+        let queued: RequestId = "queued".parse().unwrap();
+        let removed: RequestId = "removed".parse().unwrap();
+        cache
+            .queue
+            .insert(queued.clone(), (HashSet::new(), HashSet::new()));
+        cache.unsent.extend([queued.clone(), removed]);
+
+        cache.retry_unsent(&da_actor);
+        assert!(cache.unsent.is_empty());
+        assert_eq!(cache.receivers.len(), 1);
+
+        assert_eq!(calls_rx.recv().await, Some(vec![queued]));
+        da_actor.stop(None);
+        handle.await.unwrap();
+        assert_eq!(calls_rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_write_without_waiting() {
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1).with_capacity(1);
+        assert!(cache.reserve_slot().is_ok());
+
+        let validated: RequestId = "validated".parse().unwrap();
+        cache
+            .queue
+            .insert(validated.clone(), (HashSet::new(), HashSet::new()));
+        let (validated_tx, validated_rx) = oneshot();
+        cache.receivers.push(
+            await_validation(validated.clone(), validated_rx, cache.validation_timeout).boxed(),
+        );
+        validated_tx
+            .send((validated.clone(), Default::default()))
+            .unwrap();
+        assert!(cache.reserve_slot().is_ok());
+        assert!(cache.queue.is_empty());
+
+        let pending: RequestId = "pending".parse().unwrap();
+        cache
+            .queue
+            .insert(pending.clone(), (HashSet::new(), HashSet::new()));
+        let (_pending_tx, pending_rx) = oneshot();
+        cache
+            .receivers
+            .push(await_validation(pending.clone(), pending_rx, cache.validation_timeout).boxed());
+        assert!(matches!(cache.reserve_slot(), Err(BlobQueueFull(1))));
+        assert_eq!(cache.receivers.len(), 1);
+        assert!(cache.queue.contains_key(&pending));
+    }
+
+    #[test]
+    fn test_blobs_for_one_address_are_tracked_separately() {
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1);
+        let accounts = HashSet::from([Address::new([1u8; 20])]);
+        let first: RequestId = "first".parse().unwrap();
+        let second: RequestId = "second".parse().unwrap();
+
+        cache
+            .enqueue(first.clone(), accounts.clone(), HashSet::new())
+            .unwrap();
+        cache
+            .enqueue(second.clone(), accounts.clone(), HashSet::new())
+            .unwrap();
+        assert_eq!(cache.queue.len(), 2);
+        assert_eq!(cache.queue[&first].0, accounts);
+        assert_eq!(cache.queue[&second].0, accounts);
+
+        cache
+            .enqueue(first.clone(), HashSet::new(), HashSet::new())
+            .unwrap();
+        assert_eq!(cache.queue.len(), 2);
+        assert!(cache.queue[&first].0.is_empty());
+        assert_eq!(cache.queue[&second].0, accounts);
+    }
+
+    #[tokio::test]
+    async fn test_blobs_sharing_batch_header_are_validated_together() {
+        let (calls_tx, mut calls_rx) = unbounded_channel();
+        let (da_actor, handle) = Actor::spawn(None, RecordingDaClient, calls_tx)
+            .await
+            .unwrap();
+        let mut cache =
+            PendingBlobCache::with_max_cast_attempts(1).with_batching(Duration::from_secs(60), 2);
+        let batch_header_hash = H256::repeat_byte(1);
+        let first: RequestId = "first".parse().unwrap();
+        let second: RequestId = "second".parse().unwrap();
+
+        cache.batch_validation(&da_actor, batch_header_hash, first.clone());
+        assert!(cache.receivers.is_empty());
+        cache.batch_validation(&da_actor, batch_header_hash, second.clone());
+        assert_eq!(cache.receivers.len(), 2);
+        assert!(cache.batches.is_empty());
+
+        assert_eq!(calls_rx.recv().await, Some(vec![first, second]));
+        da_actor.stop(None);
+        handle.await.unwrap();
+        assert_eq!(calls_rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_single_blob_batch_is_sent_once_window_elapses() {
+        let (calls_tx, mut calls_rx) = unbounded_channel();
+        let (da_actor, handle) = Actor::spawn(None, RecordingDaClient, calls_tx)
+            .await
+            .unwrap();
+        let window = Duration::from_millis(100);
+        let mut cache = PendingBlobCache::with_max_cast_attempts(1).with_batching(window, 16);
+        let batch_header_hash = H256::repeat_byte(1);
+        let only: RequestId = "only".parse().unwrap();
+
+        cache.batch_validation(&da_actor, batch_header_hash, only.clone());
+        cache.flush_expired_batches(&da_actor);
+        assert!(cache.receivers.is_empty());
+        assert_eq!(cache.batches.len(), 1);
+
+        tokio::time::sleep(window).await;
+        cache.flush_expired_batches(&da_actor);
+        assert_eq!(cache.receivers.len(), 1);
+        assert!(cache.batches.is_empty());
+
+        assert_eq!(calls_rx.recv().await, Some(vec![only]));
+        da_actor.stop(None);
+        handle.await.unwrap();
+        assert_eq!(calls_rx.recv().await, None);
+    }
+}
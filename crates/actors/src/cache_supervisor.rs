@@ -0,0 +1,169 @@
+use lasr_messages::{
+    AccountCacheMessage, ActorType, BlobCacheMessage, PendingTransactionMessage, Shutdown,
+};
+use ractor::{concurrency::JoinHandle, ActorRef, Message};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CacheShutdownError {
+    #[error("failed to signal {0} to shut down: {1}")]
+    Signal(ActorType, String),
+
+    #[error("{0} did not shut down cleanly: {1}")]
+    Join(ActorType, String),
+}
+
+/// A running cache actor together with the handle its run loop is joined
+/// through, as returned by `Actor::spawn`.
+pub struct CacheHandle<M: Message> {
+    actor: ActorRef<M>,
+    handle: JoinHandle<()>,
+}
+
+impl<M: Message> From<(ActorRef<M>, JoinHandle<()>)> for CacheHandle<M> {
+    fn from((actor, handle): (ActorRef<M>, JoinHandle<()>)) -> Self {
+        Self { actor, handle }
+    }
+}
+
+impl<M: Message> CacheHandle<M> {
+    fn signal(&self, mode: Shutdown, drain: M, who: ActorType) -> Result<(), CacheShutdownError> {
+        match mode {
+            Shutdown::Drain => self
+                .actor
+                .cast(drain)
+                .map_err(|e| CacheShutdownError::Signal(who, format!("{e:?}"))),
+            Shutdown::Immediate => {
+                self.actor.stop(Some("immediate shutdown".to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    async fn join(self, who: ActorType) -> Result<(), CacheShutdownError> {
+        self.handle
+            .await
+            .map_err(|e| CacheShutdownError::Join(who, format!("{e:?}")))
+    }
+}
+
+/// Owns the account cache, blob cache and pending transactions actors so the
+/// three can be torn down together.
+pub struct CacheSupervisor {
+    account_cache: CacheHandle<AccountCacheMessage>,
+    blob_cache: CacheHandle<BlobCacheMessage>,
+    pending_transactions: CacheHandle<PendingTransactionMessage>,
+}
+
+impl CacheSupervisor {
+    pub fn new(
+        account_cache: impl Into<CacheHandle<AccountCacheMessage>>,
+        blob_cache: impl Into<CacheHandle<BlobCacheMessage>>,
+        pending_transactions: impl Into<CacheHandle<PendingTransactionMessage>>,
+    ) -> Self {
+        Self {
+            account_cache: account_cache.into(),
+            blob_cache: blob_cache.into(),
+            pending_transactions: pending_transactions.into(),
+        }
+    }
+
+    /// Signals every cache to stop according to `mode` and waits for each
+    /// run loop to return. Pending transactions and the blob cache are
+    /// stopped first. The account cache is only signalled once both have
+    /// returned, so with `Shutdown::Drain` it still applies every write they
+    /// queued while draining.
+    ///
+    /// Every cache is signalled and joined even if an earlier step fails, so
+    /// one cache failing to stop doesn't leave the others running. The first
+    /// failure is returned.
+    pub async fn shutdown(self, mode: Shutdown) -> Result<(), CacheShutdownError> {
+        let steps = [
+            self.pending_transactions.signal(
+                mode,
+                PendingTransactionMessage::Drain,
+                ActorType::PendingTransactions,
+            ),
+            self.blob_cache
+                .signal(mode, BlobCacheMessage::Drain, ActorType::BlobCache),
+            self.pending_transactions
+                .join(ActorType::PendingTransactions)
+                .await,
+            self.blob_cache.join(ActorType::BlobCache).await,
+            self.account_cache
+                .signal(mode, AccountCacheMessage::Drain, ActorType::AccountCache),
+            self.account_cache.join(ActorType::AccountCache).await,
+        ];
+        steps.into_iter().collect()
+    }
+}
+
+#[cfg(all(test, feature = "mock_storage"))]
+mod cache_supervisor_tests {
+    use super::{CacheHandle, CacheShutdownError, CacheSupervisor};
+    use crate::{AccountCacheActor, BlobCacheActor, PendingTransactionActor};
+    use lasr_messages::{AccountCacheMessage, ActorType, Shutdown};
+    use lasr_types::MockPersistenceStore;
+    use ractor::Actor;
+
+    async fn spawn_caches() -> CacheSupervisor {
+        CacheSupervisor::new(
+            Actor::spawn(
+                None,
+                AccountCacheActor::new(),
+                MockPersistenceStore::default(),
+            )
+            .await
+            .unwrap(),
+            Actor::spawn(None, BlobCacheActor::new(), ()).await.unwrap(),
+            Actor::spawn(None, PendingTransactionActor::new(), ())
+                .await
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_every_cache() {
+        for mode in [Shutdown::Drain, Shutdown::Immediate] {
+            let caches = spawn_caches().await;
+            tokio::time::timeout(std::time::Duration::from_secs(5), caches.shutdown(mode))
+                .await
+                .expect("every cache run loop returns")
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_remaining_caches_when_one_already_stopped() {
+        let (account_cache, account_cache_handle) = Actor::spawn(
+            None,
+            AccountCacheActor::new(),
+            MockPersistenceStore::default(),
+        )
+        .await
+        .unwrap();
+        let (blob_cache, blob_cache_handle) =
+            Actor::spawn(None, BlobCacheActor::new(), ()).await.unwrap();
+        blob_cache.stop(None);
+        blob_cache_handle.await.unwrap();
+        let caches = CacheSupervisor::new(
+            (account_cache.clone(), account_cache_handle),
+            CacheHandle::from((blob_cache, ractor::concurrency::spawn(async {}))),
+            Actor::spawn(None, PendingTransactionActor::new(), ())
+                .await
+                .unwrap(),
+        );
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            caches.shutdown(Shutdown::Drain),
+        )
+        .await
+        .expect("every cache run loop returns");
+        assert!(matches!(
+            result,
+            Err(CacheShutdownError::Signal(ActorType::BlobCache, _))
+        ));
+        assert!(account_cache.cast(AccountCacheMessage::Drain).is_err());
+    }
+}
@@ -11,10 +11,11 @@ use eigenda_client::{
 };
 use ethereum_types::H256;
 use futures::{
+    future::join_all,
     stream::{FuturesUnordered, StreamExt},
     FutureExt,
 };
-use lasr_messages::{ActorName, ActorType, DaClientMessage, SupervisorType};
+use lasr_messages::{ActorName, ActorType, DaClientMessage, RequestId, SupervisorType};
 use lasr_types::{Account, AccountType, Address};
 use ractor::{
     concurrency::OneshotSender, Actor, ActorCell, ActorProcessingErr, ActorRef, SupervisionEvent,
@@ -58,8 +59,8 @@ impl DaClientActor {
     }
     async fn validate_blob(
         da_client: Arc<Mutex<DaClient>>,
-        request_id: String,
-        tx: OneshotSender<(String, BlobVerificationProof)>,
+        request_id: RequestId,
+        tx: OneshotSender<(RequestId, BlobVerificationProof)>,
     ) {
         tracing::info!("DA Client asked to validate blob");
         let client = {
@@ -68,6 +69,46 @@ impl DaClientActor {
         };
         validate_blob(client, request_id, tx).await;
     }
+    /// Polls the status of every blob dispersed under `batch_header_hash`
+    /// concurrently, each on its own task, and reports the batch once all of
+    /// them have finished. The polls are awaited on a task of their own so
+    /// the future pool isn't held up until the whole batch is confirmed.
+    async fn validate_blob_batch(
+        da_client: Arc<Mutex<DaClient>>,
+        batch_header_hash: H256,
+        blobs: Vec<(RequestId, OneshotSender<(RequestId, BlobVerificationProof)>)>,
+    ) {
+        let count = blobs.len();
+        tracing::info!(
+            "DA Client asked to validate {count} blobs under batch header {batch_header_hash:?}"
+        );
+        let client = {
+            let state = da_client.lock().await;
+            state.client.clone()
+        };
+        let polls: Vec<_> = blobs
+            .into_iter()
+            .map(|(request_id, tx)| {
+                tokio::task::spawn(poll_blob_status(client.clone(), request_id, tx))
+            })
+            .collect();
+        tokio::task::spawn(async move {
+            let failed = join_all(polls)
+                .await
+                .into_iter()
+                .filter(|poll| !matches!(poll, Ok(Ok(()))))
+                .count();
+            if failed > 0 {
+                tracing::error!(
+                    "{failed} of {count} blob validations under batch header {batch_header_hash:?} failed"
+                );
+            } else {
+                tracing::info!(
+                    "finished validating {count} blobs under batch header {batch_header_hash:?}"
+                );
+            }
+        });
+    }
     async fn retrieve_account(
         da_client: Arc<Mutex<DaClient>>,
         address: Address,
@@ -163,6 +204,9 @@ impl Actor for DaClientActor {
     ) -> Result<(), ActorProcessingErr> {
         let da_client_ptr = Arc::clone(state);
         match message {
+            DaClientMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::DaClient);
+            }
             // Optimistically and naively store account blobs
             DaClientMessage::StoreBatch { batch, tx } => {
                 DaClientActor::store_batch(da_client_ptr, batch, tx).await;
@@ -174,6 +218,15 @@ impl Actor for DaClientActor {
                 let guard = self.future_pool.lock().await;
                 guard.push(fut.boxed());
             }
+            DaClientMessage::ValidateBlobBatch {
+                batch_header_hash,
+                blobs,
+            } => {
+                let fut =
+                    DaClientActor::validate_blob_batch(da_client_ptr, batch_header_hash, blobs);
+                let guard = self.future_pool.lock().await;
+                guard.push(fut.boxed());
+            }
             // Optimistically and naively retreive account blobs
             DaClientMessage::RetrieveAccount {
                 address,
@@ -224,17 +277,17 @@ impl ActorExt for DaClientActor {
 
 async fn get_blob_status(
     client: &EigenDaGrpcClient,
-    request_id: &str,
+    request_id: &RequestId,
 ) -> Result<BlobStatus, std::io::Error> {
     tracing::info!("acquired blob status");
-    client.clone().get_blob_status(&request_id.to_owned()[..])
+    client.clone().get_blob_status(request_id.as_str())
 }
 
 #[async_recursion::async_recursion]
 async fn poll_blob_status(
     client: EigenDaGrpcClient,
-    request_id: String,
-    tx: OneshotSender<(String, BlobVerificationProof)>,
+    request_id: RequestId,
+    tx: OneshotSender<(RequestId, BlobVerificationProof)>,
 ) -> Result<(), Box<dyn std::error::Error + Send>> {
     let res = get_blob_status(&client, &request_id).await;
     if let Ok(status) = res {
@@ -255,8 +308,8 @@ async fn poll_blob_status(
 
 async fn validate_blob(
     client: EigenDaGrpcClient,
-    request_id: String,
-    tx: OneshotSender<(String, BlobVerificationProof)>,
+    request_id: RequestId,
+    tx: OneshotSender<(RequestId, BlobVerificationProof)>,
 ) -> JoinHandle<Result<(), Box<dyn std::error::Error + Send>>> {
     tracing::info!("spawning blob validation task");
     tokio::task::spawn(async move { poll_blob_status(client, request_id, tx).await })
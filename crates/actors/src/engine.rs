@@ -408,6 +408,9 @@ impl Actor for EngineActor {
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            EngineMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Engine);
+            }
             EngineMessage::EoEvent { event } => {
                 let fut = EngineActor::handle_eo_event(event);
                 let guard = self.future_pool.lock().await;
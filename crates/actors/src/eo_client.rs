@@ -479,6 +479,9 @@ impl Actor for EoClientActor {
     ) -> Result<(), ActorProcessingErr> {
         let eo_client_ptr = Arc::clone(state);
         match message {
+            EoMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::EoClient);
+            }
             EoMessage::GetContractBlobIndex {
                 program_id: _,
                 sender: _,
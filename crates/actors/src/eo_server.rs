@@ -378,6 +378,9 @@ impl Actor for EoServerActor {
         _: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            EoMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::EoServer);
+            }
             EoMessage::Log { log, log_type } => {
                 EoServerActor::handle_log(log, log_type);
             }
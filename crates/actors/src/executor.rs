@@ -717,6 +717,9 @@ impl Actor for ExecutorActor {
     ) -> Result<(), ActorProcessingErr> {
         let engine_ptr = Arc::clone(state);
         match message {
+            ExecutorMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Executor);
+            }
             ExecutorMessage::Retrieve { .. } => {
                 // Retrieve the package from IPFS
                 // Convert the package into a payload
@@ -785,6 +788,9 @@ impl Actor for ExecutorActor {
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            ExecutorMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Executor);
+            }
             ExecutorMessage::Retrieve {
                 content_id,
                 program_id: _,
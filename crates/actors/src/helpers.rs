@@ -6,11 +6,12 @@ use std::time::Duration;
 use crate::AccountCacheError;
 use futures::future::BoxFuture;
 use futures::stream::{FuturesOrdered, FuturesUnordered};
-use lasr_messages::{AccountCacheMessage, ActorType};
+use lasr_messages::{AccountCacheMessage, ActorType, Timeouts};
 use lasr_types::{Account, Address};
-use ractor::concurrency::{oneshot, OneshotReceiver};
+use ractor::concurrency::{oneshot, OneshotReceiver, OneshotSender};
 use ractor::pg::GroupChangeMessage;
 use ractor::ActorRef;
+use thiserror::Error;
 use tokio::sync::Mutex;
 use tracing;
 
@@ -327,8 +328,22 @@ fn channel_closed_unexpectedly<E: std::error::Error + 'static>(
     Box::new(e)
 }
 
+/// Waits for the reply from an actor of type `from`, giving up after the
+/// timeout configured for it in [`Timeouts::global`].
 pub async fn handle_actor_response<T, F, M>(
     rx: OneshotReceiver<T>,
+    from: ActorType,
+    handler: F,
+) -> Result<M, Box<dyn std::error::Error>>
+where
+    F: FnOnce(T) -> Result<M, Box<dyn std::error::Error>>,
+{
+    await_actor_response(rx, Timeouts::global().get(&from), handler).await
+}
+
+async fn await_actor_response<T, F, M>(
+    rx: OneshotReceiver<T>,
+    timeout: Duration,
     handler: F,
 ) -> Result<M, Box<dyn std::error::Error>>
 where
@@ -339,7 +354,7 @@ where
             let resp = response.map_err(channel_closed_unexpectedly)?;
             handler(resp)
         }
-        _ = tokio::time::sleep(Duration::from_secs(15)) => {
+        _ = tokio::time::sleep(timeout) => {
             Err(
                 Box::new(
                     std::io::Error::new(
@@ -352,23 +367,82 @@ where
     }
 }
 
+/// Sends the `Ping` message built by `ping` to `actor` and waits up to
+/// `timeout` for it to reply with its [`ActorType`]. Returns `None` if the
+/// actor is not running or does not answer in time.
+pub async fn ping_actor<M, F>(actor: &ActorRef<M>, ping: F, timeout: Duration) -> Option<ActorType>
+where
+    M: ractor::Message,
+    F: FnOnce(OneshotSender<ActorType>) -> M,
+{
+    let (reply, rx) = oneshot();
+    actor.cast(ping(reply)).ok()?;
+    await_actor_response(rx, timeout, Ok).await.ok()
+}
+
+/// Why [`lookup_account`] did not get an answer from the account cache.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum LookupError {
+    #[error("failed to send the read to the account cache")]
+    Unavailable,
+
+    #[error("account cache dropped the read without replying")]
+    Dropped,
+
+    #[error("account cache did not reply within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Reads `address` through `cache`, waiting at most `timeout` for the reply.
+///
+/// The cache answers from memory or, failing that, from the persistence
+/// store. An account read from the store is not added to the cache, so
+/// repeated reads of it keep going to the store. `Ok(None)` means neither
+/// holds the account.
+pub async fn lookup_account(
+    cache: &ActorRef<AccountCacheMessage>,
+    address: Address,
+    who: ActorType,
+    timeout: Duration,
+) -> Result<Option<Account>, LookupError> {
+    let (tx, rx) = oneshot();
+    cache
+        .cast(AccountCacheMessage::Read { address, tx, who })
+        .map_err(|_| LookupError::Unavailable)?;
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(account)) => Ok(account),
+        Ok(Err(_)) => Err(LookupError::Dropped),
+        Err(_) => Err(LookupError::Timeout(timeout)),
+    }
+}
+
 pub async fn check_account_cache(address: Address, who: ActorType) -> Option<Account> {
     let actor: ActorRef<AccountCacheMessage> =
         ractor::registry::where_is(ActorType::AccountCache.to_string())?.into();
+    let timeout = Timeouts::global().get(&ActorType::AccountCache);
+    lookup_account(&actor, address, who, timeout)
+        .await
+        .ok()
+        .flatten()
+}
 
-    let (tx, rx) = oneshot();
+/// Looks up every address in `addresses` with a single `AccountCache` round
+/// trip. Accounts missing from both the cache and persistence store are `None`.
+pub async fn check_account_cache_many(
+    addresses: Vec<Address>,
+    who: ActorType,
+) -> Option<Vec<(Address, Option<Account>)>> {
+    let actor: ActorRef<AccountCacheMessage> =
+        ractor::registry::where_is(ActorType::AccountCache.to_string())?.into();
 
-    // Note: This message will return an `Account` from either the `AccountCache` or the persistence store.
-    // If the account was not found in `AccountCache`, but found in persistence store then it will
-    // write to `AccountCache` for faster reads in the future.
-    let message = AccountCacheMessage::Read { address, tx, who };
+    let (tx, rx) = oneshot();
+    let message = AccountCacheMessage::ReadMany { addresses, tx, who };
 
     actor.cast(message).ok()?;
 
-    let handler = create_handler!(account_cache_response);
-    let account = handle_actor_response(rx, handler).await.ok()?;
-
-    Some(account)
+    handle_actor_response(rx, ActorType::AccountCache, Ok)
+        .await
+        .ok()
 }
 
 pub async fn get_account(address: Address, who: ActorType) -> Option<Account> {
@@ -378,3 +452,172 @@ pub async fn get_account(address: Address, who: ActorType) -> Option<Account> {
     );
     check_account_cache(address, who).await
 }
+
+#[cfg(test)]
+mod helpers_tests {
+    use super::{await_actor_response, lookup_account, ping_actor, LookupError};
+    use crate::BlobCacheActor;
+    use async_trait::async_trait;
+    use lasr_messages::{
+        AccountCacheMessage, ActorType, BlobCacheMessage, Timeouts, TIMEOUT_DURATION,
+    };
+    use lasr_types::{Account, AccountType, Address};
+    use ractor::{
+        concurrency::{oneshot, OneshotSender},
+        Actor, ActorProcessingErr, ActorRef,
+    };
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    /// An account cache stub that answers reads from a fixed set of accounts.
+    struct InMemoryCache;
+
+    #[async_trait]
+    impl Actor for InMemoryCache {
+        type Msg = AccountCacheMessage;
+        type State = HashMap<Address, Account>;
+        type Arguments = HashMap<Address, Account>;
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            accounts: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(accounts)
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            message: Self::Msg,
+            accounts: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            if let AccountCacheMessage::Read { address, tx, .. } = message {
+                let _ = tx.send(accounts.get(&address).cloned());
+            }
+            Ok(())
+        }
+    }
+
+    /// An account cache stub that holds on to every read without replying.
+    struct SilentCache;
+
+    #[async_trait]
+    impl Actor for SilentCache {
+        type Msg = AccountCacheMessage;
+        type State = Vec<OneshotSender<Option<Account>>>;
+        type Arguments = ();
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            _: (),
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(Vec::new())
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            message: Self::Msg,
+            pending: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            if let AccountCacheMessage::Read { tx, .. } = message {
+                pending.push(tx);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_account_reads_through_cache() {
+        let address = Address::new([1u8; 20]);
+        let account = Account::new(AccountType::User, None, address, None);
+        let (cache, handle) = Actor::spawn(
+            None,
+            InMemoryCache,
+            HashMap::from([(address, account.clone())]),
+        )
+        .await
+        .unwrap();
+
+        let found = lookup_account(&cache, address, ActorType::Engine, TIMEOUT_DURATION).await;
+        assert_eq!(found, Ok(Some(account)));
+        let missing = lookup_account(
+            &cache,
+            Address::new([2u8; 20]),
+            ActorType::Engine,
+            TIMEOUT_DURATION,
+        )
+        .await;
+        assert_eq!(missing, Ok(None));
+
+        cache.stop(None);
+        handle.await.unwrap();
+        let stopped = lookup_account(&cache, address, ActorType::Engine, TIMEOUT_DURATION).await;
+        assert_eq!(stopped, Err(LookupError::Unavailable));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_account_times_out_when_cache_does_not_reply() {
+        let (cache, handle) = Actor::spawn(None, SilentCache, ()).await.unwrap();
+        let timeout = Duration::from_millis(10);
+
+        let result =
+            lookup_account(&cache, Address::new([1u8; 20]), ActorType::Engine, timeout).await;
+        assert_eq!(result, Err(LookupError::Timeout(timeout)));
+
+        cache.stop(None);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_actor_answers_ping_with_its_type() {
+        let (actor, handle) = Actor::spawn(None, BlobCacheActor::new(), ()).await.unwrap();
+
+        let pong = ping_actor(
+            &actor,
+            |reply| BlobCacheMessage::Ping { reply },
+            TIMEOUT_DURATION,
+        )
+        .await;
+        assert_eq!(pong, Some(ActorType::BlobCache));
+
+        actor.stop(None);
+        handle.await.unwrap();
+        let pong = ping_actor(
+            &actor,
+            |reply| BlobCacheMessage::Ping { reply },
+            TIMEOUT_DURATION,
+        )
+        .await;
+        assert_eq!(pong, None);
+    }
+
+    #[tokio::test]
+    async fn test_overridden_timeout_is_respected() {
+        let timeouts =
+            Timeouts::default().with_timeout(ActorType::AccountCache, Duration::from_millis(10));
+        assert_eq!(
+            timeouts.get(&ActorType::AccountCache),
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            timeouts.get(&ActorType::Engine),
+            Timeouts::default().get(&ActorType::Engine)
+        );
+
+        let (_tx, rx) = oneshot::<()>();
+        let response = tokio::time::timeout(
+            Duration::from_secs(1),
+            await_actor_response(rx, timeouts.get(&ActorType::AccountCache), Ok),
+        )
+        .await
+        .expect("the overridden timeout should fire first");
+        let err = response.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::TimedOut
+        );
+    }
+}
@@ -1,6 +1,7 @@
 pub mod account_cache;
 pub mod batcher;
 pub mod blob_cache;
+pub mod cache_supervisor;
 pub mod da_client;
 pub mod engine;
 pub mod eo_client;
@@ -8,6 +9,7 @@ pub mod eo_server;
 pub mod executor;
 pub mod helpers;
 pub mod manager;
+pub mod metrics;
 pub mod pending_transactions;
 pub mod rpc_server;
 pub mod scheduler;
@@ -16,6 +18,7 @@ pub mod validator;
 pub use account_cache::*;
 pub use batcher::*;
 pub use blob_cache::*;
+pub use cache_supervisor::*;
 pub use da_client::*;
 pub use engine::*;
 pub use eo_client::*;
@@ -23,6 +26,7 @@ pub use eo_server::*;
 pub use executor::*;
 pub use helpers::*;
 pub use manager::*;
+pub use metrics::*;
 pub use pending_transactions::*;
 pub use rpc_server::*;
 pub use scheduler::*;
@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters describing the work done by one of the caches. Updated by the
+/// cache handlers and read through [`CacheMetrics::snapshot`], so a
+/// monitoring actor can poll them without taking the cache.
+#[derive(Debug, Default)]
+pub struct CacheMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    writes: AtomicU64,
+    removals: AtomicU64,
+}
+
+/// A point in time copy of [`CacheMetrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheMetricsSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+    pub writes: u64,
+    pub removals: u64,
+}
+
+impl CacheMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_hit(&self) {
+        self.record_hits(1);
+    }
+
+    pub fn record_hits(&self, n: u64) {
+        self.hits.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn record_miss(&self) {
+        self.record_misses(1);
+    }
+
+    pub fn record_misses(&self, n: u64) {
+        self.misses.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn record_write(&self) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_removal(&self) {
+        self.removals.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CacheMetricsSnapshot {
+        CacheMetricsSnapshot {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            removals: self.removals.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -11,14 +11,17 @@ use tokio::sync::mpsc::Sender;
 
 use async_trait::async_trait;
 use chrono::prelude::*;
-use lasr_types::{Address, AddressOrNamespace, Outputs, Transaction, TransactionType};
+use lasr_types::{
+    Account, Address, AddressOrNamespace, Outputs, Transaction, TransactionType, TxKind, U256,
+};
 use ractor::{Actor, ActorCell, ActorProcessingErr, ActorRef, SupervisionEvent};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    get_actor_ref, helpers::Coerce, process_group_changed, SchedulerError, ValidatorError,
+    get_actor_ref, helpers::Coerce, process_group_changed, CacheMetrics, CacheMetricsSnapshot,
+    SchedulerError, ValidatorError,
 };
 
 pub const PENDING_TIMEOUT: u64 = 15000;
@@ -29,6 +32,8 @@ pub struct Vertex {
     timestamp: u64,
     outputs: Option<Outputs>,
     accounts_touched: HashSet<Address>,
+    /// Transactions added after this one that touch one of its accounts and
+    /// so wait on it.
     dependent_transactions: Vec<String>,
 }
 
@@ -171,7 +176,9 @@ impl PreCallGraph {
         // if it does not, forward it to executor for execution
         if !has_dependencies {
             tracing::warn!("no dependencies found, executing");
-            let _ = self.send_to_executor(transaction);
+            self.send_to_executor(transaction)
+                .typecast()
+                .log_err(|e| PendingTransactionError::Custom(format!("{e}")));
         }
     }
 
@@ -206,7 +213,9 @@ impl PreCallGraph {
         if let Some(next_vtx) = self.vertices.get(&next) {
             if let Ok(guard) = next_vtx.read() {
                 let transaction = guard.transaction.clone();
-                let _ = self.send_to_executor(transaction);
+                self.send_to_executor(transaction)
+                    .typecast()
+                    .log_err(|e| PendingTransactionError::Custom(format!("{e}")));
             } else {
                 //return error
                 return Err(std::io::Error::new(
@@ -230,7 +239,11 @@ impl PreCallGraph {
 
         let message = ExecutorMessage::Exec { transaction };
 
-        let _ = executor.cast(message);
+        executor.cast(message).typecast().log_err(|e| {
+            PendingTransactionError::Custom(format!(
+                "failed to cast Exec message to ExecutorActor: {e:?}"
+            ))
+        });
 
         Ok(())
     }
@@ -250,6 +263,63 @@ impl PendingGraph {
         }
     }
 
+    /// The number of transactions currently pending in the graph.
+    pub fn pending_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// The number of pending transactions that touch `address`.
+    pub fn pending_for(&self, address: &Address) -> usize {
+        self.account_index
+            .get(address)
+            .map_or(0, |transactions| transactions.len())
+    }
+
+    /// The addresses touched by `transaction` that already have pending
+    /// transactions, in the order [`Transaction::touched_addresses`] lists
+    /// them. An empty result means `transaction` could run alongside every
+    /// pending transaction.
+    pub fn conflicts_with(&self, transaction: &Transaction) -> Vec<Address> {
+        transaction
+            .touched_addresses()
+            .into_iter()
+            .filter(|address| self.pending_for(address) > 0)
+            .collect()
+    }
+
+    /// The total value of pending sends and bridge-outs from `address` in
+    /// `program_id`. The effect of a pending call on the sender's balance is
+    /// only known once it has executed, so calls are not counted.
+    pub fn pending_outflow(&self, address: &Address, program_id: &Address) -> U256 {
+        let Some(transactions) = self.account_index.get(address) else {
+            return U256::zero();
+        };
+        transactions
+            .iter()
+            .filter_map(|hash| self.vertices.get(hash))
+            .filter_map(|vertex| {
+                let vertex = vertex.read().ok()?;
+                let transaction = &vertex.transaction;
+                let debits_sender = matches!(
+                    transaction.transaction_type().kind(),
+                    TxKind::Send | TxKind::BridgeOut
+                );
+                (debits_sender
+                    && transaction.from() == *address
+                    && transaction.program_id() == *program_id)
+                    .then_some(transaction.value())
+            })
+            .fold(U256::zero(), |total, value| total.saturating_add(value))
+    }
+
+    /// The balance `account` can still spend in `program_id`: its committed
+    /// balance less [`PendingGraph::pending_outflow`].
+    pub fn available_balance(&self, account: &Account, program_id: &Address) -> U256 {
+        account
+            .balance(program_id)
+            .saturating_sub(self.pending_outflow(&account.owner_address(), program_id))
+    }
+
     pub fn clean_graph(&mut self) {
         // Look at all vertices, see if any have timed out,
         // if any have timed out, check if they have dependent transactions
@@ -282,6 +352,13 @@ impl PendingGraph {
                             tracing::warn!("collecting dependent transaction hashes");
                             deps.push(dep.clone());
                         }
+                        // the timed out transaction is no longer pending for
+                        // any of the accounts it touched
+                        for account in guard.accounts_touched() {
+                            if let Some(act_deps) = self.account_index.get_mut(account) {
+                                act_deps.retain(|h| h != &hash);
+                            }
+                        }
                     }
                 }
             }
@@ -334,7 +411,13 @@ impl PendingGraph {
                             let transaction = guard.transaction.clone();
                             let outputs = guard.outputs.clone();
                             tracing::warn!("scheduling: {} with validator", transaction_hash);
-                            let _ = self.schedule_with_validator(transaction, outputs);
+                            self.schedule_with_validator(transaction, outputs)
+                                .typecast()
+                                .log_err(|e| {
+                                    PendingTransactionError::Custom(format!(
+                                        "failed to schedule {transaction_hash} with validator: {e}"
+                                    ))
+                                });
                         }
                         Err(e) => tracing::error!(
                             "Unable to acquire read guard on vertex: {}: {}",
@@ -408,11 +491,11 @@ impl PendingGraph {
                         }
                     }
                 }
-                if has_dependencies {
-                    // If it does have dependencies, push this transaction
-                    // to the back of the account dependencies queue
-                    dependencies.push_back(transaction_id.clone());
-                }
+                // Push this transaction to the back of the account
+                // dependencies queue, even when it is the first pending
+                // transaction for the account, so later transactions
+                // touching the same account are ordered behind it
+                dependencies.push_back(transaction_id.clone());
             }
         }
 
@@ -420,53 +503,20 @@ impl PendingGraph {
             // If there are no dependencies, then go ahead and schedule the
             // transaction with the validator
             tracing::warn!("no dependencies found, scheduling with validator");
-            let _ = self.schedule_with_validator(transaction, outputs);
+            self.schedule_with_validator(transaction, outputs)
+                .typecast()
+                .log_err(|e| {
+                    PendingTransactionError::Custom(format!(
+                        "failed to schedule {transaction_id} with validator: {e}"
+                    ))
+                });
         }
     }
 
     fn handle_valid(&mut self, validated_transaction_hash: &str) -> Vec<String> {
         tracing::info!("handling validated transaction");
-        let mut transactions_ready_for_validation = Vec::new();
-        if let Some(validated_vertex) = self.vertices.remove(validated_transaction_hash) {
-            for (id, vertex) in self.vertices.iter() {
-                tracing::info!("checking for dependent transactions");
-                if let Ok(mut guard) = vertex.write() {
-                    if let Some(index) = guard
-                        .dependent_transactions
-                        .iter()
-                        .position(|hash| hash == validated_transaction_hash)
-                    {
-                        let _ = guard.dependent_transactions.remove(index);
-                        if guard.dependent_transactions.is_empty() {
-                            tracing::info!(
-                                "marking dependent transaction: {} ready for validation",
-                                &id
-                            );
-                            transactions_ready_for_validation.push(id.clone());
-                        }
-                    }
-                }
-            }
-
-            if let Ok(guard) = validated_vertex.read() {
-                for account in guard.accounts_touched.iter() {
-                    if let Some(transactions) = self.account_index.get_mut(account) {
-                        tracing::info!(
-                            "removing validated transaction {:?} from dependency graph for account: {}", 
-                            &validated_transaction_hash, &account
-                        );
-                        if let Some(index) = &transactions
-                            .iter()
-                            .position(|hash| hash == validated_transaction_hash)
-                        {
-                            transactions.remove(*index);
-                        }
-                    }
-                }
-            }
-        }
-
-        transactions_ready_for_validation
+        self.remove_vertex(validated_transaction_hash)
+            .unwrap_or_default()
     }
 
     fn handle_invalid(
@@ -475,63 +525,86 @@ impl PendingGraph {
         e: Box<dyn std::error::Error + Send>,
     ) -> Result<Vec<String>, PendingTransactionError> {
         tracing::info!("handling invalid transaction");
-        let mut transactions_ready_for_validation = Vec::new();
-        if let Some(invalid_vertex) = self.vertices.remove(invalid_transaction_hash) {
-            for (id, vertex) in self.vertices.iter() {
-                tracing::info!("checking for dependenty transactions");
-                if let Ok(mut guard) = vertex.write() {
-                    if let Some(index) = guard
-                        .dependent_transactions
-                        .iter()
-                        .position(|hash| hash == invalid_transaction_hash)
-                    {
-                        let _ = guard.dependent_transactions.remove(index);
-                        if guard.dependent_transactions.is_empty() {
-                            tracing::info!(
-                                "marking dependent transaction: {} ready for validation",
-                                &id
-                            );
-                            transactions_ready_for_validation.push(id.clone());
-                        }
-                    }
-                }
-            }
+        let transactions_ready_for_validation = self
+            .remove_vertex(invalid_transaction_hash)
+            .unwrap_or_default();
+        notify_scheduler_of_failure(invalid_transaction_hash, e);
 
-            if let Ok(guard) = invalid_vertex.read() {
-                for account in guard.accounts_touched.iter() {
-                    if let Some(transactions) = self.account_index.get_mut(account) {
-                        tracing::info!(
-                            "removing invalid transaction {:?} from depdendency graph for account: {}",
-                            &invalid_transaction_hash, &account
-                        );
+        Ok(transactions_ready_for_validation)
+    }
 
-                        if let Some(index) = &transactions
-                            .iter()
-                            .position(|hash| hash == invalid_transaction_hash)
-                        {
-                            transactions.remove(*index);
-                        }
-                    }
-                }
-            }
-        }
+    /// Cancels the pending transaction sent by `address` with `nonce`, so
+    /// that it can be replaced by another transaction with the same nonce.
+    /// The scheduler is told the transaction failed so any caller waiting
+    /// on it is released. Returns the transactions that were only waiting
+    /// on the cancelled one, or `None` if no such transaction is pending.
+    pub fn cancel(&mut self, address: &Address, nonce: U256) -> Option<Vec<String>> {
+        let transaction_hash = self
+            .account_index
+            .get(address)?
+            .iter()
+            .find(|hash| {
+                self.vertices.get(*hash).map_or(false, |vertex| {
+                    vertex.read().map_or(false, |guard| {
+                        guard.transaction.from() == *address && guard.transaction.nonce() == nonce
+                    })
+                })
+            })?
+            .clone();
+
+        tracing::warn!("cancelling pending transaction: {}", &transaction_hash);
+        let transactions_ready_for_validation = self.remove_vertex(&transaction_hash)?;
+        notify_scheduler_of_failure(
+            &transaction_hash,
+            Box::new(PendingTransactionError::Cancelled(transaction_hash.clone())),
+        );
 
-        if let Some(scheduler) =
-            get_actor_ref::<SchedulerMessage, SchedulerError>(ActorType::Scheduler)
-        {
-            let message = SchedulerMessage::SendTransactionFailure {
-                transaction_hash: invalid_transaction_hash.to_string(),
-                error: e,
-            };
+        Some(transactions_ready_for_validation)
+    }
 
-            scheduler.cast(message).typecast().log_err(|e| {
-                SchedulerError::Custom(format!(
-                    "failed to cast SendTransactionFailure to scheduler: {e:?}"
-                ))
-            });
+    /// Removes a transaction from the graph and the account index, returning
+    /// the dependent transactions that no longer wait on anything.
+    fn remove_vertex(&mut self, transaction_hash: &str) -> Option<Vec<String>> {
+        let removed_vertex = self.vertices.remove(transaction_hash)?;
+        let guard = removed_vertex.read().ok()?;
+        for account in guard.accounts_touched.iter() {
+            if let Some(transactions) = self.account_index.get_mut(account) {
+                tracing::info!(
+                    "removing transaction {:?} from depdendency graph for account: {}",
+                    &transaction_hash,
+                    &account
+                );
+                transactions.retain(|hash| hash != transaction_hash);
+            }
         }
 
-        Ok(transactions_ready_for_validation)
+        // a dependent is ready once it is at the front of the queue of every
+        // account it touches
+        let transactions_ready_for_validation = guard
+            .dependent_transactions
+            .iter()
+            .filter(|dep| {
+                self.vertices.get(*dep).map_or(false, |vertex| {
+                    vertex.read().map_or(false, |dep_guard| {
+                        dep_guard.accounts_touched.iter().all(|account| {
+                            self.account_index
+                                .get(account)
+                                .and_then(|transactions| transactions.front())
+                                .map_or(true, |front| front == *dep)
+                        })
+                    })
+                })
+            })
+            .inspect(|dep| {
+                tracing::info!(
+                    "marking dependent transaction: {} ready for validation",
+                    dep
+                )
+            })
+            .cloned()
+            .collect();
+
+        Some(transactions_ready_for_validation)
     }
 
     fn get_transactions(
@@ -596,10 +669,29 @@ impl PendingGraph {
     }
 }
 
+/// Tells the scheduler that `transaction_hash` failed with `error`, which
+/// replies to the caller waiting on it.
+fn notify_scheduler_of_failure(transaction_hash: &str, error: Box<dyn std::error::Error + Send>) {
+    if let Some(scheduler) = get_actor_ref::<SchedulerMessage, SchedulerError>(ActorType::Scheduler)
+    {
+        let message = SchedulerMessage::SendTransactionFailure {
+            transaction_hash: transaction_hash.to_string(),
+            error,
+        };
+
+        scheduler.cast(message).typecast().log_err(|e| {
+            SchedulerError::Custom(format!(
+                "failed to cast SendTransactionFailure to scheduler: {e:?}"
+            ))
+        });
+    }
+}
+
 #[derive(Default)]
 pub struct DependencyGraphs {
     pub pending: PendingGraph,
     pub pre_call: PreCallGraph,
+    metrics: CacheMetrics,
 }
 
 impl DependencyGraphs {
@@ -607,20 +699,49 @@ impl DependencyGraphs {
         Self {
             pending: PendingGraph::new(),
             pre_call: PreCallGraph::new(),
+            metrics: CacheMetrics::new(),
         }
     }
 
+    pub fn metrics(&self) -> CacheMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.pending_count()
+    }
+
+    pub fn pending_for(&self, address: &Address) -> usize {
+        self.pending.pending_for(address)
+    }
+
+    pub fn conflicts_with(&self, transaction: &Transaction) -> Vec<Address> {
+        self.pending.conflicts_with(transaction)
+    }
+
+    pub fn pending_outflow(&self, address: &Address, program_id: &Address) -> U256 {
+        self.pending.pending_outflow(address, program_id)
+    }
+
+    pub fn available_balance(&self, account: &Account, program_id: &Address) -> U256 {
+        self.pending.available_balance(account, program_id)
+    }
+
     pub fn add_transaction(&mut self, transaction: Transaction, outputs: Option<Outputs>) {
+        self.metrics.record_write();
         self.pending.add_transaction(transaction, outputs);
     }
 
     pub fn add_call(&mut self, transaction: Transaction) {
+        self.metrics.record_write();
         self.pre_call.add_call(transaction);
     }
 
     pub fn handle_completed_exec(&mut self, transaction_hash: &str) {
         if let Err(e) = self.pre_call.handle_completed_exec(transaction_hash) {
             tracing::error!("Error in handle_completed_exec: {e}");
+        } else {
+            self.metrics.record_removal();
         }
     }
 
@@ -628,7 +749,12 @@ impl DependencyGraphs {
         &self,
         transaction_ids: Vec<String>,
     ) -> Vec<(Transaction, Option<Outputs>)> {
-        self.pending.get_transactions(transaction_ids)
+        let requested = transaction_ids.len();
+        let transactions = self.pending.get_transactions(transaction_ids);
+        self.metrics.record_hits(transactions.len() as u64);
+        self.metrics
+            .record_misses((requested - transactions.len()) as u64);
+        transactions
     }
 
     pub fn schedule_with_validator(
@@ -640,6 +766,7 @@ impl DependencyGraphs {
     }
 
     pub fn handle_valid(&mut self, transaction_hash: &str) -> Vec<String> {
+        self.metrics.record_removal();
         self.pending.handle_valid(transaction_hash)
     }
 
@@ -648,7 +775,30 @@ impl DependencyGraphs {
         transaction_hash: &str,
         e: Box<dyn std::error::Error + Send>,
     ) -> Result<Vec<String>, PendingTransactionError> {
-        self.pending.handle_invalid(transaction_hash, e)
+        let ready = self.pending.handle_invalid(transaction_hash, e)?;
+        self.metrics.record_removal();
+        Ok(ready)
+    }
+
+    /// Cancels the pending transaction sent by `address` with `nonce` and
+    /// schedules any transactions that were only waiting on it. Returns
+    /// false if no such transaction is pending.
+    pub fn cancel(&mut self, address: &Address, nonce: U256) -> bool {
+        let Some(ready) = self.pending.cancel(address, nonce) else {
+            return false;
+        };
+        self.metrics.record_removal();
+        for (transaction, outputs) in self.pending.get_transactions(ready) {
+            let transaction_hash = transaction.hash_string();
+            self.schedule_with_validator(transaction, outputs)
+                .typecast()
+                .log_err(|e| {
+                    PendingTransactionError::Custom(format!(
+                        "failed to schedule {transaction_hash} with validator: {e}"
+                    ))
+                });
+        }
+        true
     }
 
     pub fn clean_pending_graph(&mut self) {
@@ -678,6 +828,9 @@ pub enum PendingTransactionError {
     #[error("failed to acquire PendingTransactionActor from registry")]
     RactorRegistryError,
 
+    #[error("transaction {0} was cancelled")]
+    Cancelled(String),
+
     #[error("{0}")]
     Custom(String),
 }
@@ -704,11 +857,14 @@ impl Actor for PendingTransactionActor {
 
     async fn handle(
         &self,
-        _myself: ActorRef<Self::Msg>,
+        myself: ActorRef<Self::Msg>,
         message: Self::Msg,
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            PendingTransactionMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::PendingTransactions);
+            }
             PendingTransactionMessage::New {
                 transaction,
                 outputs,
@@ -747,7 +903,15 @@ impl Actor for PendingTransactionActor {
                 let transactions_ready_for_validation = state.get_transactions(get_transactions);
 
                 for (transaction, outputs) in transactions_ready_for_validation {
-                    let _ = state.schedule_with_validator(transaction, outputs);
+                    let transaction_hash = transaction.hash_string();
+                    state
+                        .schedule_with_validator(transaction, outputs)
+                        .typecast()
+                        .log_err(|e| {
+                            PendingTransactionError::Custom(format!(
+                                "failed to schedule {transaction_hash} with validator: {e}"
+                            ))
+                        });
                 }
             }
             PendingTransactionMessage::Invalid { transaction, e } => {
@@ -762,7 +926,15 @@ impl Actor for PendingTransactionActor {
                     };
 
                 for (transaction, outputs) in transactions_ready_for_validation {
-                    let _ = state.schedule_with_validator(transaction, outputs);
+                    let transaction_hash = transaction.hash_string();
+                    state
+                        .schedule_with_validator(transaction, outputs)
+                        .typecast()
+                        .log_err(|e| {
+                            PendingTransactionError::Custom(format!(
+                                "failed to schedule {transaction_hash} with validator: {e}"
+                            ))
+                        });
                 }
             }
             PendingTransactionMessage::GetPendingTransaction {
@@ -771,6 +943,16 @@ impl Actor for PendingTransactionActor {
             } => {
                 tracing::info!("Pending transaction requested");
             }
+            PendingTransactionMessage::Cancel {
+                address,
+                nonce,
+                sender,
+            } => {
+                let cancelled = state.cancel(&address, nonce);
+                if sender.send(cancelled).is_err() {
+                    tracing::error!("failed to reply to cancellation of {address} nonce {nonce}");
+                }
+            }
             PendingTransactionMessage::ValidCall { transaction, .. } => {
                 let get_transactions = state.handle_valid(&transaction.hash_string());
                 tracing::warn!("received valid transactions in pending transaction in graph for transaction: {}", transaction.hash_string());
@@ -778,7 +960,15 @@ impl Actor for PendingTransactionActor {
 
                 for (transaction, outputs) in transactions_ready_for_validation {
                     tracing::warn!("scheduling: {} with validator", transaction.hash_string());
-                    let _ = state.schedule_with_validator(transaction, outputs);
+                    let transaction_hash = transaction.hash_string();
+                    state
+                        .schedule_with_validator(transaction, outputs)
+                        .typecast()
+                        .log_err(|e| {
+                            PendingTransactionError::Custom(format!(
+                                "failed to schedule {transaction_hash} with validator: {e}"
+                            ))
+                        });
                 }
             }
             PendingTransactionMessage::CleanGraph => {
@@ -788,6 +978,10 @@ impl Actor for PendingTransactionActor {
             PendingTransactionMessage::Confirmed { .. } => {
                 todo!()
             }
+            PendingTransactionMessage::Drain => {
+                tracing::warn!("PendingTransactions drained, stopping");
+                myself.stop(Some("drained".to_string()));
+            }
         }
         Ok(())
     }
@@ -875,3 +1069,280 @@ impl Actor for PendingTransactionSupervisor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod pending_transactions_tests {
+    use super::{DependencyGraphs, PendingGraph, PreCallGraph};
+    use async_trait::async_trait;
+    use lasr_messages::{ActorType, SchedulerMessage};
+    use lasr_types::{
+        Account, AccountType, Address, PayloadBuilder, TokenDeltaBuilder, Transaction,
+        TransactionType, U256,
+    };
+    use ractor::{Actor, ActorProcessingErr, ActorRef};
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+    /// A scheduler stub that reports every transaction failure it is sent.
+    struct RecordingScheduler;
+
+    #[async_trait]
+    impl Actor for RecordingScheduler {
+        type Msg = SchedulerMessage;
+        type State = UnboundedSender<(String, String)>;
+        type Arguments = UnboundedSender<(String, String)>;
+
+        async fn pre_start(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            failures: Self::Arguments,
+        ) -> Result<Self::State, ActorProcessingErr> {
+            Ok(failures)
+        }
+
+        async fn handle(
+            &self,
+            _myself: ActorRef<Self::Msg>,
+            message: Self::Msg,
+            failures: &mut Self::State,
+        ) -> Result<(), ActorProcessingErr> {
+            if let SchedulerMessage::SendTransactionFailure {
+                transaction_hash,
+                error,
+            } = message
+            {
+                failures.send((transaction_hash, error.to_string()))?;
+            }
+            Ok(())
+        }
+    }
+
+    fn send_transaction(nonce: u64) -> Transaction {
+        send_transaction_from([1u8; 20], nonce)
+    }
+
+    fn send_transaction_from(from: [u8; 20], nonce: u64) -> Transaction {
+        PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(nonce)))
+            .from(from)
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(1))
+            .nonce(U256::from(nonce))
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_first_pending_transaction_is_indexed() {
+        let mut graph = PendingGraph::new();
+        let first = send_transaction(1);
+        graph.add_transaction(first.clone(), None);
+        for account in first.get_accounts_involved() {
+            assert_eq!(
+                graph.account_index.get(&account),
+                Some(&vec![first.hash_string()].into())
+            );
+        }
+
+        let second = send_transaction(2);
+        graph.add_transaction(second.clone(), None);
+        let first_vertex = graph.vertices.get(&first.hash_string()).unwrap();
+        assert_eq!(
+            first_vertex.read().unwrap().dependent_transactions,
+            vec![second.hash_string()]
+        );
+    }
+
+    #[test]
+    fn test_valid_transaction_releases_dependent() {
+        let mut graph = PendingGraph::new();
+        let first = send_transaction(1);
+        let second = send_transaction(2);
+        let third = send_transaction(3);
+        graph.add_transaction(first.clone(), None);
+        graph.add_transaction(second.clone(), None);
+        graph.add_transaction(third.clone(), None);
+
+        assert_eq!(
+            graph.handle_valid(&first.hash_string()),
+            vec![second.hash_string()]
+        );
+        assert_eq!(
+            graph.handle_valid(&second.hash_string()),
+            vec![third.hash_string()]
+        );
+        assert!(graph.handle_valid(&third.hash_string()).is_empty());
+    }
+
+    #[test]
+    fn test_pending_counts_track_inserts_and_confirms() {
+        let mut graph = PendingGraph::new();
+        let sender = Address::from([1u8; 20]);
+        let recipient = Address::from([2u8; 20]);
+        let other = Address::from([3u8; 20]);
+        assert_eq!(graph.pending_count(), 0);
+        assert_eq!(graph.pending_for(&sender), 0);
+
+        let first = send_transaction(1);
+        let second = send_transaction(2);
+        let third = send_transaction_from([3u8; 20], 1);
+        graph.add_transaction(first.clone(), None);
+        graph.add_transaction(second.clone(), None);
+        graph.add_transaction(third.clone(), None);
+        assert_eq!(graph.pending_count(), 3);
+        assert_eq!(graph.pending_for(&sender), 2);
+        assert_eq!(graph.pending_for(&recipient), 3);
+        assert_eq!(graph.pending_for(&other), 1);
+        assert_eq!(graph.pending_for(&Address::from([4u8; 20])), 0);
+
+        graph.handle_valid(&first.hash_string());
+        assert_eq!(graph.pending_count(), 2);
+        assert_eq!(graph.pending_for(&sender), 1);
+        assert_eq!(graph.pending_for(&recipient), 2);
+
+        graph.handle_valid(&third.hash_string());
+        graph.handle_valid(&second.hash_string());
+        assert_eq!(graph.pending_count(), 0);
+        assert_eq!(graph.pending_for(&sender), 0);
+        assert_eq!(graph.pending_for(&recipient), 0);
+        assert_eq!(graph.pending_for(&other), 0);
+    }
+
+    #[test]
+    fn test_conflicts_with_reports_shared_addresses() {
+        let mut graph = PendingGraph::new();
+        graph.add_transaction(send_transaction(1), None);
+
+        let overlapping = send_transaction_from([3u8; 20], 1);
+        assert_eq!(
+            graph.conflicts_with(&overlapping),
+            vec![Address::from([2u8; 20])]
+        );
+
+        let disjoint: Transaction = PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(1)))
+            .from([4u8; 20])
+            .to([5u8; 20])
+            .program_id([6u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(1))
+            .nonce(U256::from(1))
+            .build()
+            .unwrap()
+            .into();
+        assert!(graph.conflicts_with(&disjoint).is_empty());
+    }
+
+    #[test]
+    fn test_pending_send_reduces_available_balance() {
+        let mut graph = PendingGraph::new();
+        let sender = Address::from([1u8; 20]);
+        let program_id = Address::from([0u8; 20]);
+        let mut account = Account::new(AccountType::User, None, sender, None);
+        account
+            .apply_delta(
+                &program_id,
+                &TokenDeltaBuilder::default()
+                    .receive(U256::from(10))
+                    .send(U256::zero())
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            graph.available_balance(&account, &program_id),
+            U256::from(10)
+        );
+
+        graph.add_transaction(send_transaction(1), None);
+        assert_eq!(graph.pending_outflow(&sender, &program_id), U256::from(1));
+        assert_eq!(
+            graph.pending_outflow(&Address::from([2u8; 20]), &program_id),
+            U256::zero()
+        );
+        assert_eq!(
+            graph.pending_outflow(&sender, &Address::from([9u8; 20])),
+            U256::zero()
+        );
+        assert_eq!(
+            graph.available_balance(&account, &program_id),
+            U256::from(9)
+        );
+    }
+
+    #[test]
+    fn test_cancel_releases_dependents() {
+        let mut graph = PendingGraph::new();
+        let sender = Address::from([1u8; 20]);
+        let first = send_transaction(1);
+        let second = send_transaction(2);
+        graph.add_transaction(first.clone(), None);
+        graph.add_transaction(second.clone(), None);
+
+        assert_eq!(graph.cancel(&sender, U256::from(3)), None);
+        assert_eq!(
+            graph.cancel(&sender, U256::from(1)),
+            Some(vec![second.hash_string()])
+        );
+        assert_eq!(graph.pending_count(), 1);
+        assert_eq!(graph.pending_for(&sender), 1);
+        assert_eq!(graph.cancel(&sender, U256::from(1)), None);
+
+        assert_eq!(graph.cancel(&sender, U256::from(2)), Some(vec![]));
+        assert_eq!(graph.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_notifies_waiting_caller() {
+        let (failures_tx, mut failures_rx) = unbounded_channel();
+        let (scheduler, handle) = Actor::spawn(
+            Some(ActorType::Scheduler.to_string()),
+            RecordingScheduler,
+            failures_tx,
+        )
+        .await
+        .unwrap();
+        let mut graphs = DependencyGraphs::new();
+        let sender = Address::from([1u8; 20]);
+        let transaction = send_transaction(1);
+        graphs.add_transaction(transaction.clone(), None);
+
+        assert!(graphs.cancel(&sender, U256::from(1)));
+        assert!(!graphs.cancel(&sender, U256::from(1)));
+        assert_eq!(graphs.pending_count(), 0);
+
+        // other tests may report failures to the registered scheduler too
+        let error = loop {
+            let (transaction_hash, error) = failures_rx.recv().await.unwrap();
+            if transaction_hash == transaction.hash_string() {
+                break error;
+            }
+        };
+        assert_eq!(
+            error,
+            format!("transaction {} was cancelled", transaction.hash_string())
+        );
+
+        scheduler.stop(None);
+        handle.await.unwrap();
+    }
+
+    #[test]
+    fn test_missing_executor_is_reported() {
+        let mut graph = PreCallGraph::new();
+        let transaction = send_transaction(1);
+        graph.add_call(transaction.clone());
+
+        assert!(graph.send_to_executor(transaction.clone()).is_err());
+        assert!(graph
+            .handle_completed_exec(&transaction.hash_string())
+            .is_ok());
+        assert!(graph
+            .handle_completed_exec(&transaction.hash_string())
+            .is_err());
+    }
+}
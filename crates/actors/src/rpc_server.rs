@@ -122,12 +122,39 @@ impl LasrRpcServerActor {
             RpcRequestMethod::RegisterProgram { transaction } => {
                 LasrRpcServerActor::handle_register_program_request(scheduler, transaction, reply)
             }
+            RpcRequestMethod::BridgeIn { transaction }
+            | RpcRequestMethod::BridgeOut { transaction } => {
+                LasrRpcServerActor::handle_bridge_request(transaction, reply)
+            }
             RpcRequestMethod::GetAccount { address } => {
                 LasrRpcServerActor::handle_get_account_request(scheduler, address, reply)
             }
         }
     }
 
+    /// Bridge transactions are driven by the executable oracle, so there is
+    /// nothing to schedule for them yet; reply with an error instead of
+    /// leaving the caller waiting.
+    fn handle_bridge_request(
+        transaction: Transaction,
+        reply: RpcReplyPort<RpcMessage>,
+    ) -> Result<(), ActorProcessingErr> {
+        tracing::warn!(
+            "received {} request over RPC for transaction {}",
+            transaction.transaction_type().to_string(),
+            transaction.hash_string()
+        );
+        let response = Err(RpcResponseError {
+            description: "bridge transactions are not yet supported over RPC".to_string(),
+        });
+        Ok(reply
+            .send(RpcMessage::Response {
+                response,
+                reply: None,
+            })
+            .map_err(Box::new)?)
+    }
+
     fn get_scheduler() -> Result<Option<ActorRef<SchedulerMessage>>, RpcError> {
         if let Some(actor) = ractor::registry::where_is(ActorType::Scheduler.to_string()) {
             return Ok(Some(actor.into()));
@@ -156,7 +183,7 @@ impl LasrRpcServer for LasrRpcServerImpl {
 
         let handler = create_handler!(rpc_response, call);
 
-        match handle_actor_response(rx, handler)
+        match handle_actor_response(rx, ActorType::RpcServer, handler)
             .await
             .map_err(|e| RpcError::owned(INTERNAL_ERROR_CODE, format!("Error: {e}"), None::<()>))
         {
@@ -204,7 +231,7 @@ impl LasrRpcServer for LasrRpcServerImpl {
 
         let handler = create_handler!(rpc_response, send);
 
-        match handle_actor_response(rx, handler)
+        match handle_actor_response(rx, ActorType::RpcServer, handler)
             .await
             .map_err(|e| RpcError::owned(INTERNAL_ERROR_CODE, format!("Error: {e}"), None::<()>))
         {
@@ -251,7 +278,7 @@ impl LasrRpcServer for LasrRpcServerImpl {
 
         let handler = create_handler!(rpc_response, registerProgram);
 
-        match handle_actor_response(rx, handler)
+        match handle_actor_response(rx, ActorType::RpcServer, handler)
             .await
             .map_err(|e| RpcError::owned(INTERNAL_ERROR_CODE, format!("Error: {e}"), None::<()>))
         {
@@ -304,7 +331,7 @@ impl LasrRpcServer for LasrRpcServerImpl {
 
         let handler = create_handler!(rpc_response, getAccount);
 
-        match handle_actor_response(rx, handler)
+        match handle_actor_response(rx, ActorType::RpcServer, handler)
             .await
             .map_err(|e| RpcError::owned(INTERNAL_ERROR_CODE, format!("Error: {e}"), None::<()>))
         {
@@ -430,6 +457,9 @@ impl Actor for LasrRpcServerActor {
     ) -> Result<(), ActorProcessingErr> {
         tracing::info!("RPC Actor Received RPC Message");
         match message {
+            RpcMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::RpcServer);
+            }
             RpcMessage::Request { method, reply } => {
                 LasrRpcServerActor::handle_request_method(*method, reply)?
             }
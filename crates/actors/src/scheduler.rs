@@ -161,6 +161,9 @@ impl Actor for TaskScheduler {
         state: &mut Self::State,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            SchedulerMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Scheduler);
+            }
             SchedulerMessage::Call {
                 transaction,
                 rpc_reply,
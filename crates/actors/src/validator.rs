@@ -84,25 +84,16 @@ impl ValidatorCore {
                     )) as Box<dyn std::error::Error + Send>)?
                     .into();
 
-            match (
-                account.validate_program_id(&tx.program_id()),
-                account.validate_balance(&tx.program_id(), tx.value()),
-                account.validate_nonce(tx.nonce()),
-                tx.verify_signature()
-                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>),
-            ) {
-                (Err(e), _, _, _) | (_, Err(e), _, _) | (_, _, Err(e), _) | (_, _, _, Err(e)) => {
-                    let error_string = e.to_string();
-                    let message = PendingTransactionMessage::Invalid {
-                        transaction: tx.clone(),
-                        e,
-                    };
-                    let _ = pending_transactions.cast(message);
-                    tracing::error!("{}", &error_string);
-                    return Err(Box::new(ValidatorError::Custom(error_string))
-                        as Box<dyn std::error::Error + Send>);
-                }
-                _ => {}
+            if let Err(e) = tx.validate(&account) {
+                let error_string = e.to_string();
+                let message = PendingTransactionMessage::Invalid {
+                    transaction: tx.clone(),
+                    e: Box::new(e),
+                };
+                let _ = pending_transactions.cast(message);
+                tracing::error!("{}", &error_string);
+                return Err(Box::new(ValidatorError::Custom(error_string))
+                    as Box<dyn std::error::Error + Send>);
             }
 
             let batcher: ActorRef<BatcherMessage> =
@@ -176,7 +167,7 @@ impl ValidatorCore {
                 let error_string = e.to_string();
                 let message = PendingTransactionMessage::Invalid {
                     transaction: tx.clone(),
-                    e,
+                    e: Box::new(e),
                 };
                 let _ = pending_transactions.cast(message);
                 return Err(Box::new(ValidatorError::Custom(error_string))
@@ -253,7 +244,7 @@ impl ValidatorCore {
                                     let error_string = e.to_string();
                                     let message = PendingTransactionMessage::Invalid {
                                         transaction: tx.clone(),
-                                        e,
+                                        e: Box::new(e),
                                     };
                                     let _ = pending_transactions.cast(message);
                                     return Err(Box::new(ValidatorError::Custom(error_string))
@@ -511,7 +502,7 @@ impl ValidatorCore {
                                     let error_string = e.to_string();
                                     let message = PendingTransactionMessage::Invalid {
                                         transaction: tx.clone(),
-                                        e,
+                                        e: Box::new(e),
                                     };
                                     let _ = pending_transactions.cast(message);
                                     return Err(Box::new(ValidatorError::Custom(error_string))
@@ -584,7 +575,7 @@ impl ValidatorCore {
                                     let error_string = e.to_string();
                                     let message = PendingTransactionMessage::Invalid {
                                         transaction: tx.clone(),
-                                        e,
+                                        e: Box::new(e),
                                     };
                                     let _ = pending_transactions.cast(message);
                                     return Err(Box::new(ValidatorError::Custom(error_string))
@@ -1186,6 +1177,9 @@ impl Actor for ValidatorActor {
     ) -> Result<(), ActorProcessingErr> {
         let valcore_ptr = Arc::clone(state);
         match message {
+            ValidatorMessage::Ping { reply } => {
+                let _ = reply.send(ActorType::Validator);
+            }
             ValidatorMessage::PendingTransaction { transaction } => {
                 let fut = ValidatorActor::pending_transaction(valcore_ptr, transaction);
                 let guard = self.future_pool.lock().await;
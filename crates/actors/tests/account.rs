@@ -7,12 +7,12 @@ use std::{
 };
 
 use lasr_actors::{
-    get_account, get_actor_ref, AccountCacheActor, AccountCacheError, Batcher, BatcherActor,
-    PendingTransactionActor, TaskScheduler, ETH_ADDR,
+    get_account, get_actor_ref, shutdown_account_cache, AccountCacheActor, AccountCacheError,
+    Batcher, BatcherActor, PendingTransactionActor, TaskScheduler, ETH_ADDR,
 };
 use lasr_messages::{
     AccountCacheMessage, ActorName, ActorType, BatcherMessage, PendingTransactionMessage,
-    SchedulerMessage,
+    SchedulerMessage, Shutdown,
 };
 use lasr_types::{
     Account, AccountBuilder, AccountType, Address, AddressOrNamespace, ArbitraryData,
@@ -929,3 +929,49 @@ async fn call_burn_event() {
         .await
         .unwrap();
 }
+
+#[serial]
+#[tokio::test]
+async fn account_cache_drain_flushes_queued_writes() {
+    let mock_storage = <MockPersistenceStore<String, Vec<u8>> as PersistenceStore>::new()
+        .await
+        .unwrap();
+    let account_cache_actor = AccountCacheActor::new();
+    let (account_cache, handle) = Actor::spawn(
+        Some(account_cache_actor.name()),
+        account_cache_actor,
+        mock_storage,
+    )
+    .await
+    .unwrap();
+
+    let accounts: Vec<Account> = (1..=16u8)
+        .map(|n| Account::new(AccountType::User, None, Address::new([n; 20]), None))
+        .collect();
+    for account in accounts.iter() {
+        account_cache
+            .cast(AccountCacheMessage::Write {
+                account: account.clone(),
+                who: ActorType::Batcher,
+                location: "account_cache_drain_flushes_queued_writes".to_string(),
+            })
+            .unwrap();
+    }
+    let (tx, rx) = ractor::concurrency::oneshot();
+    account_cache
+        .cast(AccountCacheMessage::ReadMany {
+            addresses: accounts.iter().map(|a| a.owner_address()).collect(),
+            tx,
+            who: ActorType::Batcher,
+        })
+        .unwrap();
+
+    shutdown_account_cache(Shutdown::Drain).unwrap();
+    handle.await.unwrap();
+
+    let cached = rx.await.unwrap();
+    assert_eq!(cached.len(), accounts.len());
+    for ((_, cached), account) in cached.into_iter().zip(accounts) {
+        assert_eq!(cached, Some(account));
+    }
+}
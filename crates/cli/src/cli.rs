@@ -19,9 +19,9 @@ use lasr_compute::{
 };
 use lasr_rpc::LasrRpcClient;
 use lasr_types::{
-    Account, AccountType, Address, BurnInstruction, CreateInstruction, HexOr20Bytes, HexOr32Bytes,
-    Instruction, Namespace, Outputs, PayloadBuilder, Transaction, TransferInstruction,
-    UpdateInstruction, U256,
+    parse_amount, Account, AccountType, Address, BurnInstruction, CreateInstruction, HexOr20Bytes,
+    HexOr32Bytes, Instruction, Namespace, Outputs, ParseAmountError, PayloadBuilder, Transaction,
+    TransferInstruction, UpdateInstruction, U256,
 };
 use lasr_wallet::{Wallet, WalletBuilder, WalletInfo};
 use secp256k1::PublicKey;
@@ -59,17 +59,9 @@ enum PackageType {
 struct U256Wrapper(pub U256);
 
 impl FromStr for U256Wrapper {
-    type Err = uint::FromDecStrErr;
+    type Err = ParseAmountError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("0x") {
-            Ok(U256Wrapper(
-                ethereum_types::U256::from_str_radix(&s[2..], 16)
-                    .expect("failed to convert hex string to U256")
-                    .into(),
-            ))
-        } else {
-            Ok(U256Wrapper(ethereum_types::U256::from_dec_str(s)?.into()))
-        }
+        Ok(U256Wrapper(parse_amount(s)?))
     }
 }
 
@@ -1,10 +1,75 @@
 use lasr_types::{Address, Transaction};
 
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::OnceLock};
 use tokio::time::Duration;
 
 pub const TIMEOUT_DURATION: Duration = tokio::time::Duration::from_millis(200);
 
+/// How long to wait for a reply from an actor whose type has no timeout of
+/// its own in [`Timeouts`].
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(15);
+
+static TIMEOUTS: OnceLock<Timeouts> = OnceLock::new();
+
+/// How long to wait for a reply from each type of actor.
+///
+/// Dispersing a blob to the DA layer takes far longer than an account cache
+/// lookup, so each `ActorType` can be given its own timeout. Types without
+/// one fall back to the default timeout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeouts {
+    default: Duration,
+    overrides: BTreeMap<ActorType, Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_RESPONSE_TIMEOUT,
+            overrides: BTreeMap::from([
+                (ActorType::AccountCache, Duration::from_secs(5)),
+                (ActorType::DaClient, Duration::from_secs(60)),
+            ]),
+        }
+    }
+}
+
+impl Timeouts {
+    /// The timeout for replies from `actor_type`.
+    pub fn get(&self, actor_type: &ActorType) -> Duration {
+        self.overrides
+            .get(actor_type)
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Sets the timeout for replies from `actor_type`.
+    pub fn with_timeout(mut self, actor_type: ActorType, timeout: Duration) -> Self {
+        self.overrides.insert(actor_type, timeout);
+        self
+    }
+
+    /// Sets the timeout used for actor types without one of their own.
+    pub fn with_default(mut self, timeout: Duration) -> Self {
+        self.default = timeout;
+        self
+    }
+
+    /// Makes these the timeouts returned by [`Timeouts::global`]. This can
+    /// only be done once, and must happen before any actor reads them;
+    /// otherwise `self` is handed back.
+    pub fn install(self) -> Result<(), Timeouts> {
+        TIMEOUTS.set(self)
+    }
+
+    /// The timeouts in use by every actor, the defaults unless others were
+    /// installed at startup.
+    pub fn global() -> &'static Timeouts {
+        TIMEOUTS.get_or_init(Timeouts::default)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialOrd, Ord, PartialEq, Eq)]
 pub enum ActorType {
     Registry,
@@ -112,5 +177,22 @@ pub enum RpcRequestMethod {
     Call { transaction: Transaction },
     Send { transaction: Transaction },
     RegisterProgram { transaction: Transaction },
+    BridgeIn { transaction: Transaction },
+    BridgeOut { transaction: Transaction },
     GetAccount { address: Address },
 }
+
+impl RpcRequestMethod {
+    /// The transaction carried by the request, `None` for requests that
+    /// only read state.
+    pub fn transaction(&self) -> Option<&Transaction> {
+        match self {
+            RpcRequestMethod::Call { transaction }
+            | RpcRequestMethod::Send { transaction }
+            | RpcRequestMethod::RegisterProgram { transaction }
+            | RpcRequestMethod::BridgeIn { transaction }
+            | RpcRequestMethod::BridgeOut { transaction } => Some(transaction),
+            RpcRequestMethod::GetAccount { .. } => None,
+        }
+    }
+}
@@ -12,6 +12,7 @@ use ractor::RpcReplyPort;
 use ractor_cluster::RactorMessage;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::str::FromStr;
 #[cfg(not(feature = "mock_storage"))]
 use tikv_client::RawClient as TikvClient;
 use web3::ethabi::{Address as EthereumAddress, FixedBytes};
@@ -45,6 +46,9 @@ pub enum TransactionResponse {
 /// A message type that the RpcServer Actor can `handle`
 #[derive(Debug, RactorMessage)]
 pub enum RpcMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Request {
         method: Box<RpcRequestMethod>,
         reply: RpcReplyPort<RpcMessage>,
@@ -58,6 +62,9 @@ pub enum RpcMessage {
 /// Message types that the `Scheduler` actor can `handle`
 #[derive(Debug, RactorMessage)]
 pub enum SchedulerMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Call {
         transaction: Transaction,
         rpc_reply: RpcReplyPort<RpcMessage>,
@@ -124,8 +131,11 @@ pub enum SchedulerMessage {
 }
 
 /// A message type that the `Validator` actor can handle
-#[derive(Debug, Clone, RactorMessage)]
+#[derive(Debug, RactorMessage)]
 pub enum ValidatorMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     PendingTransaction {
         transaction: Transaction,
     },
@@ -141,6 +151,9 @@ pub enum ValidatorMessage {
 /// A message type that the Engine can `handle`
 #[derive(Debug, RactorMessage)]
 pub enum EngineMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Call {
         transaction: Transaction,
     },
@@ -262,6 +275,9 @@ pub enum HashOrError {
 /// A message type that the `EoServer` can `handle
 #[derive(Debug, RactorMessage)]
 pub enum EoMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Log {
         log: Vec<web3::ethabi::Log>,
         log_type: EventType,
@@ -334,17 +350,70 @@ pub enum EoMessage {
     CommTest,
 }
 
+/// The id EigenDA assigns to a dispersed blob, used to poll for the blob's
+/// status. Request ids are base64 encoded, so parsing rejects empty strings and
+/// any character outside the base64 alphabets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestId(String);
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RequestIdError {
+    #[error("request id is empty")]
+    Empty,
+
+    #[error("request id contains invalid character {0:?}")]
+    InvalidCharacter(char),
+}
+
+impl RequestId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for RequestId {
+    type Err = RequestIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(RequestIdError::Empty);
+        }
+        if let Some(c) = s
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_' | '=')))
+        {
+            return Err(RequestIdError::InvalidCharacter(c));
+        }
+        Ok(RequestId(s.to_string()))
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// Message types that the `DaClient` can `handle
 #[derive(Debug, RactorMessage)]
 pub enum DaClientMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     StoreBatch {
         batch: String,
         tx: OneshotSender<Result<BlobResponse, std::io::Error>>,
     },
     StoreTransactionBlob,
     ValidateBlob {
-        request_id: String,
-        tx: OneshotSender<(String /*request_id*/, BlobVerificationProof)>,
+        request_id: RequestId,
+        tx: OneshotSender<(RequestId, BlobVerificationProof)>,
+    },
+    /// Validates several blobs dispersed under the same batch header at
+    /// once, replying to each blob on its own channel.
+    ValidateBlobBatch {
+        batch_header_hash: H256,
+        blobs: Vec<(RequestId, OneshotSender<(RequestId, BlobVerificationProof)>)>,
     },
     RetrieveAccount {
         address: Address,
@@ -370,18 +439,44 @@ pub enum DaClientMessage {
     CommTest,
 }
 
+/// How a cache actor should stop when asked to shut down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shutdown {
+    /// Process every message already queued, then stop.
+    Drain,
+    /// Stop without processing queued messages.
+    Immediate,
+}
+
 #[derive(Debug, RactorMessage)]
 pub enum AccountCacheMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Write {
         account: Account,
         who: ActorType,
         location: String,
     },
+    /// Writes several accounts in a single message, for example every
+    /// account touched by a block of transactions.
+    WriteBatch {
+        accounts: Vec<Account>,
+        who: ActorType,
+        location: String,
+    },
     Read {
         address: Address,
         tx: OneshotSender<Option<Account>>,
         who: ActorType,
     },
+    /// Reads several accounts in a single round trip, replying with one
+    /// entry per requested address in the order requested.
+    ReadMany {
+        addresses: Vec<Address>,
+        tx: OneshotSender<Vec<(Address, Option<Account>)>>,
+        who: ActorType,
+    },
     Remove {
         address: Address,
     },
@@ -392,21 +487,43 @@ pub enum AccountCacheMessage {
         address: Address,
         reply: RpcReplyPort<RpcMessage>,
     },
+    /// Writes the cache to its configured snapshot file, if any.
+    Snapshot,
+    /// Stops the actor once every message queued ahead of it is handled.
+    Drain,
 }
 
 #[derive(Debug, RactorMessage)]
 pub enum BlobCacheMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Cache {
         blob_response: BlobResponse,
         accounts: HashSet<Address>,
         transactions: HashSet<Transaction>,
+        /// The batch header the blob was dispersed under, if already known,
+        /// so it is validated together with the other blobs under it.
+        batch_header_hash: Option<H256>,
     },
     Get,
     Remove,
+    /// Sends every held back batch whose window has elapsed for validation.
+    FlushBatches,
+    /// Handles every blob validation that has already resolved.
+    PollValidations,
+    /// Retries the validation requests that could not be delivered to the
+    /// DA client.
+    RetryUnsent,
+    /// Stops the actor once every message queued ahead of it is handled.
+    Drain,
 }
 
 #[derive(Debug, RactorMessage)]
 pub enum PendingTransactionMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     New {
         transaction: Transaction,
         outputs: Option<Outputs>,
@@ -434,16 +551,28 @@ pub enum PendingTransactionMessage {
         transaction_hash: String,
         sender: OneshotSender<Option<Transaction>>,
     },
+    /// Cancels the pending transaction sent by `address` with `nonce`,
+    /// replying with whether a matching transaction was pending.
+    Cancel {
+        address: Address,
+        nonce: U256,
+        sender: OneshotSender<bool>,
+    },
     ValidCall {
         outputs: Outputs,
         transaction: Transaction,
         cert: Option<Certificate>,
     },
     CleanGraph,
+    /// Stops the actor once every message queued ahead of it is handled.
+    Drain,
 }
 
 #[derive(RactorMessage)]
 pub enum BatcherMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     AppendTransaction {
         transaction: Transaction,
         outputs: Option<Outputs>,
@@ -462,6 +591,9 @@ pub enum BatcherMessage {
 
 #[derive(Debug, RactorMessage)]
 pub enum ExecutorMessage {
+    Ping {
+        reply: OneshotSender<ActorType>,
+    },
     Retrieve {
         transaction: Transaction,
         content_id: String,
@@ -490,3 +622,34 @@ pub enum ExecutorMessage {
         job_id: uuid::Uuid,
     },
 }
+
+#[cfg(test)]
+mod messages_tests {
+    use super::{RequestId, RequestIdError};
+
+    #[test]
+    fn test_request_id_round_trip() {
+        let id = "ZjBkMmE3YjEtNDg2Yy00ZjI3LThmNmQ=";
+        let request_id: RequestId = id.parse().unwrap();
+        assert_eq!(request_id.as_str(), id);
+        assert_eq!(request_id.to_string(), id);
+        assert!("url-safe_id".parse::<RequestId>().is_ok());
+    }
+
+    #[test]
+    fn test_malformed_request_ids_are_rejected() {
+        assert_eq!("".parse::<RequestId>(), Err(RequestIdError::Empty));
+        assert_eq!(
+            "has space".parse::<RequestId>(),
+            Err(RequestIdError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            "line\nbreak".parse::<RequestId>(),
+            Err(RequestIdError::InvalidCharacter('\n'))
+        );
+        assert_eq!(
+            "0x{id}".parse::<RequestId>(),
+            Err(RequestIdError::InvalidCharacter('{'))
+        );
+    }
+}
@@ -5,14 +5,15 @@ use eo_listener::{BlocksProcessed, EoServer as EoListener, EoServerError};
 use futures::StreamExt;
 use jsonrpsee::server::ServerBuilder as RpcServerBuilder;
 use lasr_actors::{
-    graph_cleaner, helpers::Coerce, AccountCacheActor, AccountCacheSupervisor, ActorExt,
-    ActorManager, ActorManagerBuilder, Batcher, BatcherActor, BatcherError, BatcherSupervisor,
-    BlobCacheActor, BlobCacheSupervisor, DaClient, DaClientActor, DaClientSupervisor, EngineActor,
-    EngineSupervisor, EoClient, EoClientActor, EoClientSupervisor, EoServerActor,
-    EoServerSupervisor, EoServerWrapper, ExecutionEngine, ExecutorActor, ExecutorSupervisor,
-    LasrRpcServerActor, LasrRpcServerImpl, LasrRpcServerSupervisor, PendingTransactionActor,
-    PendingTransactionSupervisor, StorageRef, TaskScheduler, TaskSchedulerSupervisor,
-    ValidatorActor, ValidatorCore, ValidatorSupervisor, STORAGE_PROCESSED_BLOCKS_KEY,
+    account_cache_snapshotter, graph_cleaner, helpers::Coerce, AccountCacheActor,
+    AccountCacheSupervisor, ActorExt, ActorManager, ActorManagerBuilder, Batcher, BatcherActor,
+    BatcherError, BatcherSupervisor, BlobCacheActor, BlobCacheSupervisor, DaClient, DaClientActor,
+    DaClientSupervisor, EngineActor, EngineSupervisor, EoClient, EoClientActor, EoClientSupervisor,
+    EoServerActor, EoServerSupervisor, EoServerWrapper, ExecutionEngine, ExecutorActor,
+    ExecutorSupervisor, LasrRpcServerActor, LasrRpcServerImpl, LasrRpcServerSupervisor,
+    PendingTransactionActor, PendingTransactionSupervisor, StorageRef, TaskScheduler,
+    TaskSchedulerSupervisor, ValidatorActor, ValidatorCore, ValidatorSupervisor,
+    STORAGE_PROCESSED_BLOCKS_KEY,
 };
 use lasr_compute::{OciBundler, OciBundlerBuilder, OciManager};
 use lasr_messages::{ActorName, ActorType, ToActorType};
@@ -444,6 +445,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (_stop_tx, stop_rx) = tokio::sync::mpsc::channel(1);
 
     tokio::spawn(graph_cleaner());
+    tokio::spawn(account_cache_snapshotter());
     tokio::spawn(eo_server_wrapper.run(
         env.blocks_processed_path.to_string(),
         persistence_storage.clone(),
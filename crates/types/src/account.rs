@@ -1,36 +1,142 @@
 use crate::{
-    AddressOrNamespace, ArbitraryData, DataValue, Metadata, MetadataValue, ProgramUpdate, Status,
-    ToTokenError, Token, TokenBuilder, TokenUpdateField, Transaction,
+    AddressOrNamespace, ArbitraryData, BalanceError, Certificate, DataValue, Metadata,
+    MetadataValue, ProgramUpdate, Status, ToTokenError, Token, TokenBuilder, TokenDelta,
+    TokenDeltaBuilder, TokenUpdateField, Transaction, TxKind,
 };
 use derive_builder::Builder;
-use hex::{FromHexError, ToHex};
+use hex::FromHexError;
 use schemars::JsonSchema;
 use secp256k1::PublicKey;
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use sha3::{Digest, Keccak256};
+use sha3::{Digest, Keccak256, Sha3_256};
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{Debug, Display, LowerHex},
     hash::Hash,
+    ops::{Deref, DerefMut},
     str::FromStr,
+    sync::OnceLock,
 };
-
-pub type AccountError = std::io::Error;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum AccountError {
+    #[error("account does not have associated program: {}", .0.to_full_string())]
+    UnknownProgram(Address),
+
+    #[error(
+        "account balance insufficient for program {}: needed {needed}, available {available}",
+        program.to_full_string()
+    )]
+    InsufficientBalance {
+        program: Address,
+        needed: crate::U256,
+        available: crate::U256,
+    },
+
+    #[error("invalid nonce: expected {expected}, found {found}")]
+    InvalidNonce {
+        expected: crate::U256,
+        found: crate::U256,
+    },
+
+    #[error("account nonce is exhausted")]
+    NonceExhausted,
+
+    #[error(
+        "allowance insufficient for spender {}: needed {needed}, available {available}",
+        spender.to_full_string()
+    )]
+    InsufficientAllowance {
+        spender: Address,
+        needed: crate::U256,
+        available: crate::U256,
+    },
+
+    #[error("token id {0} is already owned")]
+    DuplicateTokenId(crate::U256),
+
+    #[error("token id {0} is not owned")]
+    UnknownTokenId(crate::U256),
+
+    #[error("transaction is sent by {}, not by this account", .0.to_full_string())]
+    NotSender(Address),
+
+    #[error("transactions of kind {0:?} cannot be applied to an account")]
+    UnsupportedTransaction(TxKind),
+
+    #[error(
+        "cannot combine token for program {} with token for program {}",
+        expected.to_full_string(),
+        found.to_full_string()
+    )]
+    ProgramMismatch { expected: Address, found: Address },
+
+    #[error("account has no certificate attached")]
+    MissingCertificate,
+
+    #[error("account certificate holds an unusable signature: {0}")]
+    InvalidCertificate(String),
+
+    #[error("account certificate needs {required} validator signatures but has {found}")]
+    InsufficientAttestations { required: usize, found: usize },
+
+    #[error(
+        "cannot split token for program {} while it holds token ids",
+        .0.to_full_string()
+    )]
+    NonFungibleSplit(Address),
+
+    #[error(
+        "cannot add program {}: account already holds the maximum of {max} programs",
+        program.to_full_string()
+    )]
+    TooManyPrograms { program: Address, max: usize },
+
+    #[error(transparent)]
+    Balance(#[from] BalanceError),
+}
 
 pub type AccountResult<T> = Result<T, Box<dyn std::error::Error + Send>>;
 
+/// The number of programs an account may hold when `MAX_PROGRAMS_PER_ACCOUNT`
+/// is not set.
+pub const DEFAULT_MAX_PROGRAMS: usize = 1024;
+
+/// The most programs a single account may hold. Caps how far an account,
+/// and so the blobs and caches holding it, can be grown with zero balance
+/// programs. Read once from `MAX_PROGRAMS_PER_ACCOUNT`, falling back to
+/// [`DEFAULT_MAX_PROGRAMS`], and fixed for the rest of the process; the
+/// `_with_limit` methods take the cap explicitly instead.
+pub fn max_programs() -> usize {
+    static MAX_PROGRAMS: OnceLock<usize> = OnceLock::new();
+    *MAX_PROGRAMS.get_or_init(|| {
+        std::env::var("MAX_PROGRAMS_PER_ACCOUNT")
+            .ok()
+            .and_then(|max| max.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_PROGRAMS)
+    })
+}
+
+/// Human readable formats such as JSON encode the address as a `0x`
+/// prefixed hex string, while compact binary formats such as bincode encode
+/// it as its raw 20 bytes.
 impl Serialize for Address {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let hex_string = hex::encode(self.inner());
-        serializer.serialize_str(&format!("0x{}", hex_string))
+        if serializer.is_human_readable() {
+            let hex_string = hex::encode(self.inner());
+            serializer.serialize_str(&format!("0x{}", hex_string))
+        } else {
+            self.0.serialize(serializer)
+        }
     }
 }
 
-struct AddressVisitor;
+pub(crate) struct AddressVisitor;
 
 impl<'de> Visitor<'de> for AddressVisitor {
     type Value = Address;
@@ -78,10 +184,26 @@ impl<'de> Deserialize<'de> for Address {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(AddressVisitor)
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(AddressVisitor)
+        } else {
+            <[u8; 20]>::deserialize(deserializer).map(Address)
+        }
     }
 }
 
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("invalid address length: expected 40 hex characters, found {0}")]
+    InvalidLength(usize),
+
+    #[error("invalid hex in address: {0}")]
+    InvalidHex(#[from] FromHexError),
+
+    #[error("invalid address checksum, expected {expected}")]
+    InvalidChecksum { expected: String },
+}
+
 /// Represents a 20-byte Ethereum Compatible address.
 ///
 /// This structure is used to store Ethereum Compatible addresses, which are
@@ -98,6 +220,10 @@ impl std::fmt::Debug for Address {
 }
 
 impl Address {
+    /// The all zero address, used as a sentinel for mints, burns and the
+    /// native ETH program.
+    pub const ZERO: Address = Address([0; 20]);
+
     /// Creates a new address from a 20 byte array
     pub const fn verse_addr() -> Address {
         let mut inner = [0; 20];
@@ -113,25 +239,27 @@ impl Address {
         Address(bytes)
     }
 
-    /// Converts the inner Address to a full hexadecimal string
-    /// this exists because in the Disply implementation we abbreviate the
-    /// address
+    /// Converts the address to its full lowercase hexadecimal string,
+    /// prefixed with `0x`. Unlike the `Display` implementation, which
+    /// renders the EIP-55 checksum, every letter is lowercase.
     pub fn to_full_string(&self) -> String {
         format!("0x{:x}", self)
     }
 
-    pub fn from_hex(hex_str: &str) -> Result<Self, FromHexError> {
+    /// Decodes an address from a 40 character hex string with an optional
+    /// `0x` prefix. Unlike `FromStr`, no checksum validation is performed.
+    pub fn from_hex(hex_str: &str) -> Result<Self, AddressError> {
         let hex_str = if let Some(v) = hex_str.strip_prefix("0x") {
             v
         } else {
             hex_str
         };
         let bytes = hex::decode(hex_str)?;
-        let mut addr_inner = [0u8; 20];
         if bytes.len() != 20 {
-            return Err(FromHexError::OddLength);
+            return Err(AddressError::InvalidLength(hex_str.len()));
         }
 
+        let mut addr_inner = [0u8; 20];
         addr_inner.copy_from_slice(&bytes[..]);
         Ok(Address(addr_inner))
     }
@@ -139,15 +267,61 @@ impl Address {
     pub fn inner(&self) -> [u8; 20] {
         self.0
     }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Converts the address to its EIP-55 mixed-case checksum string,
+    /// prefixed with `0x`.
+    ///
+    /// Each alphabetic hex character is uppercased when the corresponding
+    /// nibble of the Keccak256 hash of the lowercase hex string is >= 8.
+    pub fn to_checksum(&self) -> String {
+        let lower = format!("{:x}", self);
+        let hash = Keccak256::digest(lower.as_bytes());
+
+        let mut checksummed = String::with_capacity(42);
+        checksummed.push_str("0x");
+        for (i, c) in lower.chars().enumerate() {
+            let nibble = if i % 2 == 0 {
+                hash[i / 2] >> 4
+            } else {
+                hash[i / 2] & 0x0f
+            };
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                checksummed.push(c.to_ascii_uppercase());
+            } else {
+                checksummed.push(c);
+            }
+        }
+
+        checksummed
+    }
 }
 
+/// Domain tag prepended to the preimage of every [`AccountHash`], so account
+/// hashes cannot collide with the hashes of other types.
+pub const ACCOUNT_HASH_DOMAIN: &[u8] = b"LASR_ACCOUNT_V2";
+
 /// Represents a 32-byte account hash.
 ///
 /// This structure is used to store current state hash associated with an account
-// It supports standard traits for easy handling and
+/// It supports standard traits for easy handling and
 /// comparison operations.
 #[derive(
-    Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash,
+    Clone,
+    Copy,
+    Debug,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
 )]
 #[serde(rename_all = "camelCase")]
 pub struct AccountHash([u8; 32]);
@@ -159,6 +333,51 @@ impl AccountHash {
     pub fn new(hash: [u8; 32]) -> Self {
         Self(hash)
     }
+
+    pub fn inner(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum AccountHashError {
+    #[error("invalid account hash length: expected 64 hex characters, found {0}")]
+    InvalidLength(usize),
+
+    #[error("invalid hex in account hash: {0}")]
+    InvalidHex(#[from] FromHexError),
+}
+
+impl LowerHex for AccountHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the hash as a `0x` prefixed lowercase hex string.
+impl Display for AccountHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:x}", self)
+    }
+}
+
+impl FromStr for AccountHash {
+    type Err = AccountHashError;
+    /// Parses a hash from either a `0x` prefixed or bare 64 character hex
+    /// string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex_str = s.strip_prefix("0x").unwrap_or(s);
+        if hex_str.len() != 64 {
+            return Err(AccountHashError::InvalidLength(hex_str.len()));
+        }
+
+        let mut inner = [0u8; 32];
+        hex::decode_to_slice(hex_str, &mut inner)?;
+        Ok(AccountHash(inner))
+    }
 }
 
 /// This is currently not used
@@ -328,6 +547,42 @@ pub struct Account {
     program_account_data: ArbitraryData,
     program_account_metadata: Metadata,
     program_account_linked_programs: BTreeSet<AddressOrNamespace>,
+    #[builder(default)]
+    #[serde(default)]
+    account_hash: AccountHash,
+    /// Signatures from a validator set attesting to the account hash.
+    #[builder(default)]
+    #[serde(default)]
+    certificate: Option<Certificate>,
+}
+
+/// A mutable borrow of one field of an [`Account`], returned by its `_mut`
+/// accessors. The account hash is recomputed when the borrow is dropped, so
+/// changes made through it can't leave the stored hash stale.
+pub struct AccountFieldMut<'a, T> {
+    account: &'a mut Account,
+    field: fn(&Account) -> &T,
+    field_mut: fn(&mut Account) -> &mut T,
+}
+
+impl<T> Deref for AccountFieldMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        (self.field)(self.account)
+    }
+}
+
+impl<T> DerefMut for AccountFieldMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        (self.field_mut)(self.account)
+    }
+}
+
+impl<T> Drop for AccountFieldMut<'_, T> {
+    fn drop(&mut self) {
+        self.account.update_hash();
+    }
 }
 
 impl Account {
@@ -339,18 +594,127 @@ impl Account {
         account_type: AccountType,
         program_namespace: Option<AddressOrNamespace>,
         owner_address: Address,
-        _programs: Option<BTreeMap<Address, Token>>,
+        programs: Option<BTreeMap<Address, Token>>,
     ) -> Self {
-        Self {
+        let mut account = Self {
             account_type,
             program_namespace,
             owner_address,
-            programs: BTreeMap::new(),
+            programs: programs.unwrap_or_default(),
             nonce: crate::U256::default(),
             program_account_data: ArbitraryData::new(),
             program_account_metadata: Metadata::new(),
             program_account_linked_programs: BTreeSet::new(),
+            account_hash: AccountHash::default(),
+            certificate: None,
+        };
+        account.update_hash();
+        account
+    }
+
+    /// Decodes an account bincode encoded in one of the layouts used before
+    /// addresses were encoded as raw bytes: the original layout, whose hash is
+    /// computed on decode, or that layout followed by the account hash.
+    pub fn decode_legacy_bincode(bytes: &[u8]) -> bincode::Result<Account> {
+        crate::legacy::decode_account(bytes)
+    }
+
+    /// The certificate attesting to the account hash, if one is attached.
+    pub fn certificate(&self) -> Option<&Certificate> {
+        self.certificate.as_ref()
+    }
+
+    /// Attaches `certificate` to the account, returning the one it replaces.
+    /// The certificate is not part of the account hash.
+    pub fn attach_certificate(&mut self, certificate: Certificate) -> Option<Certificate> {
+        self.certificate.replace(certificate)
+    }
+
+    /// Checks that the attached certificate carries signatures over the
+    /// current account hash from at least `required` distinct addresses in
+    /// `validators`. Signatures from other addresses, including ones made
+    /// over an earlier state of the account, do not count.
+    pub fn verify_certificate(
+        &self,
+        validators: &[Address],
+        required: usize,
+    ) -> Result<(), AccountError> {
+        let certificate = self
+            .certificate
+            .as_ref()
+            .ok_or(AccountError::MissingCertificate)?;
+        let hash = self.compute_hash().inner();
+        let mut attested = BTreeSet::new();
+        for sig in certificate.quorum_sigs() {
+            secp256k1::ecdsa::RecoverableSignature::try_from(sig)
+                .map_err(|e| AccountError::InvalidCertificate(e.to_string()))?;
+            let signer = sig
+                .recover(&hash)
+                .map_err(|e| AccountError::InvalidCertificate(e.to_string()))?;
+            if validators.contains(&signer) {
+                attested.insert(signer);
+            }
+        }
+        if attested.len() < required {
+            return Err(AccountError::InsufficientAttestations {
+                required,
+                found: attested.len(),
+            });
         }
+        Ok(())
+    }
+
+    /// The hash of the account state as of the last mutation.
+    pub fn hash(&self) -> AccountHash {
+        self.account_hash
+    }
+
+    /// Computes the Sha3-256 hash of [`ACCOUNT_HASH_DOMAIN`], the owner
+    /// address, every program held by the account (in address order), the
+    /// account nonce and then the bincode encoding of the account type,
+    /// program namespace, program account data, metadata and linked
+    /// programs. Every field of the account except the stored hash and the
+    /// certificate is covered.
+    ///
+    /// Tokens are hashed in their canonical form, so the hash does not depend
+    /// on the order token ids were added in.
+    pub fn compute_hash(&self) -> AccountHash {
+        let mut hasher = Sha3_256::new();
+        hasher.update(ACCOUNT_HASH_DOMAIN);
+        hasher.update(self.owner_address.inner());
+        for (program_id, token) in &self.programs {
+            hasher.update(program_id.inner());
+            let bytes = if token.is_canonical() {
+                bincode::serialize(token)
+            } else {
+                let mut token = token.clone();
+                token.canonicalize();
+                bincode::serialize(&token)
+            };
+            hasher.update(bytes.expect("tokens always serialize"));
+        }
+        let mut nonce = [0u8; 32];
+        self.nonce.to_big_endian(&mut nonce);
+        hasher.update(nonce);
+        let program_state = bincode::serialize(&(
+            &self.account_type,
+            &self.program_namespace,
+            &self.program_account_data,
+            &self.program_account_metadata,
+            &self.program_account_linked_programs,
+        ));
+        hasher.update(program_state.expect("account fields always serialize"));
+        AccountHash::new(hasher.finalize().into())
+    }
+
+    /// Canonicalizes every token held and recomputes the stored account hash.
+    /// Every mutating method on `Account` calls this itself, and the `_mut`
+    /// accessors call it once the [`AccountFieldMut`] they return is dropped.
+    pub fn update_hash(&mut self) {
+        for token in self.programs.values_mut() {
+            token.canonicalize();
+        }
+        self.account_hash = self.compute_hash();
     }
 
     pub fn account_type(&self) -> AccountType {
@@ -373,32 +737,84 @@ impl Account {
         &self.programs
     }
 
-    pub fn programs_mut(&mut self) -> &mut BTreeMap<Address, Token> {
-        &mut self.programs
+    /// The number of programs this account holds a token for.
+    pub fn program_count(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// The number of bytes this account takes up when bincode encoded, as it
+    /// is in account blobs. The encoding is only counted, not written out.
+    pub fn serialized_size(&self) -> usize {
+        let size = bincode::serialized_size(self).expect("accounts always serialize");
+        usize::try_from(size).expect("account size must fit in a usize")
+    }
+
+    pub fn has_program(&self, program_id: &Address) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
+    /// Each program held by the account and its balance, in address order.
+    pub fn balances(&self) -> impl Iterator<Item = (&Address, crate::U256)> + '_ {
+        self.programs
+            .iter()
+            .map(|(program_id, token)| (program_id, token.balance()))
+    }
+
+    /// The program with the highest balance, for showing an account's
+    /// primary token. Ties go to the lowest address.
+    pub fn primary_program(&self) -> Option<(&Address, &Token)> {
+        self.programs.iter().max_by(|(a, a_token), (b, b_token)| {
+            a_token
+                .balance()
+                .cmp(&b_token.balance())
+                .then_with(|| b.cmp(a))
+        })
+    }
+
+    pub fn programs_mut(&mut self) -> AccountFieldMut<'_, BTreeMap<Address, Token>> {
+        AccountFieldMut {
+            account: self,
+            field: |account| &account.programs,
+            field_mut: |account| &mut account.programs,
+        }
     }
 
     pub fn program_account_data(&self) -> &ArbitraryData {
         &self.program_account_data
     }
 
-    pub fn program_account_data_mut(&mut self) -> &mut ArbitraryData {
-        &mut self.program_account_data
+    pub fn program_account_data_mut(&mut self) -> AccountFieldMut<'_, ArbitraryData> {
+        AccountFieldMut {
+            account: self,
+            field: |account| &account.program_account_data,
+            field_mut: |account| &mut account.program_account_data,
+        }
     }
 
     pub fn program_account_metadata(&self) -> &Metadata {
         &self.program_account_metadata
     }
 
-    pub fn program_account_metadat_mut(&mut self) -> &mut Metadata {
-        &mut self.program_account_metadata
+    pub fn program_account_metadat_mut(&mut self) -> AccountFieldMut<'_, Metadata> {
+        AccountFieldMut {
+            account: self,
+            field: |account| &account.program_account_metadata,
+            field_mut: |account| &mut account.program_account_metadata,
+        }
     }
 
     pub fn program_account_linked_programs(&self) -> &BTreeSet<AddressOrNamespace> {
         &self.program_account_linked_programs
     }
 
-    pub fn program_account_linked_programs_mut(&mut self) -> &mut BTreeSet<AddressOrNamespace> {
-        &mut self.program_account_linked_programs
+    pub fn program_account_linked_programs_mut(
+        &mut self,
+    ) -> AccountFieldMut<'_, BTreeSet<AddressOrNamespace>> {
+        AccountFieldMut {
+            account: self,
+            field: |account| &account.program_account_linked_programs,
+            field_mut: |account| &mut account.program_account_linked_programs,
+        }
     }
 
     pub fn balance(&self, program_id: &Address) -> crate::U256 {
@@ -409,6 +825,58 @@ impl Account {
         crate::U256::from(0)
     }
 
+    /// Moves `amount` of `from_program` out of this account and into
+    /// `to_program` on `to`.
+    ///
+    /// Both sides are validated before either balance is touched, so a
+    /// transfer that would underflow the sender or overflow the receiver
+    /// leaves both accounts unchanged, as does a locked token on either side.
+    /// If `to` does not yet hold `to_program` an empty token is created for
+    /// it, provided `to` holds fewer than [`max_programs`] programs.
+    pub fn transfer(
+        &mut self,
+        from_program: &Address,
+        to: &mut Account,
+        to_program: &Address,
+        amount: crate::U256,
+    ) -> Result<(), AccountError> {
+        self.validate_balance(from_program, amount)?;
+        if self
+            .programs
+            .get(from_program)
+            .is_some_and(Token::is_locked)
+        {
+            return Err(BalanceError::Locked(*from_program).into());
+        }
+        if to.programs.get(to_program).is_some_and(Token::is_locked) {
+            return Err(BalanceError::Locked(*to_program).into());
+        }
+        to.check_program_capacity(to_program, max_programs())?;
+        let receiver_balance = to.balance(to_program);
+        if receiver_balance.checked_add(amount).is_none() {
+            return Err(BalanceError::Overflow {
+                receive: amount,
+                balance: receiver_balance,
+            }
+            .into());
+        }
+
+        let owner_address = to.owner_address();
+        let receiver = to
+            .programs
+            .entry(*to_program)
+            .or_insert_with(|| Token::empty(*to_program, owner_address));
+        receiver.update_balance(amount, crate::U256::from(0))?;
+
+        if let Some(sender) = self.programs.get_mut(from_program) {
+            sender.update_balance(crate::U256::from(0), amount)?;
+        }
+        self.update_hash();
+        to.update_hash();
+
+        Ok(())
+    }
+
     pub fn apply_send_transaction(
         &mut self,
         transaction: Transaction,
@@ -416,7 +884,7 @@ impl Account {
     ) -> AccountResult<Token> {
         if transaction.transaction_type().is_bridge_in() {
             let token: Token = transaction.into();
-            self.insert_program(&token.program_id(), token.clone());
+            self.insert_program(&token.program_id(), token.clone())?;
             return Ok(token);
         }
 
@@ -457,11 +925,11 @@ impl Account {
                     "replaced token with new token: token_data: {:?}",
                     &token.data()
                 );
-                self.programs.insert(token.program_id(), token.clone());
+                self.insert_program(&token.program_id(), token.clone())?;
                 return Ok(token.clone());
             } else {
                 *token = new_token;
-                self.programs.insert(token.program_id(), token.clone());
+                self.insert_program(&token.program_id(), token.clone())?;
                 return Ok(token.clone());
             }
         }
@@ -473,10 +941,10 @@ impl Account {
                 let program_account_data = account.program_account_data();
                 token.set_metadata(program_account_metadata.clone());
                 token.set_data(program_account_data.clone());
-                self.insert_program(&token.program_id(), token.clone());
+                self.insert_program(&token.program_id(), token.clone())?;
                 return Ok(token);
             } else {
-                self.insert_program(&token.program_id(), token.clone());
+                self.insert_program(&token.program_id(), token.clone())?;
                 return Ok(token);
             }
         }
@@ -509,11 +977,11 @@ impl Account {
                             "replaced token with new token: token_data: {:?}",
                             &token.data()
                         );
-                        self.programs.insert(token.program_id(), token.clone());
+                        self.insert_program(&token.program_id(), token.clone())?;
                         return Ok(token.clone());
                     } else {
                         *token = new_token;
-                        self.programs.insert(token.program_id(), token.clone());
+                        self.insert_program(&token.program_id(), token.clone())?;
                         return Ok(token.clone());
                     }
                 } else {
@@ -523,10 +991,10 @@ impl Account {
                         let program_account_data = account.program_account_data();
                         token.set_metadata(program_account_metadata.clone());
                         token.set_data(program_account_data.clone());
-                        self.insert_program(&token.program_id(), token.clone());
+                        self.insert_program(&token.program_id(), token.clone())?;
                         return Ok(token);
                     } else {
-                        self.insert_program(&token.program_id(), token.clone());
+                        self.insert_program(&token.program_id(), token.clone())?;
                         return Ok(token);
                     }
                 }
@@ -538,6 +1006,62 @@ impl Account {
         )))
     }
 
+    /// Debits `fee` of `program_id` from this account. Nothing is changed if
+    /// the token is unknown, locked or holds less than `fee`.
+    pub fn deduct_fee(
+        &mut self,
+        program_id: &Address,
+        fee: crate::U256,
+    ) -> Result<(), AccountError> {
+        self.validate_balance(program_id, fee)?;
+        if let Some(token) = self.programs.get_mut(program_id) {
+            token.update_balance(crate::U256::from(0), fee)?;
+        }
+        self.update_hash();
+        Ok(())
+    }
+
+    /// Applies this account's side of `transaction` and returns the delta
+    /// to apply to the token the counterparty holds for the same program.
+    ///
+    /// A `Send` debits the transaction value from this account. A `Call`
+    /// also debits it, but only after spending that much of the allowance
+    /// this account granted to the program being called. This account must
+    /// be the sender, and nothing is changed if the debit fails.
+    pub fn apply_transaction(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<TokenDelta, AccountError> {
+        if transaction.from() != self.owner_address() {
+            return Err(AccountError::NotSender(transaction.from()));
+        }
+
+        let program_id = transaction.program_id();
+        let amount = transaction.value();
+        match transaction.transaction_type().kind() {
+            TxKind::Send => {
+                self.validate_balance(&program_id, amount)?;
+            }
+            TxKind::Call => {
+                self.validate_balance(&program_id, amount)?;
+                if let Some(token) = self.programs.get_mut(&program_id) {
+                    token.spend_allowance(&transaction.to(), amount)?;
+                }
+            }
+            kind => return Err(AccountError::UnsupportedTransaction(kind)),
+        }
+        if let Some(token) = self.programs.get_mut(&program_id) {
+            token.update_balance(crate::U256::from(0), amount)?;
+        }
+        self.update_hash();
+
+        Ok(TokenDeltaBuilder::default()
+            .receive(amount)
+            .send(crate::U256::from(0))
+            .build()
+            .expect("receive and send are always set"))
+    }
+
     pub fn apply_transfer_to_instruction(
         &mut self,
         token_address: &Address,
@@ -553,7 +1077,9 @@ impl Account {
             if !token_ids.is_empty() {
                 entry.add_token_ids(token_ids)?;
             }
-            Ok(entry.clone())
+            let token = entry.clone();
+            self.update_hash();
+            Ok(token)
         } else {
             let token_metadata = if let Some(program_account) = program_account {
                 program_account.program_account_metadata().clone()
@@ -587,7 +1113,7 @@ impl Account {
             if !token_ids.is_empty() {
                 token.add_token_ids(token_ids)?;
             }
-            self.programs.insert(token.program_id(), token.clone());
+            self.insert_program(&token.program_id(), token.clone())?;
 
             Ok(token)
         }
@@ -624,7 +1150,9 @@ impl Account {
             if !token_ids.is_empty() {
                 entry.remove_token_ids(token_ids)?;
             }
-            return Ok(entry.clone());
+            let token = entry.clone();
+            self.update_hash();
+            return Ok(token);
         }
 
         Err(Box::new(std::io::Error::new(
@@ -649,7 +1177,9 @@ impl Account {
                 entry.remove_token_ids(token_ids)?;
             }
 
-            return Ok(entry.clone());
+            let token = entry.clone();
+            self.update_hash();
+            return Ok(token);
         }
 
         Err(Box::new(std::io::Error::new(
@@ -693,7 +1223,9 @@ impl Account {
                 token.apply_token_update_field_values(update.value())?;
             }
 
-            Ok(token.clone())
+            let token = token.clone();
+            self.update_hash();
+            Ok(token)
         } else {
             tracing::info!("creating token for token distribution");
             let token_owner = {
@@ -745,7 +1277,7 @@ impl Account {
                 token.program_id(),
                 token_owner
             );
-            self.programs.insert(token.program_id(), token.clone());
+            self.insert_program(&token.program_id(), token.clone())?;
 
             Ok(token.clone())
         }
@@ -771,7 +1303,9 @@ impl Account {
                 token.apply_token_update_field_values(update.value())?;
                 tracing::warn!("token data after update {:?}", token.data());
             }
-            Ok(token.clone())
+            let token = token.clone();
+            self.update_hash();
+            Ok(token)
         } else {
             let token_metadata = program_account.program_account_metadata();
             let token_data = program_account.program_account_data();
@@ -798,7 +1332,7 @@ impl Account {
                 tracing::warn!("token data after applying update: {:?}", token.data());
             }
 
-            self.programs.insert(*program_id, token.clone());
+            self.insert_program(program_id, token.clone())?;
             Ok(token)
         }
     }
@@ -872,50 +1406,211 @@ impl Account {
         for update in update.updates() {
             self.apply_program_update_field_values(update.value())?;
         }
+        self.update_hash();
+
+        Ok(())
+    }
+
+    /// Applies `delta` to the token held for `program_id` and returns the
+    /// resulting balance. A program the account does not hold yet starts
+    /// from an empty token, provided the account holds fewer than
+    /// [`max_programs`] programs; an overdraw or overflow leaves the account
+    /// untouched.
+    pub fn apply_delta(
+        &mut self,
+        program_id: &Address,
+        delta: &TokenDelta,
+    ) -> Result<crate::U256, AccountError> {
+        self.apply_delta_with_limit(program_id, delta, max_programs())
+    }
+
+    /// [`Account::apply_delta`] with an explicit cap on the number of
+    /// programs the account may hold.
+    pub fn apply_delta_with_limit(
+        &mut self,
+        program_id: &Address,
+        delta: &TokenDelta,
+        max: usize,
+    ) -> Result<crate::U256, AccountError> {
+        self.check_program_capacity(program_id, max)?;
+        let mut token = self
+            .programs
+            .get(program_id)
+            .cloned()
+            .unwrap_or_else(|| Token::empty(*program_id, self.owner_address()));
+        token.apply_delta(delta)?;
+        let balance = token.balance();
+        self.programs.insert(*program_id, token);
+        self.update_hash();
+        Ok(balance)
+    }
+
+    /// Applies every delta to the token held for its program. Either all
+    /// deltas are applied or, if any program is unknown or any delta fails,
+    /// none are.
+    #[deprecated(note = "use `Account::apply_delta`, which returns the new balance")]
+    pub fn update_programs(
+        &mut self,
+        deltas: &BTreeMap<Address, TokenDelta>,
+    ) -> Result<(), AccountError> {
+        let mut updated = Vec::with_capacity(deltas.len());
+        for (program_id, delta) in deltas {
+            let mut token = self
+                .programs
+                .get(program_id)
+                .ok_or(AccountError::UnknownProgram(*program_id))?
+                .clone();
+            token.apply_delta(delta)?;
+            updated.push((*program_id, token));
+        }
+        self.programs.extend(updated);
+        self.update_hash();
+        Ok(())
+    }
+
+    /// The changes that turn the tokens held by this account into the ones
+    /// held by `other`, one delta per program whose balance, metadata or data
+    /// differ.
+    ///
+    /// A program held by only one of the accounts is compared against an
+    /// empty token, so its delta moves the full balance in or out.
+    pub fn diff(&self, other: &Account) -> BTreeMap<Address, TokenDelta> {
+        let zero = crate::U256::from(0);
+        let program_ids: BTreeSet<&Address> =
+            self.programs.keys().chain(other.programs.keys()).collect();
+        let mut deltas = BTreeMap::new();
+        for program_id in program_ids {
+            let before = self.programs.get(program_id);
+            let after = other.programs.get(program_id);
+            let old_balance = before.map_or(zero, Token::balance);
+            let new_balance = after.map_or(zero, Token::balance);
+            let metadata = after
+                .map(Token::metadata)
+                .filter(|metadata| before.map(Token::metadata).as_ref() != Some(metadata));
+            let data = after
+                .map(Token::data)
+                .filter(|data| before.map(Token::data).as_ref() != Some(data));
+            if old_balance == new_balance && metadata.is_none() && data.is_none() {
+                continue;
+            }
+
+            let (receive, send) = if new_balance >= old_balance {
+                (new_balance - old_balance, zero)
+            } else {
+                (zero, old_balance - new_balance)
+            };
+            let delta = TokenDeltaBuilder::default()
+                .receive(receive)
+                .send(send)
+                .metadata(metadata)
+                .data(data)
+                .build()
+                .expect("every delta field is set");
+            deltas.insert(*program_id, delta);
+        }
+        deltas
+    }
 
+    /// Fails with [`AccountError::TooManyPrograms`] if `program_id` is not
+    /// held yet and the account already holds `max` programs.
+    fn check_program_capacity(&self, program_id: &Address, max: usize) -> Result<(), AccountError> {
+        if self.programs.len() >= max && !self.programs.contains_key(program_id) {
+            return Err(AccountError::TooManyPrograms {
+                program: *program_id,
+                max,
+            });
+        }
         Ok(())
     }
 
-    pub fn insert_program(&mut self, program_id: &Address, token: Token) -> Option<Token> {
-        self.programs.insert(*program_id, token)
+    /// Inserts `token` under its own program id, which is the key every
+    /// lookup uses. A `program_id` that disagrees with the token is logged
+    /// and ignored so the token can always be found again. Adding a program
+    /// beyond [`max_programs`] fails and leaves the account unchanged.
+    pub fn insert_program(
+        &mut self,
+        program_id: &Address,
+        token: Token,
+    ) -> Result<Option<Token>, AccountError> {
+        self.insert_program_with_limit(program_id, token, max_programs())
+    }
+
+    /// [`Account::insert_program`] with an explicit cap on the number of
+    /// programs the account may hold.
+    pub fn insert_program_with_limit(
+        &mut self,
+        program_id: &Address,
+        token: Token,
+        max: usize,
+    ) -> Result<Option<Token>, AccountError> {
+        self.check_program_capacity(&token.program_id(), max)?;
+        if *program_id != token.program_id() {
+            tracing::warn!(
+                "inserting token for program {} under its own program id instead of {}",
+                token.program_id().to_full_string(),
+                program_id.to_full_string()
+            );
+        }
+        let previous = self.programs.insert(token.program_id(), token);
+        self.update_hash();
+        Ok(previous)
+    }
+
+    /// Registers a program deployed by this account at `nonce`, returning its
+    /// address from [`crate::derive_program_address`]. The account starts
+    /// holding an empty token for the new program; an existing token for
+    /// that address is kept as is.
+    pub fn deploy_program(&mut self, nonce: crate::U256) -> Result<Address, AccountError> {
+        let program_id = crate::derive_program_address(&self.owner_address, nonce);
+        if !self.programs.contains_key(&program_id) {
+            self.insert_program(&program_id, Token::empty(program_id, self.owner_address))?;
+        }
+        Ok(program_id)
+    }
+
+    /// Removes the token held for `program_id`, returning it, or `None` if
+    /// the account does not hold the program.
+    pub fn remove_program(&mut self, program_id: &Address) -> Option<Token> {
+        let removed = self.programs.remove(program_id)?;
+        self.update_hash();
+        Some(removed)
     }
 
-    pub fn validate_program_id(&self, program_id: &Address) -> AccountResult<()> {
+    pub fn validate_program_id(&self, program_id: &Address) -> Result<(), AccountError> {
         tracing::warn!("attempting to validate program_id");
         if let Some(_token) = self.programs.get(program_id) {
             return Ok(());
         }
 
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!(
-                "account does not have associated program: {}",
-                program_id.to_full_string()
-            ),
-        )))
+        Err(AccountError::UnknownProgram(*program_id))
     }
 
-    pub fn validate_balance(&self, program_id: &Address, amount: crate::U256) -> AccountResult<()> {
+    pub fn validate_balance(
+        &self,
+        program_id: &Address,
+        amount: crate::U256,
+    ) -> Result<(), AccountError> {
         tracing::warn!("attempting to validate balance");
         if let Some(token) = self.programs.get(program_id) {
-            tracing::warn!("token.balance() {} >= {} amount", &token.balance(), &amount);
-            if token.balance() >= amount {
+            tracing::warn!(
+                "token.spendable() {} >= {} amount",
+                &token.spendable(),
+                &amount
+            );
+            if token.can_spend(amount) {
                 return Ok(());
+            } else if token.is_locked() {
+                return Err(BalanceError::Locked(*program_id).into());
             } else {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "account balance insufficient",
-                )));
+                return Err(AccountError::InsufficientBalance {
+                    program: *program_id,
+                    needed: amount,
+                    available: token.spendable(),
+                });
             }
         }
 
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!(
-                "account does not have associated program: {}",
-                program_id.to_full_string()
-            ),
-        )))
+        Err(AccountError::UnknownProgram(*program_id))
     }
 
     pub fn validate_token_ownership(
@@ -1033,35 +1728,44 @@ impl Account {
         )))
     }
 
-    pub fn validate_nonce(&self, nonce: crate::U256) -> AccountResult<()> {
-        tracing::info!("checking nonce: {nonce} > {}", self.nonce);
-        if self.nonce == crate::U256::from(0) && nonce == crate::U256::from(0) {
-            return Ok(());
-        }
-        if nonce > self.nonce {
+    /// Validates that `nonce` is exactly the next nonce expected from this
+    /// account, i.e. the current account nonce plus one. Any stale or
+    /// skipped-ahead nonce is rejected so a transaction cannot be replayed.
+    /// An account whose nonce is already `U256::MAX` accepts no further
+    /// transactions.
+    pub fn validate_nonce(&self, nonce: crate::U256) -> Result<(), AccountError> {
+        let expected = self
+            .nonce
+            .checked_add(crate::U256::from(1))
+            .ok_or(AccountError::NonceExhausted)?;
+        tracing::info!("checking nonce: {nonce} == {expected}");
+        if nonce == expected {
             return Ok(());
         }
 
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "unable to validate nonce",
-        )))
+        Err(AccountError::InvalidNonce {
+            expected,
+            found: nonce,
+        })
     }
 
-    pub fn increment_nonce(&mut self) {
-        self.nonce += crate::U256::from(1);
+    /// Advances the nonce by one. Fails without changing the account if the
+    /// nonce is already `U256::MAX`.
+    pub fn increment_nonce(&mut self) -> Result<(), AccountError> {
+        self.nonce = self
+            .nonce
+            .checked_add(crate::U256::from(1))
+            .ok_or(AccountError::NonceExhausted)?;
+        self.update_hash();
+        Ok(())
     }
 }
 
+/// Formats the address as its `0x` prefixed EIP-55 checksum, the same
+/// string returned by [`Address::to_checksum`].
 impl Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let hex_str: String = self.encode_hex();
-        write!(
-            f,
-            "0x{}...{}",
-            &hex_str[0..4],
-            &hex_str[hex_str.len() - 4..]
-        )
+        f.write_str(&self.to_checksum())
     }
 }
 
@@ -1078,7 +1782,10 @@ impl From<&[u8; 20]> for Address {
 }
 
 impl FromStr for Address {
-    type Err = FromHexError;
+    type Err = AddressError;
+    /// Parses an address from either a `0x` prefixed or bare 40 character
+    /// hex string. If the string is mixed-case it is treated as an EIP-55
+    /// checksummed address and the checksum is validated.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let hex_str = if let Some(v) = s.strip_prefix("0x") {
             v
@@ -1096,14 +1803,29 @@ impl FromStr for Address {
             return Ok(Address::new(inner));
         }
 
-        let decoded = hex::decode(hex_str)?;
-        if decoded.len() != 20 {
-            return Err(FromHexError::InvalidStringLength);
+        if hex_str.len() != 40 {
+            return Err(AddressError::InvalidLength(hex_str.len()));
         }
 
-        let mut inner: [u8; 20] = [0; 20];
-        inner.copy_from_slice(&decoded);
-        Ok(Address::new(inner))
+        let address = Address::from_hex(hex_str)?;
+
+        let has_upper = hex_str.chars().any(|c| c.is_ascii_uppercase());
+        let has_lower = hex_str.chars().any(|c| c.is_ascii_lowercase());
+        if has_upper && has_lower {
+            let expected = address.to_checksum();
+            if expected[2..] != *hex_str {
+                return Err(AddressError::InvalidChecksum { expected });
+            }
+        }
+
+        Ok(address)
+    }
+}
+
+impl TryFrom<&str> for Address {
+    type Error = AddressError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Address::from_str(value)
     }
 }
 
@@ -1186,3 +1908,991 @@ impl From<[u8; 32]> for Address {
         Address(address)
     }
 }
+
+#[cfg(test)]
+mod account_tests {
+    use super::{
+        Account, AccountBuilder, AccountError, AccountHash, AccountHashError, AccountType, Address,
+        AddressError,
+    };
+    use crate::{
+        AddressOrNamespace, ArbitraryData, BalanceError, CertificateBuilder, Metadata,
+        PayloadBuilder, RecoverableSignature, Token, TokenDeltaBuilder, Transaction,
+        TransactionType, TxKind, U256,
+    };
+    use hex::FromHexError;
+    use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+    use sha3::{Digest, Sha3_256};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::str::FromStr;
+
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn test_address_checksum_round_trip() {
+        let address = Address::from_str(&CHECKSUMMED.to_lowercase()).unwrap();
+        assert_eq!(address.to_checksum(), CHECKSUMMED);
+        assert_eq!(Address::from_str(CHECKSUMMED).unwrap(), address);
+        assert_eq!(Address::try_from(&CHECKSUMMED[2..]).unwrap(), address);
+    }
+
+    #[test]
+    fn test_address_display_is_checksummed() {
+        let address = Address::from_str(CHECKSUMMED).unwrap();
+        assert_eq!(format!("{}", address), CHECKSUMMED);
+        assert_eq!(address.to_string(), address.to_checksum());
+        assert_eq!(Address::from_str(&address.to_string()).unwrap(), address);
+    }
+
+    #[test]
+    fn test_address_json_is_hex_and_bincode_is_raw_bytes() {
+        let address = Address::from_str(CHECKSUMMED).unwrap();
+
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", address.to_full_string()));
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+
+        let bytes = bincode::serialize(&address).unwrap();
+        assert_eq!(bytes, address.inner());
+        assert_eq!(bincode::deserialize::<Address>(&bytes).unwrap(), address);
+    }
+
+    #[test]
+    fn test_address_parse_errors() {
+        assert_eq!(
+            Address::from_str("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"),
+            Err(AddressError::InvalidLength(38))
+        );
+        assert!(matches!(
+            Address::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"),
+            Err(AddressError::InvalidChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_address_from_hex() {
+        let address = Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(Address::from_hex(&CHECKSUMMED[2..]).unwrap(), address);
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beae"),
+            Err(AddressError::InvalidHex(FromHexError::OddLength))
+        );
+        assert!(matches!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg"),
+            Err(AddressError::InvalidHex(
+                FromHexError::InvalidHexCharacter { .. }
+            ))
+        ));
+        assert_eq!(
+            Address::from_hex("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"),
+            Err(AddressError::InvalidLength(38))
+        );
+    }
+
+    #[test]
+    fn test_account_hash_hex_round_trip() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 0xab;
+        bytes[31] = 0x01;
+        let hash = AccountHash::new(bytes);
+        let formatted = hash.to_string();
+        assert_eq!(formatted, format!("0xab{}01", "00".repeat(30)));
+        assert_eq!(AccountHash::from_str(&formatted).unwrap(), hash);
+        assert_eq!(AccountHash::from_str(&formatted[2..]).unwrap(), hash);
+        assert_eq!(
+            AccountHash::from_str(&formatted.to_uppercase()[2..]).unwrap(),
+            hash
+        );
+
+        assert_eq!(
+            AccountHash::from_str("0xab"),
+            Err(AccountHashError::InvalidLength(2))
+        );
+        assert!(matches!(
+            AccountHash::from_str(&"zz".repeat(32)),
+            Err(AccountHashError::InvalidHex(_))
+        ));
+    }
+
+    #[test]
+    fn test_account_nonce_validation() {
+        let mut account = Account::new(AccountType::User, None, Address::new([1u8; 20]), None);
+        account.validate_nonce(U256::from(1)).unwrap();
+        account.increment_nonce().unwrap();
+        assert_eq!(account.nonce(), U256::from(1));
+
+        assert_eq!(
+            account.validate_nonce(U256::from(1)),
+            Err(AccountError::InvalidNonce {
+                expected: U256::from(2),
+                found: U256::from(1),
+            })
+        );
+        account.validate_nonce(U256::from(2)).unwrap();
+        account.increment_nonce().unwrap();
+        assert_eq!(account.nonce(), U256::from(2));
+    }
+
+    #[test]
+    fn test_exhausted_nonce_is_rejected() {
+        let mut account = AccountBuilder::default()
+            .account_type(AccountType::User)
+            .program_namespace(None)
+            .owner_address(Address::new([1u8; 20]))
+            .programs(BTreeMap::new())
+            .nonce(U256::MAX)
+            .program_account_data(ArbitraryData::new())
+            .program_account_metadata(Metadata::new())
+            .program_account_linked_programs(BTreeSet::new())
+            .build()
+            .unwrap();
+        let before = account.clone();
+
+        assert_eq!(
+            account.validate_nonce(U256::MAX),
+            Err(AccountError::NonceExhausted)
+        );
+        assert_eq!(account.increment_nonce(), Err(AccountError::NonceExhausted));
+        assert_eq!(account, before);
+    }
+
+    fn account_with_balance(owner: Address, program_id: Address, balance: U256) -> Account {
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        let mut token = Token::empty(program_id, owner);
+        *token.balance_mut() = balance;
+        account.insert_program(&program_id, token).unwrap();
+        account
+    }
+
+    #[test]
+    fn test_account_transfer() {
+        let program_id = Address::new([9u8; 20]);
+        let mut sender = account_with_balance(Address::new([1u8; 20]), program_id, U256::from(10));
+        let mut receiver = Account::new(AccountType::User, None, Address::new([2u8; 20]), None);
+
+        sender
+            .transfer(&program_id, &mut receiver, &program_id, U256::from(4))
+            .unwrap();
+        assert_eq!(sender.balance(&program_id), U256::from(6));
+        assert_eq!(receiver.balance(&program_id), U256::from(4));
+
+        assert_eq!(
+            sender.transfer(&program_id, &mut receiver, &program_id, U256::from(7)),
+            Err(AccountError::InsufficientBalance {
+                program: program_id,
+                needed: U256::from(7),
+                available: U256::from(6),
+            })
+        );
+        assert_eq!(sender.balance(&program_id), U256::from(6));
+        assert_eq!(receiver.balance(&program_id), U256::from(4));
+    }
+
+    #[test]
+    fn test_account_transfer_failed_credit_is_atomic() {
+        let program_id = Address::new([9u8; 20]);
+        let mut sender = account_with_balance(Address::new([1u8; 20]), program_id, U256::from(10));
+        let mut receiver = account_with_balance(Address::new([2u8; 20]), program_id, U256::MAX);
+        let sender_before = sender.clone();
+        let receiver_before = receiver.clone();
+
+        assert_eq!(
+            sender.transfer(&program_id, &mut receiver, &program_id, U256::from(1)),
+            Err(AccountError::Balance(BalanceError::Overflow {
+                receive: U256::from(1),
+                balance: U256::MAX,
+            }))
+        );
+        assert_eq!(sender, sender_before);
+        assert_eq!(receiver, receiver_before);
+    }
+
+    #[test]
+    fn test_account_hash_tracks_balance_changes() {
+        let program_id = Address::new([9u8; 20]);
+        let mut sender = account_with_balance(Address::new([1u8; 20]), program_id, U256::from(10));
+        let mut receiver = Account::new(AccountType::User, None, Address::new([2u8; 20]), None);
+        let sender_hash = sender.hash();
+        let receiver_hash = receiver.hash();
+        assert_eq!(sender_hash, sender.compute_hash());
+
+        sender
+            .transfer(&program_id, &mut receiver, &program_id, U256::from(4))
+            .unwrap();
+        assert_ne!(sender.hash(), sender_hash);
+        assert_ne!(receiver.hash(), receiver_hash);
+        assert_eq!(sender.hash(), sender.compute_hash());
+        assert_eq!(receiver.hash(), receiver.compute_hash());
+
+        let credited = account_with_balance(Address::new([2u8; 20]), program_id, U256::from(4));
+        let mut token = credited.programs()[&program_id].clone();
+        *token.balance_mut() = U256::from(5);
+        let mut changed = credited.clone();
+        changed.insert_program(&program_id, token).unwrap();
+        assert_ne!(changed.hash(), credited.hash());
+    }
+
+    #[test]
+    fn test_account_new_keeps_programs() {
+        let owner = Address::new([1u8; 20]);
+        let programs = BTreeMap::from([
+            (
+                Address::new([8u8; 20]),
+                Token::empty(Address::new([8u8; 20]), owner),
+            ),
+            (
+                Address::new([9u8; 20]),
+                Token::empty(Address::new([9u8; 20]), owner),
+            ),
+        ]);
+        let account = Account::new(AccountType::User, None, owner, Some(programs));
+        assert_eq!(account.programs().len(), 2);
+        assert_eq!(account.hash(), account.compute_hash());
+
+        let empty = Account::new(AccountType::User, None, owner, None);
+        assert!(empty.programs().is_empty());
+    }
+
+    #[test]
+    fn test_account_balances() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = account_with_balance(owner, Address::new([9u8; 20]), U256::from(7));
+        account
+            .insert_program(
+                &Address::new([8u8; 20]),
+                Token::empty(Address::new([8u8; 20]), owner),
+            )
+            .unwrap();
+
+        assert_eq!(account.program_count(), 2);
+        assert!(account.has_program(&Address::new([9u8; 20])));
+        assert!(!account.has_program(&Address::new([7u8; 20])));
+        assert_eq!(
+            account.balances().collect::<Vec<_>>(),
+            vec![
+                (&Address::new([8u8; 20]), U256::from(0)),
+                (&Address::new([9u8; 20]), U256::from(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_hash_ignores_token_id_order() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let account_with_ids = |ids: Vec<U256>| {
+            let mut token = Token::empty(program_id, owner);
+            *token.token_ids_mut() = ids;
+            Account::new(
+                AccountType::User,
+                None,
+                owner,
+                Some(BTreeMap::from([(program_id, token)])),
+            )
+        };
+
+        let sorted = account_with_ids(vec![U256::from(1), U256::from(2), U256::from(3)]);
+        let shuffled = account_with_ids(vec![U256::from(3), U256::from(1), U256::from(2)]);
+        assert_eq!(sorted.compute_hash(), shuffled.compute_hash());
+        assert_eq!(sorted.hash(), shuffled.hash());
+        assert_eq!(sorted, shuffled);
+    }
+
+    #[test]
+    fn test_deploy_program_registers_empty_token() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+
+        let program_id = account.deploy_program(U256::from(1)).unwrap();
+        assert_eq!(
+            program_id,
+            crate::derive_program_address(&owner, U256::from(1))
+        );
+        assert_eq!(
+            account.programs().get(&program_id),
+            Some(&Token::empty(program_id, owner))
+        );
+        assert_eq!(account.hash(), account.compute_hash());
+
+        assert_eq!(account.deploy_program(U256::from(1)).unwrap(), program_id);
+        assert_ne!(account.deploy_program(U256::from(2)).unwrap(), program_id);
+        assert_eq!(account.program_count(), 2);
+    }
+
+    #[test]
+    fn test_insert_program_respects_cap() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        let empty = |n: u8| Token::empty(Address::new([n; 20]), owner);
+
+        for n in 1..=3u8 {
+            account
+                .insert_program_with_limit(&Address::new([n; 20]), empty(n), 3)
+                .unwrap();
+        }
+        let full = account.clone();
+        assert_eq!(
+            account.insert_program_with_limit(&Address::new([4u8; 20]), empty(4), 3),
+            Err(AccountError::TooManyPrograms {
+                program: Address::new([4u8; 20]),
+                max: 3,
+            })
+        );
+        assert_eq!(account, full);
+
+        assert_eq!(
+            account.insert_program_with_limit(&Address::new([3u8; 20]), empty(3), 3),
+            Ok(Some(empty(3)))
+        );
+        assert_eq!(account.program_count(), 3);
+    }
+
+    #[test]
+    fn test_apply_delta_respects_cap() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = account_with_balance(owner, Address::new([1u8; 20]), U256::from(1));
+        let receive = TokenDeltaBuilder::default()
+            .receive(U256::from(5))
+            .send(U256::from(0))
+            .build()
+            .unwrap();
+
+        account
+            .apply_delta_with_limit(&Address::new([2u8; 20]), &receive, 2)
+            .unwrap();
+        let full = account.clone();
+        assert_eq!(
+            account.apply_delta_with_limit(&Address::new([3u8; 20]), &receive, 2),
+            Err(AccountError::TooManyPrograms {
+                program: Address::new([3u8; 20]),
+                max: 2,
+            })
+        );
+        assert_eq!(account, full);
+
+        assert_eq!(
+            account.apply_delta_with_limit(&Address::new([2u8; 20]), &receive, 2),
+            Ok(U256::from(10))
+        );
+        assert_eq!(account.program_count(), 2);
+    }
+
+    #[test]
+    fn test_account_hash_is_domain_separated() {
+        let owner = Address::new([1u8; 20]);
+        let account = Account::new(AccountType::User, None, owner, None);
+
+        let mut preimage = b"LASR_ACCOUNT_V2".to_vec();
+        preimage.extend(owner.inner());
+        preimage.extend([0u8; 32]);
+        // `AccountType::User`, no namespace, then empty data, metadata and
+        // linked programs.
+        preimage.extend([0u8; 4]);
+        preimage.extend([0u8; 1]);
+        preimage.extend([0u8; 24]);
+        assert_eq!(
+            account.compute_hash(),
+            AccountHash::new(Sha3_256::digest(&preimage).into())
+        );
+    }
+
+    #[test]
+    fn test_account_hash_covers_program_state() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([2u8; 20]);
+        let user = Account::new(AccountType::User, None, owner, None);
+        let program = Account::new(AccountType::Program(program_id), None, owner, None);
+        assert_ne!(user.compute_hash(), program.compute_hash());
+
+        let mut updated = program.clone();
+        updated
+            .program_account_metadat_mut()
+            .insert("name".to_string(), "token".to_string());
+        assert_ne!(updated.hash(), program.compute_hash());
+
+        let mut linked = program.clone();
+        linked
+            .program_account_linked_programs_mut()
+            .insert(AddressOrNamespace::Address(owner));
+        assert_ne!(linked.compute_hash(), program.compute_hash());
+    }
+
+    #[test]
+    fn test_mut_accessors_keep_hash_current() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([2u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let mut previous = account.hash();
+        let mut assert_rehashed = |account: &Account| {
+            assert_eq!(account.hash(), account.compute_hash());
+            assert_ne!(account.hash(), previous);
+            previous = account.hash();
+        };
+
+        *account
+            .programs_mut()
+            .get_mut(&program_id)
+            .unwrap()
+            .balance_mut() = U256::from(3);
+        assert_rehashed(&account);
+
+        account
+            .program_account_data_mut()
+            .insert("key".to_string(), "value".to_string());
+        assert_rehashed(&account);
+
+        account
+            .program_account_metadat_mut()
+            .insert("name".to_string(), "token".to_string());
+        assert_rehashed(&account);
+
+        account
+            .program_account_linked_programs_mut()
+            .insert(AddressOrNamespace::Address(program_id));
+        assert_rehashed(&account);
+    }
+
+    #[test]
+    fn test_address_zero() {
+        assert!(Address::ZERO.is_zero());
+        assert_eq!(Address::ZERO, Address::eth_addr());
+        assert_eq!(Address::ZERO, Address::default());
+        assert!(!Address::verse_addr().is_zero());
+    }
+
+    #[test]
+    fn test_validate_balance_rejects_locked_tokens() {
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(Address::new([1u8; 20]), program_id, U256::from(10));
+        assert_eq!(
+            account.validate_balance(&program_id, U256::from(10)),
+            Ok(())
+        );
+        assert_eq!(
+            account.validate_balance(&program_id, U256::from(11)),
+            Err(AccountError::InsufficientBalance {
+                program: program_id,
+                needed: U256::from(11),
+                available: U256::from(10),
+            })
+        );
+
+        let mut token = account.programs()[&program_id].clone();
+        token.lock().unwrap();
+        account.insert_program(&program_id, token).unwrap();
+        assert_eq!(
+            account.validate_balance(&program_id, U256::from(1)),
+            Err(AccountError::Balance(BalanceError::Locked(program_id)))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_update_programs_applies_metadata_with_balance() {
+        let owner = Address::new([1u8; 20]);
+        let (first, second) = (Address::new([8u8; 20]), Address::new([9u8; 20]));
+        let mut account = account_with_balance(owner, first, U256::from(10));
+        let mut token = Token::empty(second, owner);
+        *token.balance_mut() = U256::from(3);
+        account.insert_program(&second, token).unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata.insert("symbol".to_string(), "TKN".to_string());
+        let deltas = BTreeMap::from([
+            (
+                first,
+                TokenDeltaBuilder::default()
+                    .receive(U256::from(0))
+                    .send(U256::from(4))
+                    .metadata(Some(metadata.clone()))
+                    .build()
+                    .unwrap(),
+            ),
+            (
+                second,
+                TokenDeltaBuilder::default()
+                    .receive(U256::from(4))
+                    .send(U256::from(0))
+                    .build()
+                    .unwrap(),
+            ),
+        ]);
+        let hash = account.hash();
+        account.update_programs(&deltas).unwrap();
+        assert_eq!(account.balance(&first), U256::from(6));
+        assert_eq!(account.balance(&second), U256::from(7));
+        assert_eq!(account.programs()[&first].metadata(), metadata);
+        assert_eq!(account.programs()[&second].metadata(), Metadata::new());
+        assert_ne!(account.hash(), hash);
+
+        let overdraw = BTreeMap::from([
+            (
+                first,
+                TokenDeltaBuilder::default()
+                    .receive(U256::from(0))
+                    .send(U256::from(1))
+                    .build()
+                    .unwrap(),
+            ),
+            (
+                second,
+                TokenDeltaBuilder::default()
+                    .receive(U256::from(0))
+                    .send(U256::from(8))
+                    .build()
+                    .unwrap(),
+            ),
+        ]);
+        assert!(account.update_programs(&overdraw).is_err());
+        assert_eq!(account.balance(&first), U256::from(6));
+        assert_eq!(account.balance(&second), U256::from(7));
+    }
+
+    fn validator_keys() -> Vec<SecretKey> {
+        (1u8..=3)
+            .map(|n| SecretKey::from_slice(&[n; 32]).unwrap())
+            .collect()
+    }
+
+    fn sign_hash(key: &SecretKey, hash: AccountHash) -> RecoverableSignature {
+        let message = Message::from_digest_slice(&hash.inner()).unwrap();
+        SECP256K1.sign_ecdsa_recoverable(&message, key).into()
+    }
+
+    #[test]
+    fn test_attach_and_verify_certificate() {
+        let keys = validator_keys();
+        let validators: Vec<Address> = keys
+            .iter()
+            .map(|key| Address::from(PublicKey::from_secret_key(SECP256K1, key)))
+            .collect();
+        let mut account = account_with_balance(
+            Address::new([1u8; 20]),
+            Address::new([9u8; 20]),
+            U256::from(10),
+        );
+        assert_eq!(
+            account.verify_certificate(&validators, 1),
+            Err(AccountError::MissingCertificate)
+        );
+
+        let sigs: BTreeSet<RecoverableSignature> = keys[..2]
+            .iter()
+            .map(|key| sign_hash(key, account.hash()))
+            .collect();
+        let certificate = CertificateBuilder::default()
+            .quorum_id([7u8; 20])
+            .quorum_sigs(sigs)
+            .build()
+            .unwrap();
+        let hash = account.hash();
+        assert!(account.attach_certificate(certificate.clone()).is_none());
+        assert_eq!(account.hash(), hash);
+        assert_eq!(account.certificate(), Some(&certificate));
+
+        account.verify_certificate(&validators, 2).unwrap();
+        assert_eq!(
+            account.verify_certificate(&validators, 3),
+            Err(AccountError::InsufficientAttestations {
+                required: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_certificate_over_other_state_is_rejected() {
+        let keys = validator_keys();
+        let validators: Vec<Address> = keys
+            .iter()
+            .map(|key| Address::from(PublicKey::from_secret_key(SECP256K1, key)))
+            .collect();
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(Address::new([1u8; 20]), program_id, U256::from(10));
+        let sigs: BTreeSet<RecoverableSignature> = keys
+            .iter()
+            .map(|key| sign_hash(key, account.hash()))
+            .collect();
+        account.attach_certificate(
+            CertificateBuilder::default()
+                .quorum_id([7u8; 20])
+                .quorum_sigs(sigs)
+                .build()
+                .unwrap(),
+        );
+        account.verify_certificate(&validators, 3).unwrap();
+
+        *account
+            .programs_mut()
+            .get_mut(&program_id)
+            .unwrap()
+            .balance_mut() = U256::from(1_000);
+        assert_eq!(
+            account.verify_certificate(&validators, 1),
+            Err(AccountError::InsufficientAttestations {
+                required: 1,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialized_size_matches_bincode_length() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        assert_eq!(
+            account.serialized_size(),
+            bincode::serialize(&account).unwrap().len()
+        );
+
+        let program_id = Address::new([9u8; 20]);
+        let mut token = Token::empty(program_id, owner);
+        *token.balance_mut() = U256::from(10);
+        token
+            .metadata_mut()
+            .insert("symbol".to_string(), "TKN".to_string());
+        account.insert_program(&program_id, token).unwrap();
+        assert_eq!(
+            account.serialized_size(),
+            bincode::serialize(&account).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_apply_delta_returns_new_balance() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let delta = TokenDeltaBuilder::default()
+            .receive(U256::from(2))
+            .send(U256::from(5))
+            .build()
+            .unwrap();
+
+        let hash = account.hash();
+        assert_eq!(
+            account.apply_delta(&program_id, &delta).unwrap(),
+            U256::from(7)
+        );
+        assert_eq!(account.balance(&program_id), U256::from(7));
+        assert_ne!(account.hash(), hash);
+    }
+
+    #[test]
+    fn test_apply_delta_rejects_underflow() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(3));
+        let delta = TokenDeltaBuilder::default()
+            .receive(U256::from(1))
+            .send(U256::from(5))
+            .build()
+            .unwrap();
+
+        let hash = account.hash();
+        assert_eq!(
+            account.apply_delta(&program_id, &delta),
+            Err(AccountError::Balance(BalanceError::InsufficientBalance {
+                send: U256::from(5),
+                available: U256::from(4),
+            }))
+        );
+        assert_eq!(account.balance(&program_id), U256::from(3));
+        assert_eq!(account.hash(), hash);
+    }
+
+    #[test]
+    fn test_apply_delta_inserts_new_program() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        let delta = TokenDeltaBuilder::default()
+            .receive(U256::from(4))
+            .send(U256::from(0))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            account.apply_delta(&program_id, &delta).unwrap(),
+            U256::from(4)
+        );
+        let token = &account.programs()[&program_id];
+        assert_eq!(token.owner_id(), owner);
+        assert_eq!(token.balance(), U256::from(4));
+    }
+
+    #[test]
+    fn test_insert_program_keys_by_token_program_id() {
+        let owner = Address::new([1u8; 20]);
+        let (requested, actual) = (Address::new([8u8; 20]), Address::new([9u8; 20]));
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        let mut token = Token::empty(actual, owner);
+        *token.balance_mut() = U256::from(5);
+
+        account.insert_program(&requested, token).unwrap();
+        assert!(!account.has_program(&requested));
+        assert_eq!(account.balance(&actual), U256::from(5));
+        assert_eq!(account.validate_balance(&actual, U256::from(5)), Ok(()));
+    }
+
+    fn transaction(
+        transaction_type: TransactionType,
+        from: Address,
+        to: Address,
+        program_id: Address,
+        value: U256,
+    ) -> Transaction {
+        PayloadBuilder::default()
+            .transaction_type(transaction_type)
+            .from(from.into())
+            .to(to.into())
+            .program_id(program_id.into())
+            .op(String::new())
+            .inputs(String::new())
+            .value(value)
+            .nonce(U256::from(1))
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn test_deduct_fee() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+
+        let hash = account.hash();
+        account.deduct_fee(&program_id, U256::from(4)).unwrap();
+        assert_eq!(account.balance(&program_id), U256::from(6));
+        assert_ne!(account.hash(), hash);
+
+        assert_eq!(
+            account.deduct_fee(&program_id, U256::from(7)),
+            Err(AccountError::InsufficientBalance {
+                program: program_id,
+                needed: U256::from(7),
+                available: U256::from(6),
+            })
+        );
+        assert_eq!(
+            account.deduct_fee(&owner, U256::from(1)),
+            Err(AccountError::UnknownProgram(owner))
+        );
+        assert_eq!(account.balance(&program_id), U256::from(6));
+    }
+
+    #[test]
+    fn test_apply_send_transaction_produces_debit_delta() {
+        let (owner, receiver) = (Address::new([1u8; 20]), Address::new([2u8; 20]));
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let send = transaction(
+            TransactionType::Send(U256::from(1)),
+            owner,
+            receiver,
+            program_id,
+            U256::from(4),
+        );
+
+        let hash = account.hash();
+        let delta = account.apply_transaction(&send).unwrap();
+        assert_eq!(delta.receive(), U256::from(4));
+        assert_eq!(delta.send(), U256::from(0));
+        assert_eq!(account.balance(&program_id), U256::from(6));
+        assert_ne!(account.hash(), hash);
+
+        let mut counterparty = account_with_balance(receiver, program_id, U256::from(1));
+        assert_eq!(
+            counterparty.apply_delta(&program_id, &delta).unwrap(),
+            U256::from(5)
+        );
+        assert_eq!(counterparty.balance(&program_id), U256::from(5));
+    }
+
+    #[test]
+    fn test_apply_send_transaction_rejects_insufficient_balance() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(3));
+        let send = transaction(
+            TransactionType::Send(U256::from(1)),
+            owner,
+            Address::new([2u8; 20]),
+            program_id,
+            U256::from(4),
+        );
+
+        assert_eq!(
+            account.apply_transaction(&send),
+            Err(AccountError::InsufficientBalance {
+                program: program_id,
+                needed: U256::from(4),
+                available: U256::from(3),
+            })
+        );
+        assert_eq!(account.balance(&program_id), U256::from(3));
+    }
+
+    #[test]
+    fn test_apply_call_transaction_spends_allowance() {
+        let (owner, program) = (Address::new([1u8; 20]), Address::new([3u8; 20]));
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let call = transaction(
+            TransactionType::Call(U256::from(1)),
+            owner,
+            program,
+            program_id,
+            U256::from(4),
+        );
+
+        assert!(matches!(
+            account.apply_transaction(&call),
+            Err(AccountError::InsufficientAllowance { .. })
+        ));
+        assert_eq!(account.balance(&program_id), U256::from(10));
+
+        account
+            .programs_mut()
+            .get_mut(&program_id)
+            .unwrap()
+            .approve(program, U256::from(6));
+        account.apply_transaction(&call).unwrap();
+        assert_eq!(account.balance(&program_id), U256::from(6));
+        assert_eq!(
+            account.programs()[&program_id].allowance_of(&program),
+            U256::from(2)
+        );
+    }
+
+    #[test]
+    fn test_apply_transaction_rejects_unsupported_kinds() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let bridge_out = transaction(
+            TransactionType::BridgeOut(U256::from(1)),
+            owner,
+            Address::new([2u8; 20]),
+            program_id,
+            U256::from(4),
+        );
+        assert_eq!(
+            account.apply_transaction(&bridge_out),
+            Err(AccountError::UnsupportedTransaction(TxKind::BridgeOut))
+        );
+
+        let not_sender = transaction(
+            TransactionType::Send(U256::from(1)),
+            Address::new([2u8; 20]),
+            owner,
+            program_id,
+            U256::from(4),
+        );
+        assert_eq!(
+            account.apply_transaction(&not_sender),
+            Err(AccountError::NotSender(Address::new([2u8; 20])))
+        );
+        assert_eq!(account.balance(&program_id), U256::from(10));
+    }
+
+    #[test]
+    fn test_remove_program() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let mut account = account_with_balance(owner, program_id, U256::from(10));
+        let empty = Account::new(AccountType::User, None, owner, None);
+
+        let removed = account.remove_program(&program_id).unwrap();
+        assert_eq!(removed.balance(), U256::from(10));
+        assert!(!account.has_program(&program_id));
+        assert_eq!(account.hash(), empty.hash());
+
+        assert_eq!(account.remove_program(&program_id), None);
+        assert_eq!(account.hash(), empty.hash());
+    }
+
+    #[test]
+    fn test_diff_modified_program() {
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([9u8; 20]);
+        let before = account_with_balance(owner, program_id, U256::from(10));
+        let mut after = before.clone();
+        after
+            .programs_mut()
+            .get_mut(&program_id)
+            .unwrap()
+            .update_balance(U256::from(0), U256::from(4))
+            .unwrap();
+
+        let deltas = before.diff(&after);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[&program_id].receive(), U256::from(0));
+        assert_eq!(deltas[&program_id].send(), U256::from(4));
+        assert_eq!(deltas[&program_id].metadata(), None);
+        assert!(before.diff(&before).is_empty());
+
+        let mut metadata = Metadata::new();
+        metadata.insert("symbol".to_string(), "TKN".to_string());
+        let mut renamed = before.clone();
+        *renamed
+            .programs_mut()
+            .get_mut(&program_id)
+            .unwrap()
+            .metadata_mut() = metadata.clone();
+        let deltas = before.diff(&renamed);
+        assert_eq!(deltas[&program_id].send(), U256::from(0));
+        assert_eq!(deltas[&program_id].metadata(), Some(&metadata));
+
+        let mut updated = before.clone();
+        for (program_id, delta) in before.diff(&after) {
+            updated.apply_delta(&program_id, &delta).unwrap();
+        }
+        assert_eq!(updated.programs(), after.programs());
+    }
+
+    #[test]
+    fn test_diff_added_and_removed_programs() {
+        let owner = Address::new([1u8; 20]);
+        let (kept, removed, added) = (
+            Address::new([7u8; 20]),
+            Address::new([8u8; 20]),
+            Address::new([9u8; 20]),
+        );
+        let mut before = account_with_balance(owner, kept, U256::from(5));
+        let mut token = Token::empty(removed, owner);
+        *token.balance_mut() = U256::from(3);
+        before.insert_program(&removed, token).unwrap();
+
+        let mut after = account_with_balance(owner, kept, U256::from(5));
+        let mut token = Token::empty(added, owner);
+        *token.balance_mut() = U256::from(6);
+        after.insert_program(&added, token).unwrap();
+
+        let deltas = before.diff(&after);
+        assert_eq!(deltas.keys().collect::<Vec<_>>(), vec![&removed, &added]);
+        assert_eq!(deltas[&removed].send(), U256::from(3));
+        assert_eq!(deltas[&removed].receive(), U256::from(0));
+        assert_eq!(deltas[&added].receive(), U256::from(6));
+        assert_eq!(deltas[&added].send(), U256::from(0));
+    }
+
+    #[test]
+    fn test_primary_program() {
+        let owner = Address::new([1u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        assert_eq!(account.primary_program(), None);
+
+        for (n, balance) in [(7u8, 5u64), (3, 2), (9, 8), (4, 8), (6, 0)] {
+            let program_id = Address::new([n; 20]);
+            let mut token = Token::empty(program_id, owner);
+            *token.balance_mut() = U256::from(balance);
+            account.insert_program(&program_id, token).unwrap();
+        }
+
+        let (program_id, token) = account.primary_program().unwrap();
+        assert_eq!(program_id, &Address::new([4u8; 20]));
+        assert_eq!(token.balance(), U256::from(8));
+    }
+}
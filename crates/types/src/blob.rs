@@ -0,0 +1,219 @@
+//! The body of the account blobs dispersed to and retrieved from EigenDA.
+//!
+//! A blob starts with a one byte layout version and a 4 byte big-endian
+//! account count, followed by one length-prefixed frame per account as
+//! written by [`encode_framed`].
+use crate::{decode_framed, encode_framed, legacy::AccountV1, Account, WireError};
+use bytes::BytesMut;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// The layout versions an account blob may be written with. Each version
+/// keeps its own decoder so blobs already on the DA layer stay readable
+/// after the layout changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum BlobVersion {
+    /// A 4 byte big-endian account count followed by one frame per account,
+    /// with addresses written as `0x` prefixed hex strings.
+    V1 = 1,
+    /// The same layout as `V1`, with addresses written as their raw 20 bytes
+    /// and accounts that may carry a certificate.
+    V2 = 2,
+}
+
+impl BlobVersion {
+    /// The version new blobs are written with.
+    pub const CURRENT: BlobVersion = BlobVersion::V2;
+}
+
+impl TryFrom<u8> for BlobVersion {
+    type Error = BlobDecodeError;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(BlobVersion::V1),
+            2 => Ok(BlobVersion::V2),
+            _ => Err(BlobDecodeError::UnsupportedVersion(version)),
+        }
+    }
+}
+
+/// The layout version written at the start of every account blob.
+pub const ACCOUNT_BLOB_VERSION: u8 = BlobVersion::CURRENT as u8;
+
+/// The size of the version and account count at the start of a blob.
+pub const ACCOUNT_BLOB_HEADER_LEN: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum BlobDecodeError {
+    #[error("unsupported account blob version {0}, this node reads up to {ACCOUNT_BLOB_VERSION}")]
+    UnsupportedVersion(u8),
+
+    #[error("account blob ended before all {expected} accounts were read")]
+    Truncated { expected: u32 },
+
+    #[error("account blob has {0} bytes after the last account")]
+    TrailingBytes(usize),
+
+    #[error(transparent)]
+    Wire(#[from] WireError),
+}
+
+/// Encodes `accounts` into the blob body that is dispersed to the DA layer.
+///
+/// Panics if there are more than `u32::MAX` accounts.
+pub fn encode_account_blob(accounts: &[Account]) -> Vec<u8> {
+    let count = u32::try_from(accounts.len()).expect("account count must fit in a u32");
+    let mut blob = Vec::with_capacity(ACCOUNT_BLOB_HEADER_LEN);
+    blob.push(ACCOUNT_BLOB_VERSION);
+    blob.extend_from_slice(&count.to_be_bytes());
+    for account in accounts {
+        blob.extend_from_slice(&encode_framed(account));
+    }
+    blob
+}
+
+/// Decodes a blob body written by [`encode_account_blob`] with any known
+/// layout version, rejecting versions this node does not understand.
+pub fn decode_account_blob(bytes: &[u8]) -> Result<Vec<Account>, BlobDecodeError> {
+    let Some((&version, rest)) = bytes.split_first() else {
+        return Err(BlobDecodeError::Truncated { expected: 0 });
+    };
+    match BlobVersion::try_from(version)? {
+        BlobVersion::V1 => decode_accounts::<AccountV1>(rest),
+        BlobVersion::V2 => decode_accounts::<Account>(rest),
+    }
+}
+
+/// Decodes the account count and frames that follow the version byte, with
+/// each frame holding an account in the layout `T`.
+fn decode_accounts<T>(body: &[u8]) -> Result<Vec<Account>, BlobDecodeError>
+where
+    T: DeserializeOwned + Into<Account>,
+{
+    if body.len() < ACCOUNT_BLOB_HEADER_LEN - 1 {
+        return Err(BlobDecodeError::Truncated { expected: 0 });
+    }
+    let mut count = [0u8; 4];
+    count.copy_from_slice(&body[..4]);
+    let expected = u32::from_be_bytes(count);
+
+    let mut buf = BytesMut::from(&body[4..]);
+    let mut accounts = Vec::new();
+    for _ in 0..expected {
+        let account: T = decode_framed(&mut buf)?.ok_or(BlobDecodeError::Truncated { expected })?;
+        accounts.push(account.into());
+    }
+    if !buf.is_empty() {
+        return Err(BlobDecodeError::TrailingBytes(buf.len()));
+    }
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod blob_tests {
+    use super::{
+        decode_account_blob, encode_account_blob, BlobDecodeError, BlobVersion,
+        ACCOUNT_BLOB_VERSION,
+    };
+    use crate::{Account, AccountHash, AccountType, Address, U256};
+
+    fn account(n: u8) -> Account {
+        Account::new(AccountType::User, None, Address::new([n; 20]), None)
+    }
+
+    #[test]
+    fn test_account_blob_round_trip() {
+        let accounts = vec![account(1), account(2), account(3)];
+        let blob = encode_account_blob(&accounts);
+        assert_eq!(blob[0], ACCOUNT_BLOB_VERSION);
+        assert_eq!(decode_account_blob(&blob).unwrap(), accounts);
+        assert_eq!(
+            decode_account_blob(&encode_account_blob(&[])).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_account_blob_version_mismatch_is_rejected() {
+        let mut blob = encode_account_blob(&[account(1)]);
+        blob[0] = ACCOUNT_BLOB_VERSION + 1;
+        assert!(matches!(
+            decode_account_blob(&blob),
+            Err(BlobDecodeError::UnsupportedVersion(version)) if version == ACCOUNT_BLOB_VERSION + 1
+        ));
+    }
+
+    /// A version 1 frame body, holding the original account layout followed
+    /// by the account hash: a user account owned by `[1; 20]` with nonce 3, hash
+    /// `[0xab; 32]` and a balance of 5 of program `[2; 20]`. Every address is
+    /// a `u64` length prefixed `0x` hex string, as is every `U256`.
+    const LEGACY_V1_ACCOUNT: &str = concat!(
+        "00000000002a00000000000000307830313031303130313031303130313031303130313031303130",
+        "31303130313031303130313031303101000000000000002a00000000000000307830323032303230",
+        "3230323032303230323032303230323032303230323032303230323032303230322a000000000000",
+        "00307830323032303230323032303230323032303230323032303230323032303230323032303230",
+        "3230322a000000000000003078303130313031303130313031303130313031303130313031303130",
+        "31303130313031303130313031420000000000000030783030303030303030303030303030303030",
+        "30303030303030303030303030303030303030303030303030303030303030303030303030303030",
+        "30303030303035000000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000010000004200000000000000307830303030303030303030303030303030303030",
+        "30303030303030303030303030303030303030303030303030303030303030303030303030303030",
+        "3030303033000000000000000000000000000000000000000000000000ababababababababababab",
+        "ababababababababababababababababababababab",
+    );
+
+    #[test]
+    fn test_decode_v1_account_blob() {
+        let body = hex::decode(LEGACY_V1_ACCOUNT).unwrap();
+        let mut blob = vec![1, 0, 0, 0, 1];
+        blob.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        blob.extend_from_slice(&body);
+        assert_eq!(BlobVersion::try_from(blob[0]).unwrap(), BlobVersion::V1);
+
+        let accounts = decode_account_blob(&blob).unwrap();
+        assert_eq!(accounts.len(), 1);
+        let (owner, program_id) = (Address::new([1; 20]), Address::new([2; 20]));
+        let account = &accounts[0];
+        assert_eq!(account.account_type(), AccountType::User);
+        assert_eq!(account.owner_address(), owner);
+        assert_eq!(account.nonce(), U256::from(3));
+        assert_eq!(account.hash(), AccountHash::new([0xab; 32]));
+        assert!(account.certificate().is_none());
+        assert_eq!(account.programs().len(), 1);
+        let token = &account.programs()[&program_id];
+        assert_eq!(token.program_id(), program_id);
+        assert_eq!(token.owner_id(), owner);
+        assert_eq!(token.balance(), U256::from(5));
+
+        assert_eq!(Account::decode_legacy_bincode(&body).unwrap(), *account);
+    }
+
+    #[test]
+    fn test_unknown_account_blob_version_is_rejected() {
+        let mut blob = encode_account_blob(&[account(1)]);
+        for version in [0, 0xff] {
+            blob[0] = version;
+            let err = decode_account_blob(&blob).unwrap_err();
+            assert!(matches!(err, BlobDecodeError::UnsupportedVersion(v) if v == version));
+            assert!(err.to_string().contains(&version.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_truncated_account_blob_is_rejected() {
+        let blob = encode_account_blob(&[account(1), account(2)]);
+        assert!(matches!(
+            decode_account_blob(&blob[..blob.len() - 1]),
+            Err(BlobDecodeError::Truncated { expected: 2 })
+        ));
+
+        let mut blob = blob;
+        blob.push(0);
+        assert!(matches!(
+            decode_account_blob(&blob),
+            Err(BlobDecodeError::TrailingBytes(1))
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{Address, U256};
+use secp256k1::PublicKey;
+use sha3::{Digest, Keccak256};
+
+/// Derives the `Address` of `pk` together with its 33 byte compressed
+/// encoding.
+///
+/// Both come from a single uncompressed serialization: the address is the
+/// last 20 bytes of the Keccak256 hash of the x and y coordinates, and the
+/// compressed key is the x coordinate prefixed with the parity of y.
+pub fn pubkey_to_address_and_bytes(pk: &PublicKey) -> (Address, [u8; 33]) {
+    let uncompressed = pk.serialize_uncompressed();
+
+    let hash = Keccak256::digest(&uncompressed[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[hash.len() - 20..]);
+
+    let mut compressed = [0u8; 33];
+    compressed[0] = 0x02 | (uncompressed[64] & 1);
+    compressed[1..].copy_from_slice(&uncompressed[1..33]);
+
+    (Address::new(address), compressed)
+}
+
+/// Derives the address of a program deployed by `deployer` at `nonce`, in
+/// the style of `CREATE`: the last 20 bytes of the Keccak256 hash of the
+/// deployer address followed by the big endian nonce.
+pub fn derive_program_address(deployer: &Address, nonce: U256) -> Address {
+    let mut nonce_bytes = [0u8; 32];
+    nonce.to_big_endian(&mut nonce_bytes);
+
+    let mut hasher = Keccak256::new();
+    hasher.update(deployer.inner());
+    hasher.update(nonce_bytes);
+    let hash = hasher.finalize();
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[hash.len() - 20..]);
+    Address::new(address)
+}
+
+#[cfg(test)]
+mod crypto_tests {
+    use super::{derive_program_address, pubkey_to_address_and_bytes};
+    use crate::{Address, U256};
+    use secp256k1::{PublicKey, SecretKey};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_pubkey_to_address_and_bytes_matches_from_public_key() {
+        for seed in 1..=8u8 {
+            let secret_key = SecretKey::from_slice(&[seed; 32]).unwrap();
+            let pk = PublicKey::from_secret_key_global(&secret_key);
+
+            let (address, compressed) = pubkey_to_address_and_bytes(&pk);
+            assert_eq!(address, Address::from(pk));
+            assert_eq!(compressed, pk.serialize());
+        }
+    }
+
+    #[test]
+    fn test_derive_program_address() {
+        let deployer = Address::new([1u8; 20]);
+        let address = derive_program_address(&deployer, U256::from(1));
+
+        assert_eq!(
+            address,
+            Address::from_str("0x5bcb17cc31b6be4d603a7c89eff1fdc86383d96f").unwrap()
+        );
+        assert_eq!(derive_program_address(&deployer, U256::from(1)), address);
+        assert_ne!(derive_program_address(&deployer, U256::from(2)), address);
+        assert_ne!(
+            derive_program_address(&Address::new([2u8; 20]), U256::from(1)),
+            address
+        );
+    }
+}
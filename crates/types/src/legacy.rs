@@ -0,0 +1,262 @@
+//! Account layouts from before bincode encoded addresses as their raw 20
+//! bytes, kept so accounts persisted or dispersed in them stay readable.
+//!
+//! Each type mirrors the current one field for field, with every
+//! [`Address`] replaced by a [`LegacyAddress`] that reads the `0x` prefixed
+//! hex string addresses were written as back then.
+use std::collections::{BTreeMap, BTreeSet};
+
+use bincode::Options;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    account::AddressVisitor, Account, AccountBuilder, AccountHash, AccountType, Address,
+    AddressOrNamespace, ArbitraryData, Metadata, Namespace, Status, Token, TokenBuilder, U256,
+};
+
+/// An address written as a `0x` prefixed hex string in every format.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct LegacyAddress(Address);
+
+impl<'de> Deserialize<'de> for LegacyAddress {
+    fn deserialize<D>(deserializer: D) -> Result<LegacyAddress, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_str(AddressVisitor)
+            .map(LegacyAddress)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum LegacyAccountType {
+    User,
+    Program(LegacyAddress),
+}
+
+impl From<LegacyAccountType> for AccountType {
+    fn from(value: LegacyAccountType) -> Self {
+        match value {
+            LegacyAccountType::User => AccountType::User,
+            LegacyAccountType::Program(address) => AccountType::Program(address.0),
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+enum LegacyAddressOrNamespace {
+    Address(LegacyAddress),
+    Namespace(Namespace),
+    This,
+}
+
+impl From<LegacyAddressOrNamespace> for AddressOrNamespace {
+    fn from(value: LegacyAddressOrNamespace) -> Self {
+        match value {
+            LegacyAddressOrNamespace::Address(address) => AddressOrNamespace::Address(address.0),
+            LegacyAddressOrNamespace::Namespace(namespace) => {
+                AddressOrNamespace::Namespace(namespace)
+            }
+            LegacyAddressOrNamespace::This => AddressOrNamespace::This,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyToken {
+    program_id: LegacyAddress,
+    owner_id: LegacyAddress,
+    balance: U256,
+    metadata: Metadata,
+    token_ids: Vec<U256>,
+    allowance: BTreeMap<LegacyAddress, U256>,
+    approvals: BTreeMap<LegacyAddress, Vec<U256>>,
+    data: ArbitraryData,
+    status: Status,
+}
+
+impl From<LegacyToken> for Token {
+    fn from(value: LegacyToken) -> Self {
+        let mut token = TokenBuilder::default()
+            .program_id(value.program_id.0)
+            .owner_id(value.owner_id.0)
+            .balance(value.balance)
+            .metadata(value.metadata)
+            .token_ids(value.token_ids)
+            .allowance(
+                value
+                    .allowance
+                    .into_iter()
+                    .map(|(spender, amount)| (spender.0, amount))
+                    .collect(),
+            )
+            .approvals(
+                value
+                    .approvals
+                    .into_iter()
+                    .map(|(spender, ids)| (spender.0, ids))
+                    .collect(),
+            )
+            .data(value.data)
+            .status(value.status)
+            .build()
+            .expect("every token field is set");
+        token.canonicalize();
+        token
+    }
+}
+
+/// The original layout of [`Account`], as persisted before accounts carried
+/// a hash or a certificate.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountV0 {
+    account_type: LegacyAccountType,
+    program_namespace: Option<LegacyAddressOrNamespace>,
+    owner_address: LegacyAddress,
+    programs: BTreeMap<LegacyAddress, LegacyToken>,
+    nonce: U256,
+    program_account_data: ArbitraryData,
+    program_account_metadata: Metadata,
+    program_account_linked_programs: BTreeSet<LegacyAddressOrNamespace>,
+}
+
+impl AccountV0 {
+    fn into_account(self, account_hash: AccountHash) -> Account {
+        AccountBuilder::default()
+            .account_type(self.account_type.into())
+            .program_namespace(self.program_namespace.map(Into::into))
+            .owner_address(self.owner_address.0)
+            .programs(
+                self.programs
+                    .into_iter()
+                    .map(|(program_id, token)| (program_id.0, token.into()))
+                    .collect(),
+            )
+            .nonce(self.nonce)
+            .program_account_data(self.program_account_data)
+            .program_account_metadata(self.program_account_metadata)
+            .program_account_linked_programs(
+                self.program_account_linked_programs
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
+            )
+            .account_hash(account_hash)
+            .build()
+            .expect("every account field is set")
+    }
+}
+
+impl From<AccountV0> for Account {
+    fn from(value: AccountV0) -> Self {
+        let mut account = value.into_account(AccountHash::default());
+        account.update_hash();
+        account
+    }
+}
+
+/// The [`AccountV0`] layout followed by the account hash, as written to
+/// version 1 account blobs and persisted until addresses were encoded as raw
+/// bytes.
+#[derive(Deserialize)]
+pub(crate) struct AccountV1 {
+    account: AccountV0,
+    account_hash: AccountHash,
+}
+
+impl From<AccountV1> for Account {
+    fn from(value: AccountV1) -> Self {
+        value.account.into_account(value.account_hash)
+    }
+}
+
+/// The bincode options `bincode::deserialize` uses, except that bytes left
+/// over after the value are an error, so one layout cannot be read as a
+/// prefix of another.
+fn exact() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+}
+
+/// Decodes an account bincode encoded in the [`AccountV1`] or [`AccountV0`]
+/// layout. Accounts read from the hashless layout get their hash computed.
+pub(crate) fn decode_account(bytes: &[u8]) -> bincode::Result<Account> {
+    exact()
+        .deserialize::<AccountV1>(bytes)
+        .map(Into::into)
+        .or_else(|_| exact().deserialize::<AccountV0>(bytes).map(Into::into))
+}
+
+#[cfg(test)]
+mod legacy_tests {
+    use super::decode_account;
+    use crate::{AccountType, Address, AddressOrNamespace, Namespace, U256};
+
+    /// A program account encoded exactly as the original `Account` was, with
+    /// every address and `U256` a `u64` length prefixed `0x` hex string. The
+    /// account is program `[3; 20]` owned by `[1; 20]`, namespaced `demo`,
+    /// with nonce 3, a `name` metadata entry, linked program `[5; 20]` and a
+    /// balance of 5 of program `[2; 20]`, of which `[4; 20]` may spend 7.
+    const ORIGINAL_ACCOUNT: &str = concat!(
+        "010000002a0000000000000030783033303330333033303330333033303330333033303330333033",
+        "30333033303330333033303330330101000000040000000000000064656d6f2a0000000000000030",
+        "78303130313031303130313031303130313031303130313031303130313031303130313031303130",
+        "3101000000000000002a000000000000003078303230323032303230323032303230323032303230",
+        "323032303230323032303230323032303230322a0000000000000030783032303230323032303230",
+        "32303230323032303230323032303230323032303230323032303230322a00000000000000307830",
+        "31303130313031303130313031303130313031303130313031303130313031303130313031303142",
+        "00000000000000307830303030303030303030303030303030303030303030303030303030303030",
+        "30303030303030303030303030303030303030303030303030303030303030303500000000000000",
+        "00000000000000000001000000000000002a00000000000000307830343034303430343034303430",
+        "34303430343034303430343034303430343034303430343034303442000000000000003078303030",
+        "30303030303030303030303030303030303030303030303030303030303030303030303030303030",
+        "30303030303030303030303030303030303030303700000000000000000000000000000000010000",
+        "00420000000000000030783030303030303030303030303030303030303030303030303030303030",
+        "30303030303030303030303030303030303030303030303030303030303030303030330000000000",
+        "000000010000000000000004000000000000006e616d65040000000000000064656d6f0100000000",
+        "000000000000002a0000000000000030783035303530353035303530353035303530353035303530",
+        "3530353035303530353035303530353035",
+    );
+
+    #[test]
+    fn test_decode_original_account_layout() {
+        let bytes = hex::decode(ORIGINAL_ACCOUNT).unwrap();
+        let account = decode_account(&bytes).unwrap();
+
+        let owner = Address::new([1; 20]);
+        let program_id = Address::new([2; 20]);
+        assert_eq!(
+            account.account_type(),
+            AccountType::Program(Address::new([3; 20]))
+        );
+        assert_eq!(
+            account.program_namespace(),
+            Some(AddressOrNamespace::Namespace(Namespace("demo".to_string())))
+        );
+        assert_eq!(account.owner_address(), owner);
+        assert_eq!(account.nonce(), U256::from(3));
+        assert_eq!(
+            account.program_account_metadata().inner().get("name"),
+            Some(&"demo".to_string())
+        );
+        assert!(account
+            .program_account_linked_programs()
+            .contains(&AddressOrNamespace::Address(Address::new([5; 20]))));
+        let token = &account.programs()[&program_id];
+        assert_eq!(token.program_id(), program_id);
+        assert_eq!(token.owner_id(), owner);
+        assert_eq!(token.balance(), U256::from(5));
+        assert_eq!(token.allowance_of(&Address::new([4; 20])), U256::from(7));
+        assert_eq!(account.hash(), account.compute_hash());
+        assert!(account.certificate().is_none());
+
+        assert!(decode_account(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode_account(&bincode::serialize(&account).unwrap()).is_err());
+    }
+}
@@ -1,13 +1,24 @@
 pub mod account;
+pub mod blob;
+pub mod crypto;
+mod legacy;
 pub mod persistence;
 pub mod programming_model;
 pub mod signing;
+pub mod state;
+pub mod strict;
 pub mod token;
 pub mod transaction;
+pub mod wire;
 
 pub use account::*;
+pub use blob::*;
+pub use crypto::*;
 pub use persistence::*;
 pub use programming_model::*;
 pub use signing::*;
+pub use state::*;
+pub use strict::*;
 pub use token::*;
 pub use transaction::*;
+pub use wire::*;
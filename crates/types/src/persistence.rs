@@ -64,6 +64,18 @@ where
     K: std::cmp::Eq + std::hash::Hash + Clone,
     V: Clone;
 
+impl<K, V> Default for MockPersistenceStore<K, V>
+where
+    K: std::cmp::Eq + std::hash::Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        MockPersistenceStore(std::sync::Arc::new(tokio::sync::Mutex::new(
+            std::collections::HashMap::new(),
+        )))
+    }
+}
+
 impl<K, V> PersistenceStore for MockPersistenceStore<K, V>
 where
     K: std::cmp::Eq + std::hash::Hash + Clone,
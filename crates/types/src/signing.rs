@@ -123,6 +123,16 @@ impl RecoverableSignature {
     pub fn v_into_bytes(&self) -> [u8; 4] {
         self.v.to_le_bytes()
     }
+
+    /// The raw recovery id (`0` or `1` for well formed signatures) encoded in `v`.
+    pub fn recovery_id(&self) -> i32 {
+        normalize_recovery_id(self.v)
+    }
+
+    /// The EIP-155 chain id encoded in `v`, if any.
+    pub fn chain_id(&self) -> Option<u64> {
+        chain_id_from_v(self.v)
+    }
 }
 
 impl From<Signature> for RecoverableSignature {
@@ -145,62 +155,51 @@ impl From<Signature> for RecoverableSignature {
 impl TryFrom<RecoverableSignature> for Signature {
     type Error = secp256k1::Error;
     fn try_from(value: RecoverableSignature) -> Result<Signature, Self::Error> {
-        let mut data = Vec::new();
-        let mut recovery_id = value.get_v();
-        if (0..=3).contains(&recovery_id) {
-            tracing::info!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else if (27..=30).contains(&recovery_id) {
-            recovery_id -= 27;
-            tracing::info!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else if (35..=38).contains(&recovery_id) {
-            recovery_id -= 35;
-            tracing::info!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else {
-            tracing::info!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        }
+        Signature::try_from(&value)
     }
 }
 
 impl TryFrom<&RecoverableSignature> for Signature {
     type Error = secp256k1::Error;
     fn try_from(value: &RecoverableSignature) -> Result<Signature, Self::Error> {
+        let recovery_id = value.recovery_id();
+        tracing::warn!(
+            "using r: {:?} and s: {:?} with recovery_id: {:?} (v: {:?}) to convert to secp256k1 signature",
+            &value.get_r(),
+            value.get_s(),
+            recovery_id,
+            value.get_v()
+        );
         let mut data = Vec::new();
-        let mut recovery_id = value.get_v();
-        if (0..=3).contains(&recovery_id) {
-            tracing::warn!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else if (27..=30).contains(&recovery_id) {
-            recovery_id -= 27;
-            tracing::warn!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else if (35..=38).contains(&recovery_id) {
-            recovery_id -= 35;
-            tracing::warn!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        } else {
-            tracing::info!("using r: {:?} and s: {:?} with recovery_id: {:?} to convert to secp256k1 signature", &value.get_r(), value.get_s(), value.get_v());
-            data.extend_from_slice(&value.get_r());
-            data.extend_from_slice(&value.get_s());
-            Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
-        }
+        data.extend_from_slice(&value.get_r());
+        data.extend_from_slice(&value.get_s());
+        Signature::from_compact(&data, RecoveryId::from_i32(recovery_id)?)
+    }
+}
+
+/// Normalizes a signature `v` value into a raw secp256k1 recovery id.
+///
+/// Raw recovery ids (`0..=3`) are returned as is, legacy electrum style
+/// values (`27..=30`) are offset by 27 and EIP-155 values
+/// (`chain_id * 2 + 35 + recovery_id`) are reduced back to `0` or `1`.
+/// Any other value is returned untouched and will fail to convert into a
+/// `RecoveryId`.
+pub fn normalize_recovery_id(v: i32) -> i32 {
+    match v {
+        0..=3 => v,
+        27..=30 => v - 27,
+        v if v >= 35 => (v - 35) % 2,
+        _ => v,
+    }
+}
+
+/// Extracts the EIP-155 chain id from a signature `v` value, if `v` was
+/// encoded as `chain_id * 2 + 35 + recovery_id`.
+pub fn chain_id_from_v(v: i32) -> Option<u64> {
+    if v >= 35 {
+        Some(((v - 35) / 2) as u64)
+    } else {
+        None
     }
 }
 
@@ -213,6 +212,14 @@ pub struct Certificate {
 }
 
 impl Certificate {
+    pub fn quorum_id(&self) -> [u8; 20] {
+        self.quorum_id
+    }
+
+    pub fn quorum_sigs(&self) -> &BTreeSet<RecoverableSignature> {
+        &self.quorum_sigs
+    }
+
     // Converts the certificate into a vector of bytes with the first 20
     // being the quorum id, followed by PublicKey (33) and Signature (
     pub fn to_vec(&self) -> Vec<u8> {
@@ -0,0 +1,194 @@
+use crate::{Account, AccountHash, Address};
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+
+/// Hashes two sibling nodes into their parent. The pair is sorted first, so
+/// a proof only needs the siblings and not which side each one is on.
+fn hash_pair(a: &AccountHash, b: &AccountHash) -> AccountHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha3_256::new();
+    hasher.update(left.inner());
+    hasher.update(right.inner());
+    AccountHash::new(hasher.finalize().into())
+}
+
+/// Hashes each pair of nodes into the level above. The last node of a level
+/// with an odd number of nodes is carried up unchanged.
+fn next_level(level: &[AccountHash]) -> Vec<AccountHash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+fn leaves(accounts: &BTreeMap<Address, Account>) -> Vec<AccountHash> {
+    accounts.values().map(Account::compute_hash).collect()
+}
+
+/// Computes the Merkle root over the hashes of `accounts`, taken in address
+/// order. The root of a single account is its hash, and the root of no
+/// accounts is the zero hash.
+pub fn compute_state_root(accounts: &BTreeMap<Address, Account>) -> AccountHash {
+    let mut level = leaves(accounts);
+    if level.is_empty() {
+        return AccountHash::default();
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Returns the sibling hashes on the path from the account at `address` up
+/// to the root computed by [`compute_state_root`], lowest level first.
+/// Levels where the account's node is carried up without a sibling add
+/// nothing to the path. Returns `None` if `address` is not in `accounts`.
+pub fn compute_state_proof(
+    accounts: &BTreeMap<Address, Account>,
+    address: &Address,
+) -> Option<Vec<AccountHash>> {
+    let mut index = accounts.keys().position(|key| key == address)?;
+    let mut level = leaves(accounts);
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Checks that `account` is included under `root`, given the sibling path
+/// returned by [`compute_state_proof`].
+pub fn verify_account_inclusion(
+    account: &Account,
+    proof: &[AccountHash],
+    root: &AccountHash,
+) -> bool {
+    let node = proof.iter().fold(account.compute_hash(), |node, sibling| {
+        hash_pair(&node, sibling)
+    });
+    node == *root
+}
+
+#[cfg(test)]
+mod state_tests {
+    use super::{compute_state_proof, compute_state_root, verify_account_inclusion};
+    use crate::{Account, AccountHash, AccountType, Address, Token, U256};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn accounts(owners: &[u8]) -> BTreeMap<Address, Account> {
+        owners
+            .iter()
+            .map(|n| {
+                let address = Address::new([*n; 20]);
+                (
+                    address,
+                    Account::new(AccountType::User, None, address, None),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_state_root_of_no_accounts_is_zero() {
+        assert_eq!(compute_state_root(&BTreeMap::new()), AccountHash::default());
+    }
+
+    #[test]
+    fn test_state_root_of_one_account() {
+        let accounts = accounts(&[1]);
+        assert_eq!(
+            compute_state_root(&accounts),
+            AccountHash::from_str(
+                "0x5980afb194a1572a4e5e745f83a78bbfb38309912f3abdb72380e960f82b5ade"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            compute_state_proof(&accounts, &Address::new([1; 20])),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_state_root_of_two_accounts() {
+        let accounts = accounts(&[2, 1]);
+        assert_eq!(
+            compute_state_root(&accounts),
+            AccountHash::from_str(
+                "0xc5faa2d6721203cf9bb56c5f16c13bdf7a8f61d86708ad38b18883650a6f4027"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            compute_state_proof(&accounts, &Address::new([1; 20])),
+            Some(vec![accounts[&Address::new([2; 20])].compute_hash()])
+        );
+        assert_eq!(compute_state_proof(&accounts, &Address::new([3; 20])), None);
+    }
+
+    #[test]
+    fn test_state_proof_skips_carried_nodes() {
+        let accounts = accounts(&[1, 2, 3]);
+        assert_eq!(
+            compute_state_proof(&accounts, &Address::new([3; 20])).map(|proof| proof.len()),
+            Some(1)
+        );
+        assert_eq!(
+            compute_state_proof(&accounts, &Address::new([1; 20])).map(|proof| proof.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_verify_account_inclusion() {
+        let accounts = accounts(&[1, 2, 3, 4, 5]);
+        let root = compute_state_root(&accounts);
+        for (address, account) in &accounts {
+            let proof = compute_state_proof(&accounts, address).unwrap();
+            assert!(verify_account_inclusion(account, &proof, &root));
+        }
+    }
+
+    #[test]
+    fn test_verify_account_inclusion_rejects_tampered_account() {
+        let accounts = accounts(&[1, 2, 3]);
+        let root = compute_state_root(&accounts);
+        let address = Address::new([2; 20]);
+        let proof = compute_state_proof(&accounts, &address).unwrap();
+
+        let mut tampered = accounts[&address].clone();
+        let program_id = Address::new([9; 20]);
+        let mut token = Token::empty(program_id, address);
+        *token.balance_mut() = U256::from(100);
+        tampered.insert_program(&program_id, token).unwrap();
+        assert!(!verify_account_inclusion(&tampered, &proof, &root));
+    }
+
+    #[test]
+    fn test_verify_account_inclusion_rejects_wrong_root() {
+        let other_root = compute_state_root(&accounts(&[1, 2, 4]));
+        let accounts = accounts(&[1, 2, 3]);
+        let address = Address::new([1; 20]);
+        let proof = compute_state_proof(&accounts, &address).unwrap();
+
+        assert!(!verify_account_inclusion(
+            &accounts[&address],
+            &proof,
+            &other_root
+        ));
+        assert!(!verify_account_inclusion(
+            &accounts[&address],
+            &proof,
+            &AccountHash::default()
+        ));
+    }
+}
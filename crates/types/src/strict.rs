@@ -0,0 +1,151 @@
+//! Strict JSON decoding for types received from clients.
+//!
+//! The derived `Deserialize` impls ignore keys they do not recognise, so a
+//! misspelled field or a payload from a newer client is silently parsed with
+//! that field dropped. [`StrictDeserialize::from_json_strict`] rejects such
+//! input instead, while plain `serde_json` decoding keeps the lenient
+//! behaviour stored data relies on.
+use serde::{de::DeserializeOwned, forward_to_deserialize_any, Deserializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{Account, Token, Transaction};
+
+#[derive(Debug, Error)]
+pub enum StrictJsonError {
+    #[error("unknown field `{field}` in {ty}")]
+    UnknownField { ty: &'static str, field: String },
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+pub trait StrictDeserialize: DeserializeOwned {
+    /// Rejects any key of `value` that is not a field of `Self`.
+    fn check_fields(value: &Value) -> Result<(), StrictJsonError> {
+        deny_unknown_fields::<Self>(value)
+    }
+
+    /// Decodes `json`, failing if it carries fields `Self` does not define.
+    fn from_json_strict(json: &str) -> Result<Self, StrictJsonError> {
+        let value: Value = serde_json::from_str(json)?;
+        Self::check_fields(&value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl StrictDeserialize for Token {}
+
+impl StrictDeserialize for Transaction {}
+
+impl StrictDeserialize for Account {
+    fn check_fields(value: &Value) -> Result<(), StrictJsonError> {
+        deny_unknown_fields::<Self>(value)?;
+        if let Some(Value::Object(programs)) = value.get("programs") {
+            for token in programs.values() {
+                Token::check_fields(token)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn deny_unknown_fields<T: DeserializeOwned>(value: &Value) -> Result<(), StrictJsonError> {
+    let Value::Object(map) = value else {
+        return Ok(());
+    };
+    let (ty, fields) = struct_fields::<T>();
+    match map.keys().find(|key| !fields.contains(&key.as_str())) {
+        Some(field) => Err(StrictJsonError::UnknownField {
+            ty,
+            field: field.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The name and field names a derived `Deserialize` impl hands to
+/// `deserialize_struct`, found without decoding anything.
+fn struct_fields<T: DeserializeOwned>() -> (&'static str, &'static [&'static str]) {
+    let mut found = ("", &[][..]);
+    let _ = T::deserialize(FieldNames(&mut found));
+    found
+}
+
+struct FieldNames<'a>(&'a mut (&'static str, &'static [&'static str]));
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(serde::de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = (name, fields);
+        Err(serde::de::Error::custom("only field names are read"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod strict_tests {
+    use super::{StrictDeserialize, StrictJsonError};
+    use crate::{Account, AccountType, Address, Token, Transaction};
+    use serde_json::Value;
+
+    fn with_extra_field(mut value: Value) -> String {
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("extra".to_string(), Value::Bool(true));
+        value.to_string()
+    }
+
+    #[test]
+    fn test_strict_rejects_unknown_fields() {
+        let tx = Transaction::default();
+        let json = serde_json::to_string(&tx).unwrap();
+        assert_eq!(Transaction::from_json_strict(&json).unwrap(), tx);
+
+        let extra = with_extra_field(serde_json::to_value(&tx).unwrap());
+        assert_eq!(serde_json::from_str::<Transaction>(&extra).unwrap(), tx);
+        assert!(matches!(
+            Transaction::from_json_strict(&extra),
+            Err(StrictJsonError::UnknownField { ty: "Transaction", field }) if field == "extra"
+        ));
+
+        let owner = Address::new([1u8; 20]);
+        let program_id = Address::new([2u8; 20]);
+        let mut account = Account::new(AccountType::User, None, owner, None);
+        account
+            .insert_program(&program_id, Token::empty(program_id, owner))
+            .unwrap();
+        let json = serde_json::to_string(&account).unwrap();
+        assert_eq!(Account::from_json_strict(&json).unwrap(), account);
+
+        let mut value = serde_json::to_value(&account).unwrap();
+        let token = value["programs"]
+            .as_object_mut()
+            .unwrap()
+            .values_mut()
+            .next()
+            .unwrap();
+        *token = serde_json::from_str(&with_extra_field(token.take())).unwrap();
+        assert!(serde_json::from_value::<Account>(value.clone()).is_ok());
+        assert!(matches!(
+            Account::from_json_strict(&value.to_string()),
+            Err(StrictJsonError::UnknownField { ty: "Token", field }) if field == "extra"
+        ));
+    }
+}
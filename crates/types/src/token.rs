@@ -6,9 +6,10 @@ use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
 use std::ops::{AddAssign, SubAssign};
+use thiserror::Error;
 use uint::construct_uint;
 
-use crate::{Address, RecoverableSignature, Transaction};
+use crate::{AccountError, Address, RecoverableSignature, Transaction};
 
 pub const TOKEN_WITNESS_VERSION: &str = "0.1.0";
 
@@ -87,6 +88,65 @@ impl<'de> Visitor<'de> for U256Visitor {
     }
 }
 
+/// Serde functions for [`U256`] fields exposed over RPC, used with
+/// `#[serde(with = "u256_hex")]`. Values are written as Ethereum JSON-RPC
+/// quantities, `0x` prefixed hex without leading zeros. Any form accepted
+/// by the default `U256` deserializer is still read.
+pub mod u256_hex {
+    use super::{U256Visitor, U256};
+    use serde::{de::Visitor, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{:x}", value))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<U256, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        match value.strip_prefix("0x") {
+            Some(digits) if !digits.is_empty() && digits.len() < 64 => {
+                U256::from_str_radix(digits, 16).map_err(serde::de::Error::custom)
+            }
+            _ => U256Visitor.visit_str(&value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ParseAmountError {
+    #[error("amount {0:?} is not a decimal or 0x prefixed hex number")]
+    Invalid(String),
+
+    #[error("amount {0} does not fit in 256 bits")]
+    Overflow(String),
+}
+
+/// Parses an amount written either in decimal or as a `0x` prefixed hex
+/// number, as typed on the command line.
+pub fn parse_amount(s: &str) -> Result<U256, ParseAmountError> {
+    let invalid = || ParseAmountError::Invalid(s.to_string());
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => {
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            if digits.trim_start_matches('0').len() > 64 {
+                return Err(ParseAmountError::Overflow(s.to_string()));
+            }
+            U256::from_str_radix(digits, 16).map_err(|_| invalid())
+        }
+        None => U256::from_dec_str(s).map_err(|e| match e {
+            uint::FromDecStrErr::InvalidLength => ParseAmountError::Overflow(s.to_string()),
+            uint::FromDecStrErr::InvalidCharacter => invalid(),
+        }),
+    }
+}
+
 impl From<EthU256> for &mut U256 {
     fn from(value: EthU256) -> Self {
         value.into()
@@ -135,6 +195,33 @@ impl From<&EthU256> for U256 {
     }
 }
 
+/// The most bytes of keys and values a single token's metadata or data may
+/// hold, so that one token cannot push an account blob past the DA limit.
+pub const DEFAULT_MAX_TOKEN_DATA_LEN: usize = 64 * 1024;
+
+/// Metadata or data that would hold more bytes than allowed.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("{len} bytes of keys and values exceed the limit of {max} bytes")]
+pub struct SizeError {
+    pub len: usize,
+    pub max: usize,
+}
+
+fn entries_len(entries: &BTreeMap<String, String>) -> usize {
+    entries
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum()
+}
+
+fn check_entries_len(entries: &BTreeMap<String, String>, max: usize) -> Result<(), SizeError> {
+    let len = entries_len(entries);
+    if len > max {
+        return Err(SizeError { len, max });
+    }
+    Ok(())
+}
+
 /// Represents a generic data container.
 ///
 /// This structure is used to store arbitrary data as a vector of bytes (`Vec<u8>`).
@@ -157,6 +244,27 @@ impl ArbitraryData {
         Self(BTreeMap::new())
     }
 
+    /// Wraps `entries`, rejecting them if their keys and values add up to
+    /// more than [`DEFAULT_MAX_TOKEN_DATA_LEN`] bytes.
+    pub fn try_new(entries: BTreeMap<String, String>) -> Result<Self, SizeError> {
+        Self::try_new_with_limit(entries, DEFAULT_MAX_TOKEN_DATA_LEN)
+    }
+
+    /// Wraps `entries`, rejecting them if their keys and values add up to
+    /// more than `max` bytes.
+    pub fn try_new_with_limit(
+        entries: BTreeMap<String, String>,
+        max: usize,
+    ) -> Result<Self, SizeError> {
+        check_entries_len(&entries, max)?;
+        Ok(Self(entries))
+    }
+
+    /// The number of bytes taken up by every key and value.
+    pub fn byte_len(&self) -> usize {
+        entries_len(&self.0)
+    }
+
     pub fn insert(&mut self, key: String, value: String) {
         self.0.insert(key, value);
     }
@@ -208,6 +316,27 @@ impl Metadata {
         Self(BTreeMap::new())
     }
 
+    /// Wraps `entries`, rejecting them if their keys and values add up to
+    /// more than [`DEFAULT_MAX_TOKEN_DATA_LEN`] bytes.
+    pub fn try_new(entries: BTreeMap<String, String>) -> Result<Self, SizeError> {
+        Self::try_new_with_limit(entries, DEFAULT_MAX_TOKEN_DATA_LEN)
+    }
+
+    /// Wraps `entries`, rejecting them if their keys and values add up to
+    /// more than `max` bytes.
+    pub fn try_new_with_limit(
+        entries: BTreeMap<String, String>,
+        max: usize,
+    ) -> Result<Self, SizeError> {
+        check_entries_len(&entries, max)?;
+        Ok(Self(entries))
+    }
+
+    /// The number of bytes taken up by every key and value.
+    pub fn byte_len(&self) -> usize {
+        entries_len(&self.0)
+    }
+
     pub fn get(&self, key: &str) -> Option<&String> {
         self.0.get(key)
     }
@@ -256,6 +385,37 @@ pub enum TokenType {
     Data,
 }
 
+/// A change to a single token: a balance move, optionally together with
+/// replacement metadata and data that are applied in the same step.
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDelta {
+    receive: U256,
+    send: U256,
+    #[builder(default)]
+    metadata: Option<Metadata>,
+    #[builder(default)]
+    data: Option<ArbitraryData>,
+}
+
+impl TokenDelta {
+    pub fn receive(&self) -> U256 {
+        self.receive
+    }
+
+    pub fn send(&self) -> U256 {
+        self.send
+    }
+
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    pub fn data(&self) -> Option<&ArbitraryData> {
+        self.data.as_ref()
+    }
+}
+
 #[derive(
     Builder, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
@@ -265,6 +425,9 @@ pub struct Token {
     owner_id: Address,
     balance: U256,
     metadata: Metadata,
+    /// Kept in ascending order, which [`Token::owns_id`] and
+    /// [`Token::remove_id`] rely on. Decoding sorts them.
+    #[serde(deserialize_with = "deserialize_sorted_ids")]
     token_ids: Vec<U256>,
     allowance: BTreeMap<Address, U256>,
     approvals: BTreeMap<Address, Vec<U256>>,
@@ -272,6 +435,16 @@ pub struct Token {
     status: Status,
 }
 
+/// Decodes token ids in ascending order, whatever order they were stored or
+/// sent in.
+fn deserialize_sorted_ids<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<U256>, D::Error> {
+    let mut ids = Vec::<U256>::deserialize(deserializer)?;
+    ids.sort_unstable();
+    Ok(ids)
+}
+
 impl Token {
     pub(crate) fn set_metadata(&mut self, metadata: Metadata) {
         self.metadata = metadata;
@@ -328,7 +501,10 @@ impl Token {
         &mut self,
         token_ids: &Vec<U256>,
     ) -> Result<(), Box<dyn std::error::Error + Send>> {
-        self.token_ids.extend(token_ids);
+        for id in token_ids {
+            self.mint_id(*id)
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+        }
         Ok(())
     }
 
@@ -567,6 +743,23 @@ pub enum StatusValue {
 }
 
 impl Token {
+    /// Creates a token for `program_id` owned by `owner_id` with a zero
+    /// balance, no token ids, allowances, approvals, metadata or data, and
+    /// a `Free` status.
+    pub fn empty(program_id: Address, owner_id: Address) -> Token {
+        Token {
+            program_id,
+            owner_id,
+            balance: U256::from(0),
+            metadata: Metadata::new(),
+            token_ids: Vec::new(),
+            allowance: BTreeMap::new(),
+            approvals: BTreeMap::new(),
+            data: ArbitraryData::new(),
+            status: Status::Free,
+        }
+    }
+
     pub fn program_id(&self) -> Address {
         self.program_id
     }
@@ -595,6 +788,9 @@ impl Token {
         self.token_ids.clone()
     }
 
+    /// Direct access to the token ids held. Replacing or reordering them can
+    /// leave them unsorted, which breaks [`Token::owns_id`] and
+    /// [`Token::remove_id`] until [`Token::canonicalize`] is called.
     pub fn token_ids_mut(&mut self) -> &mut Vec<U256> {
         &mut self.token_ids
     }
@@ -631,12 +827,263 @@ impl Token {
         &mut self.status
     }
 
-    pub fn update_balance(&mut self, receive: U256, send: U256) {
-        self.balance += receive;
-        self.balance -= send;
+    pub fn is_locked(&self) -> bool {
+        self.status == Status::Locked
+    }
+
+    /// True if the token holds any token ids, making it a non-fungible
+    /// collection rather than a plain balance.
+    pub fn is_nft(&self) -> bool {
+        !self.token_ids.is_empty()
+    }
+
+    /// True if the token holds no token ids, so only its balance matters.
+    pub fn is_fungible(&self) -> bool {
+        !self.is_nft()
+    }
+
+    /// The amount that can currently be spent from the token, which is zero
+    /// while the token is locked.
+    pub fn spendable(&self) -> U256 {
+        if self.is_locked() {
+            return U256::from(0);
+        }
+        self.balance
+    }
+
+    /// True if `amount` can be spent from the token right now.
+    pub fn can_spend(&self, amount: U256) -> bool {
+        !self.is_locked() && self.balance >= amount
+    }
+
+    /// Locks the token, freezing its balance and allowances until
+    /// [`Token::unlock`] is called. Errors if the token is already locked.
+    pub fn lock(&mut self) -> Result<(), AccountError> {
+        if self.is_locked() {
+            return Err(BalanceError::Locked(self.program_id).into());
+        }
+
+        self.status = Status::Locked;
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) {
+        self.status = Status::Free;
+    }
+
+    /// Credits `receive` and then debits `send` from the token balance.
+    ///
+    /// The balance is only modified if both steps succeed, so an error
+    /// leaves the token untouched. Locked tokens reject any update.
+    pub fn update_balance(&mut self, receive: U256, send: U256) -> Result<(), BalanceError> {
+        if self.is_locked() {
+            return Err(BalanceError::Locked(self.program_id));
+        }
+
+        let available = self
+            .balance
+            .checked_add(receive)
+            .ok_or(BalanceError::Overflow {
+                receive,
+                balance: self.balance,
+            })?;
+        self.balance = available
+            .checked_sub(send)
+            .ok_or(BalanceError::InsufficientBalance { send, available })?;
+        Ok(())
+    }
+
+    /// Adds the balance of `rhs` to this token, leaving it untouched if the
+    /// sum would overflow.
+    pub fn try_add(&mut self, rhs: &Token) -> Result<(), BalanceError> {
+        self.balance = self
+            .balance
+            .checked_add(rhs.balance)
+            .ok_or(BalanceError::Overflow {
+                receive: rhs.balance,
+                balance: self.balance,
+            })?;
+        Ok(())
+    }
+
+    /// Subtracts the balance of `rhs` from this token, leaving it untouched
+    /// if `rhs` holds more than this token.
+    pub fn try_sub(&mut self, rhs: &Token) -> Result<(), BalanceError> {
+        self.balance =
+            self.balance
+                .checked_sub(rhs.balance)
+                .ok_or(BalanceError::InsufficientBalance {
+                    send: rhs.balance,
+                    available: self.balance,
+                })?;
+        Ok(())
+    }
+
+    /// Combines `other` into this token: balances and allowances are summed,
+    /// and token ids and approved ids are unioned. The owner, metadata and
+    /// data of this token are kept. Errors without changing anything if
+    /// `other` belongs to a different program, this token is locked, or a
+    /// sum would overflow.
+    pub fn merge(&mut self, other: Token) -> Result<(), AccountError> {
+        if other.program_id != self.program_id {
+            return Err(AccountError::ProgramMismatch {
+                expected: self.program_id,
+                found: other.program_id,
+            });
+        }
+        if self.is_locked() {
+            return Err(BalanceError::Locked(self.program_id).into());
+        }
+
+        let balance = self
+            .balance
+            .checked_add(other.balance)
+            .ok_or(BalanceError::Overflow {
+                receive: other.balance,
+                balance: self.balance,
+            })?;
+        let mut allowance = self.allowance.clone();
+        for (spender, amount) in other.allowance {
+            let entry = allowance.entry(spender).or_default();
+            *entry = entry.checked_add(amount).ok_or(BalanceError::Overflow {
+                receive: amount,
+                balance: *entry,
+            })?;
+        }
+
+        self.balance = balance;
+        self.allowance = allowance;
+        self.token_ids.extend(other.token_ids);
+        for (spender, ids) in other.approvals {
+            self.approvals.entry(spender).or_default().extend(ids);
+        }
+        self.canonicalize();
+        self.token_ids.dedup();
+        for ids in self.approvals.values_mut() {
+            ids.dedup();
+        }
+        Ok(())
+    }
+
+    /// Moves `amount` of the fungible balance out of this token and returns
+    /// it as a new token for the same program and owner. Errors without
+    /// changing anything if this token holds token ids, which cannot be
+    /// divided, or if it is locked or holds less than `amount`.
+    pub fn split(&mut self, amount: U256) -> Result<Token, AccountError> {
+        if self.is_nft() {
+            return Err(AccountError::NonFungibleSplit(self.program_id));
+        }
+        self.update_balance(U256::from(0), amount)?;
+        let mut split = Token::empty(self.program_id, self.owner_id);
+        split.balance = amount;
+        Ok(split)
+    }
+
+    /// Applies the balance change in `delta`, then replaces the metadata and
+    /// data with the ones in `delta` if present. Nothing is changed if the
+    /// balance update fails.
+    pub fn apply_delta(&mut self, delta: &TokenDelta) -> Result<(), BalanceError> {
+        self.update_balance(delta.receive, delta.send)?;
+        if let Some(metadata) = &delta.metadata {
+            self.metadata = metadata.clone();
+        }
+        if let Some(data) = &delta.data {
+            self.data = data.clone();
+        }
+        Ok(())
+    }
+
+    /// Adds `id` to the token ids held, keeping them sorted. Errors if the
+    /// id is already held.
+    pub fn mint_id(&mut self, id: U256) -> Result<(), AccountError> {
+        match self.token_ids.binary_search(&id) {
+            Ok(_) => Err(AccountError::DuplicateTokenId(id)),
+            Err(pos) => {
+                self.token_ids.insert(pos, id);
+                Ok(())
+            }
+        }
+    }
+
+    /// Sorts the token ids held and the ids in every approval, so that two
+    /// tokens with the same logical state serialize to the same bytes.
+    pub fn canonicalize(&mut self) {
+        self.token_ids.sort_unstable();
+        for ids in self.approvals.values_mut() {
+            ids.sort_unstable();
+        }
+    }
+
+    /// True if the token is already in the form produced by
+    /// [`Token::canonicalize`].
+    pub fn is_canonical(&self) -> bool {
+        let sorted = |ids: &[U256]| ids.windows(2).all(|pair| pair[0] <= pair[1]);
+        sorted(&self.token_ids) && self.approvals.values().all(|ids| sorted(ids))
+    }
+
+    pub fn owns_id(&self, id: &U256) -> bool {
+        self.token_ids.binary_search(id).is_ok()
+    }
+
+    /// Removes `id` from the token ids held. Errors if the id is not held.
+    pub fn remove_id(&mut self, id: &U256) -> Result<(), AccountError> {
+        let pos = self
+            .token_ids
+            .binary_search(id)
+            .map_err(|_| AccountError::UnknownTokenId(*id))?;
+        self.token_ids.remove(pos);
+        Ok(())
+    }
+
+    /// Sets the amount `spender` may move on behalf of the token owner,
+    /// replacing any previous allowance.
+    pub fn approve(&mut self, spender: Address, amount: U256) {
+        if amount.is_zero() {
+            self.allowance.remove(&spender);
+        } else {
+            self.allowance.insert(spender, amount);
+        }
+    }
+
+    /// The amount `spender` is still allowed to move, zero if it was never
+    /// approved.
+    pub fn allowance_of(&self, spender: &Address) -> U256 {
+        self.allowance.get(spender).copied().unwrap_or_default()
+    }
+
+    /// Consumes `amount` of the allowance granted to `spender`. Spending the
+    /// full allowance revokes it.
+    pub fn spend_allowance(&mut self, spender: &Address, amount: U256) -> Result<(), AccountError> {
+        if self.is_locked() {
+            return Err(BalanceError::Locked(self.program_id).into());
+        }
+
+        let available = self.allowance_of(spender);
+        if amount > available {
+            return Err(AccountError::InsufficientAllowance {
+                spender: *spender,
+                needed: amount,
+                available,
+            });
+        }
+
+        self.approve(*spender, available - amount);
+        Ok(())
     }
 }
 
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum BalanceError {
+    #[error("attempted to send {send} but only {available} is available")]
+    InsufficientBalance { send: U256, available: U256 },
+
+    #[error("receiving {receive} would overflow balance of {balance}")]
+    Overflow { receive: U256, balance: U256 },
+
+    #[error("token for program {} is locked", .0.to_full_string())]
+    Locked(Address),
+}
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
@@ -646,17 +1093,25 @@ pub enum Status {
     Free,
 }
 
+/// Adds the balances, saturating at `U256::MAX` with a logged warning
+/// instead of panicking on overflow. Use [`Token::try_add`] to detect it.
 impl AddAssign for Token {
     fn add_assign(&mut self, rhs: Self) {
-        let new_balance = EthU256::from(self.balance) + EthU256::from(rhs.balance());
-        self.balance = new_balance.into();
+        if let Err(e) = self.try_add(&rhs) {
+            tracing::warn!("saturating token balance: {e}");
+            self.balance = U256::MAX;
+        }
     }
 }
 
+/// Subtracts the balances, saturating at zero with a logged warning instead
+/// of panicking on underflow. Use [`Token::try_sub`] to detect it.
 impl SubAssign for Token {
     fn sub_assign(&mut self, rhs: Self) {
-        let new_balance: EthU256 = EthU256::from(self.balance) - EthU256::from(rhs.balance());
-        self.balance = new_balance.into();
+        if let Err(e) = self.try_sub(&rhs) {
+            tracing::warn!("saturating token balance: {e}");
+            self.balance = U256::zero();
+        }
     }
 }
 
@@ -690,3 +1145,471 @@ pub struct GraphEntry {
     transaction: Transaction,
     dependencies: Vec<[u8; 32]>,
 }
+
+#[cfg(test)]
+mod token_tests {
+    use super::{
+        parse_amount, ArbitraryData, BalanceError, Metadata, ParseAmountError, SizeError, Status,
+        Token, TokenBuilder, TokenDeltaBuilder, DEFAULT_MAX_TOKEN_DATA_LEN, U256,
+    };
+    use crate::{AccountError, Address};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    fn token_with_balance(balance: U256) -> Token {
+        TokenBuilder::default()
+            .program_id(Address::new([1u8; 20]))
+            .owner_id(Address::new([2u8; 20]))
+            .balance(balance)
+            .metadata(Metadata::new())
+            .token_ids(vec![])
+            .allowance(BTreeMap::new())
+            .approvals(BTreeMap::new())
+            .data(ArbitraryData::new())
+            .status(Status::Free)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_update_balance_exact_balance() {
+        let mut token = token_with_balance(U256::from(100));
+        token
+            .update_balance(U256::from(0), U256::from(100))
+            .unwrap();
+        assert_eq!(token.balance(), U256::from(0));
+    }
+
+    #[test]
+    fn test_update_balance_one_over_balance() {
+        let mut token = token_with_balance(U256::from(100));
+        assert_eq!(
+            token.update_balance(U256::from(0), U256::from(101)),
+            Err(BalanceError::InsufficientBalance {
+                send: U256::from(101),
+                available: U256::from(100),
+            })
+        );
+        assert_eq!(token.balance(), U256::from(100));
+    }
+
+    #[test]
+    fn test_update_balance_overflow_on_receive() {
+        let mut token = token_with_balance(U256::MAX);
+        assert_eq!(
+            token.update_balance(U256::from(1), U256::from(0)),
+            Err(BalanceError::Overflow {
+                receive: U256::from(1),
+                balance: U256::MAX,
+            })
+        );
+        assert_eq!(token.balance(), U256::MAX);
+    }
+
+    #[test]
+    fn test_spend_allowance_partial() {
+        let spender = Address::new([3u8; 20]);
+        let mut token = token_with_balance(U256::from(100));
+        assert_eq!(token.allowance_of(&spender), U256::from(0));
+
+        token.approve(spender, U256::from(50));
+        token.spend_allowance(&spender, U256::from(20)).unwrap();
+        assert_eq!(token.allowance_of(&spender), U256::from(30));
+
+        assert_eq!(
+            token.spend_allowance(&spender, U256::from(31)),
+            Err(AccountError::InsufficientAllowance {
+                spender,
+                needed: U256::from(31),
+                available: U256::from(30),
+            })
+        );
+        assert_eq!(token.allowance_of(&spender), U256::from(30));
+    }
+
+    #[test]
+    fn test_spend_allowance_exact() {
+        let spender = Address::new([3u8; 20]);
+        let mut token = token_with_balance(U256::from(100));
+        token.approve(spender, U256::from(50));
+        token.spend_allowance(&spender, U256::from(50)).unwrap();
+        assert_eq!(token.allowance_of(&spender), U256::from(0));
+        assert!(token.allowance().is_empty());
+
+        assert!(token.spend_allowance(&spender, U256::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_token_ids_stay_sorted() {
+        let mut token = token_with_balance(U256::from(0));
+        for id in [5u64, 1, 3] {
+            token.mint_id(U256::from(id)).unwrap();
+        }
+        assert_eq!(
+            token.token_ids(),
+            vec![U256::from(1), U256::from(3), U256::from(5)]
+        );
+        assert_eq!(
+            token.mint_id(U256::from(3)),
+            Err(AccountError::DuplicateTokenId(U256::from(3)))
+        );
+
+        assert!(token.owns_id(&U256::from(3)));
+        token.remove_id(&U256::from(3)).unwrap();
+        assert!(!token.owns_id(&U256::from(3)));
+        assert_eq!(
+            token.remove_id(&U256::from(3)),
+            Err(AccountError::UnknownTokenId(U256::from(3)))
+        );
+        assert_eq!(token.token_ids(), vec![U256::from(1), U256::from(5)]);
+    }
+
+    #[test]
+    fn test_empty_token_matches_builder() {
+        let empty = Token::empty(Address::new([1u8; 20]), Address::new([2u8; 20]));
+        let built = token_with_balance(U256::from(0));
+        assert_eq!(empty, built);
+        assert_eq!(
+            serde_json::to_string(&empty).unwrap(),
+            serde_json::to_string(&built).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_locked_token_rejects_updates() {
+        let spender = Address::new([3u8; 20]);
+        let mut token = token_with_balance(U256::from(100));
+        token.approve(spender, U256::from(10));
+        token.lock().unwrap();
+        assert_eq!(
+            token.lock(),
+            Err(AccountError::Balance(BalanceError::Locked(
+                token.program_id()
+            )))
+        );
+
+        assert_eq!(
+            token.update_balance(U256::from(1), U256::from(0)),
+            Err(BalanceError::Locked(token.program_id()))
+        );
+        assert!(token.spend_allowance(&spender, U256::from(1)).is_err());
+        assert_eq!(token.balance(), U256::from(100));
+        assert_eq!(token.allowance_of(&spender), U256::from(10));
+
+        token.unlock();
+        token.update_balance(U256::from(1), U256::from(0)).unwrap();
+        assert_eq!(token.balance(), U256::from(101));
+    }
+
+    #[test]
+    fn test_canonicalize_sorts_token_ids_and_approvals() {
+        let spender = Address::new([3u8; 20]);
+        let mut token = token_with_balance(U256::from(0));
+        *token.token_ids_mut() = vec![U256::from(3), U256::from(1), U256::from(2)];
+        token
+            .approvals_mut()
+            .insert(spender, vec![U256::from(2), U256::from(1)]);
+        assert!(!token.is_canonical());
+
+        token.canonicalize();
+        assert!(token.is_canonical());
+        assert_eq!(
+            token.token_ids(),
+            vec![U256::from(1), U256::from(2), U256::from(3)]
+        );
+        assert_eq!(
+            token.approvals()[&spender],
+            vec![U256::from(1), U256::from(2)]
+        );
+        assert!(token.owns_id(&U256::from(3)));
+    }
+
+    #[test]
+    fn test_decoding_sorts_token_ids() {
+        let mut token = token_with_balance(U256::from(0));
+        *token.token_ids_mut() = vec![U256::from(3), U256::from(1), U256::from(2)];
+
+        let json = serde_json::to_string(&token).unwrap();
+        let mut decoded: Token = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            decoded.token_ids(),
+            vec![U256::from(1), U256::from(2), U256::from(3)]
+        );
+        assert!(decoded.owns_id(&U256::from(1)));
+        decoded.remove_id(&U256::from(3)).unwrap();
+        assert_eq!(decoded.token_ids(), vec![U256::from(1), U256::from(2)]);
+    }
+
+    #[test]
+    fn test_can_spend_boundaries() {
+        let mut token = token_with_balance(U256::from(10));
+        assert_eq!(token.spendable(), U256::from(10));
+        assert!(token.can_spend(U256::from(0)));
+        assert!(token.can_spend(U256::from(10)));
+        assert!(!token.can_spend(U256::from(11)));
+
+        token.lock().unwrap();
+        assert_eq!(token.spendable(), U256::from(0));
+        assert!(!token.can_spend(U256::from(0)));
+        assert!(!token.can_spend(U256::from(1)));
+
+        token.unlock();
+        assert!(token.can_spend(U256::from(10)));
+    }
+
+    #[test]
+    fn test_apply_delta_updates_metadata_with_balance() {
+        let mut metadata = Metadata::new();
+        metadata.insert("symbol".to_string(), "TKN".to_string());
+        let delta = TokenDeltaBuilder::default()
+            .receive(U256::from(5))
+            .send(U256::from(2))
+            .metadata(Some(metadata.clone()))
+            .build()
+            .unwrap();
+
+        let mut token = token_with_balance(U256::from(10));
+        let data = token.data();
+        token.apply_delta(&delta).unwrap();
+        assert_eq!(token.balance(), U256::from(13));
+        assert_eq!(token.metadata(), metadata);
+        assert_eq!(token.data(), data);
+    }
+
+    #[test]
+    fn test_apply_delta_without_metadata_leaves_it_untouched() {
+        let delta = TokenDeltaBuilder::default()
+            .receive(U256::from(0))
+            .send(U256::from(4))
+            .build()
+            .unwrap();
+
+        let mut token = token_with_balance(U256::from(10));
+        let metadata = token.metadata();
+        token.apply_delta(&delta).unwrap();
+        assert_eq!(token.balance(), U256::from(6));
+        assert_eq!(token.metadata(), metadata);
+
+        let overdraw = TokenDeltaBuilder::default()
+            .receive(U256::from(0))
+            .send(U256::from(7))
+            .metadata(Some(Metadata::new()))
+            .build()
+            .unwrap();
+        assert!(token.apply_delta(&overdraw).is_err());
+        assert_eq!(token.balance(), U256::from(6));
+        assert_eq!(token.metadata(), metadata);
+    }
+
+    #[test]
+    fn test_sub_assign_saturates_at_zero() {
+        let mut token = token_with_balance(U256::from(5));
+        token -= token_with_balance(U256::from(5));
+        assert_eq!(token.balance(), U256::from(0));
+
+        let mut token = token_with_balance(U256::from(5));
+        token -= token_with_balance(U256::from(6));
+        assert_eq!(token.balance(), U256::from(0));
+
+        let mut token = token_with_balance(U256::MAX - U256::from(1));
+        token += token_with_balance(U256::from(2));
+        assert_eq!(token.balance(), U256::MAX);
+    }
+
+    #[test]
+    fn test_try_sub_rejects_underflow() {
+        let mut token = token_with_balance(U256::from(5));
+        token.try_sub(&token_with_balance(U256::from(5))).unwrap();
+        assert_eq!(token.balance(), U256::from(0));
+
+        let mut token = token_with_balance(U256::from(5));
+        assert_eq!(
+            token.try_sub(&token_with_balance(U256::from(6))),
+            Err(BalanceError::InsufficientBalance {
+                send: U256::from(6),
+                available: U256::from(5),
+            })
+        );
+        assert_eq!(token.balance(), U256::from(5));
+
+        let mut token = token_with_balance(U256::MAX);
+        assert!(matches!(
+            token.try_add(&token_with_balance(U256::from(1))),
+            Err(BalanceError::Overflow { .. })
+        ));
+        assert_eq!(token.balance(), U256::MAX);
+    }
+
+    #[test]
+    fn test_u256_hex_quantities() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Quantity(#[serde(with = "super::u256_hex")] U256);
+
+        let max_limb = U256([0, 0, 0, u64::MAX]);
+        let cases = [
+            (U256::zero(), "\"0x0\"".to_string()),
+            (U256::from(42), "\"0x2a\"".to_string()),
+            (
+                max_limb,
+                format!("\"0x{}{}\"", "f".repeat(16), "0".repeat(48)),
+            ),
+        ];
+        for (value, json) in cases {
+            assert_eq!(serde_json::to_string(&Quantity(value)).unwrap(), json);
+            assert_eq!(
+                serde_json::from_str::<Quantity>(&json).unwrap(),
+                Quantity(value)
+            );
+            assert_eq!(
+                serde_json::from_str::<Quantity>(&serde_json::to_string(&value).unwrap()).unwrap(),
+                Quantity(value)
+            );
+        }
+        assert!(serde_json::from_str::<Quantity>("\"0x\"").is_err());
+        assert!(serde_json::from_str::<Quantity>("\"0xzz\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("1000"), Ok(U256::from(1000)));
+        assert_eq!(parse_amount("0"), Ok(U256::from(0)));
+        assert_eq!(parse_amount("0x3e8"), Ok(U256::from(1000)));
+        assert_eq!(parse_amount("0X3E8"), Ok(U256::from(1000)));
+        assert_eq!(
+            parse_amount(&format!("0x{}", "f".repeat(64))),
+            Ok(U256::MAX)
+        );
+        assert_eq!(parse_amount(&U256::MAX.to_string()), Ok(U256::MAX));
+        assert_eq!(
+            parse_amount(&format!("0x0{}", "f".repeat(64))),
+            Ok(U256::MAX)
+        );
+
+        let too_large = format!("0x1{}", "0".repeat(64));
+        assert_eq!(
+            parse_amount(&too_large),
+            Err(ParseAmountError::Overflow(too_large))
+        );
+        let too_large = format!("{}0", U256::MAX);
+        assert_eq!(
+            parse_amount(&too_large),
+            Err(ParseAmountError::Overflow(too_large))
+        );
+
+        for invalid in ["", "0x", "12a", "0xzz", "-1", " 1"] {
+            assert_eq!(
+                parse_amount(invalid),
+                Err(ParseAmountError::Invalid(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_rejects_other_program() {
+        let mut token = token_with_balance(U256::from(5));
+        let mut other = token_with_balance(U256::from(1));
+        other.program_id = Address::new([9u8; 20]);
+
+        assert_eq!(
+            token.merge(other),
+            Err(AccountError::ProgramMismatch {
+                expected: Address::new([1u8; 20]),
+                found: Address::new([9u8; 20]),
+            })
+        );
+        assert_eq!(token, token_with_balance(U256::from(5)));
+    }
+
+    #[test]
+    fn test_merge_unions_token_ids() {
+        let spender = Address::new([3u8; 20]);
+        let mut token = token_with_balance(U256::from(5));
+        token.token_ids = [1u64, 3].map(U256::from).to_vec();
+        token.approve(spender, U256::from(2));
+        let mut other = token_with_balance(U256::from(7));
+        other.token_ids = [3u64, 2].map(U256::from).to_vec();
+        other.approve(spender, U256::from(4));
+        other.approvals.insert(spender, vec![U256::from(2)]);
+
+        token.merge(other).unwrap();
+        assert_eq!(token.balance(), U256::from(12));
+        assert_eq!(token.token_ids(), [1u64, 2, 3].map(U256::from).to_vec());
+        assert_eq!(token.allowance_of(&spender), U256::from(6));
+        assert_eq!(token.approvals().get(&spender), Some(&vec![U256::from(2)]));
+
+        let mut full = token_with_balance(U256::MAX);
+        assert!(matches!(
+            full.merge(token_with_balance(U256::from(1))),
+            Err(AccountError::Balance(BalanceError::Overflow { .. }))
+        ));
+        assert_eq!(full.balance(), U256::MAX);
+    }
+
+    #[test]
+    fn test_metadata_and_data_size_limit() {
+        let entries = |len: usize| BTreeMap::from([("k".to_string(), "v".repeat(len - 1))]);
+        let at_limit = entries(DEFAULT_MAX_TOKEN_DATA_LEN);
+        let over_limit = entries(DEFAULT_MAX_TOKEN_DATA_LEN + 1);
+        let too_large = SizeError {
+            len: DEFAULT_MAX_TOKEN_DATA_LEN + 1,
+            max: DEFAULT_MAX_TOKEN_DATA_LEN,
+        };
+
+        let metadata = Metadata::try_new(at_limit.clone()).unwrap();
+        assert_eq!(metadata.byte_len(), DEFAULT_MAX_TOKEN_DATA_LEN);
+        assert_eq!(
+            Metadata::try_new(over_limit.clone()),
+            Err(too_large.clone())
+        );
+
+        let data = ArbitraryData::try_new(at_limit).unwrap();
+        assert_eq!(data.byte_len(), DEFAULT_MAX_TOKEN_DATA_LEN);
+        assert_eq!(ArbitraryData::try_new(over_limit), Err(too_large));
+
+        assert_eq!(
+            ArbitraryData::try_new_with_limit(entries(9), 8),
+            Err(SizeError { len: 9, max: 8 })
+        );
+    }
+
+    #[test]
+    fn test_split_moves_fungible_balance() {
+        let mut token = token_with_balance(U256::from(10));
+        let split = token.split(U256::from(4)).unwrap();
+        assert_eq!(token.balance(), U256::from(6));
+        assert_eq!(split.balance(), U256::from(4));
+        assert_eq!(split.program_id(), token.program_id());
+        assert_eq!(split.owner_id(), token.owner_id());
+
+        assert_eq!(
+            token.split(U256::from(7)),
+            Err(AccountError::Balance(BalanceError::InsufficientBalance {
+                send: U256::from(7),
+                available: U256::from(6),
+            }))
+        );
+        assert_eq!(token.balance(), U256::from(6));
+    }
+
+    #[test]
+    fn test_split_rejects_token_ids() {
+        let mut token = token_with_balance(U256::from(10));
+        token.token_ids = vec![U256::from(1)];
+        assert_eq!(
+            token.split(U256::from(4)),
+            Err(AccountError::NonFungibleSplit(Address::new([1u8; 20])))
+        );
+        assert_eq!(token.balance(), U256::from(10));
+    }
+
+    #[test]
+    fn test_token_shape() {
+        let mut token = token_with_balance(U256::from(10));
+        assert!(token.is_fungible());
+        assert!(!token.is_nft());
+
+        token.token_ids = vec![U256::from(1), U256::from(2)];
+        assert!(token.is_nft());
+        assert!(!token.is_fungible());
+    }
+}
@@ -1,10 +1,13 @@
-use crate::{Address, ArbitraryData, Metadata, Status, Token, TokenBuilder};
+use crate::{
+    Account, AccountError, Address, ArbitraryData, Metadata, Status, Token, TokenBuilder,
+    TokenDelta,
+};
 use crate::{RecoverableSignature, RecoverableSignatureBuilder};
 use derive_builder::Builder;
 use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 use sha3::{Digest, Keccak256};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Display, LowerHex};
 use thiserror::Error;
 
@@ -19,6 +22,43 @@ impl Display for ToTokenError {
     }
 }
 
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum TxError {
+    #[error("unable to recover transaction signer: {0}")]
+    InvalidSignature(String),
+
+    #[error("malformed transaction signature: {0}")]
+    Signature(String),
+
+    #[error(
+        "transaction sender {} does not match recovered signer {}",
+        from.to_full_string(),
+        recovered.to_full_string()
+    )]
+    SenderMismatch { from: Address, recovered: Address },
+
+    #[error("transaction needs {required} authorized signers but only {found} signed")]
+    InsufficientSigners { required: usize, found: usize },
+
+    #[error(transparent)]
+    Account(#[from] AccountError),
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum PayloadError {
+    #[error("payload sender must not be the zero address")]
+    ZeroSender,
+
+    #[error("send payload recipient must not be the zero address")]
+    ZeroRecipient,
+
+    #[error("payload value {value} does not match transaction type amount {amount}")]
+    ValueMismatch {
+        value: crate::U256,
+        amount: crate::U256,
+    },
+}
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
@@ -32,6 +72,26 @@ pub enum TransactionType {
 }
 
 impl TransactionType {
+    pub fn bridge_in_from_u64(n: u64) -> Self {
+        TransactionType::BridgeIn(crate::U256::from(n))
+    }
+
+    pub fn send_from_u64(n: u64) -> Self {
+        TransactionType::Send(crate::U256::from(n))
+    }
+
+    pub fn call_from_u64(n: u64) -> Self {
+        TransactionType::Call(crate::U256::from(n))
+    }
+
+    pub fn bridge_out_from_u64(n: u64) -> Self {
+        TransactionType::BridgeOut(crate::U256::from(n))
+    }
+
+    pub fn register_program_from_u64(n: u64) -> Self {
+        TransactionType::RegisterProgram(crate::U256::from(n))
+    }
+
     pub fn is_send(&self) -> bool {
         matches!(self, TransactionType::Send(_))
     }
@@ -52,6 +112,37 @@ impl TransactionType {
         matches!(self, TransactionType::RegisterProgram(_))
     }
 
+    /// True for both bridge-in and bridge-out transactions.
+    pub fn is_bridge(&self) -> bool {
+        self.is_bridge_in() || self.is_bridge_out()
+    }
+
+    pub fn kind(&self) -> TxKind {
+        match self {
+            TransactionType::BridgeIn(_) => TxKind::BridgeIn,
+            TransactionType::Send(_) => TxKind::Send,
+            TransactionType::Call(_) => TxKind::Call,
+            TransactionType::BridgeOut(_) => TxKind::BridgeOut,
+            TransactionType::RegisterProgram(_) => TxKind::RegisterProgram,
+        }
+    }
+
+    /// The amount wrapped by the transaction type, whichever the variant.
+    pub fn amount(&self) -> crate::U256 {
+        *self.json_entry().1
+    }
+
+    /// The key and amount of the single entry in [`TransactionType::to_json`].
+    fn json_entry(&self) -> (&'static str, &crate::U256) {
+        match self {
+            Self::BridgeIn(n) => ("bridgeIn", n),
+            Self::Send(n) => ("send", n),
+            Self::Call(n) => ("call", n),
+            Self::RegisterProgram(n) => ("registerProgram", n),
+            Self::BridgeOut(n) => ("bridgeOut", n),
+        }
+    }
+
     pub fn to_json(&self) -> serde_json::Value {
         match self {
             Self::BridgeIn(n) => serde_json::json!({"bridgeIn": format!("0x{:064x}", n)}),
@@ -65,6 +156,20 @@ impl TransactionType {
     }
 }
 
+/// The variant of a [`TransactionType`] without its inner value, for code
+/// that only needs to route on the kind of transaction.
+#[derive(
+    Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum TxKind {
+    BridgeIn,
+    Send,
+    Call,
+    BridgeOut,
+    RegisterProgram,
+}
+
 impl ToString for TransactionType {
     fn to_string(&self) -> String {
         match self {
@@ -92,8 +197,21 @@ pub struct Payload {
     op: String,
     #[serde(rename(serialize = "transactionInputs", deserialize = "transactionInputs"))]
     inputs: String,
+    #[serde(with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
     value: crate::U256,
+    #[serde(with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
     nonce: crate::U256,
+    /// The fee paid by the sender for the transaction to be executed.
+    #[builder(default)]
+    #[serde(default, with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
+    fee: crate::U256,
+    /// Unix timestamp in seconds after which the transaction is expired.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    valid_until: Option<u64>,
 }
 
 impl Payload {
@@ -129,37 +247,104 @@ impl Payload {
         self.nonce
     }
 
+    pub fn fee(&self) -> crate::U256 {
+        self.fee
+    }
+
+    pub fn valid_until(&self) -> Option<u64> {
+        self.valid_until
+    }
+
     pub fn hash_string(&self) -> String {
-        let mut hasher = Keccak256::new();
-        hasher.update(&self.as_bytes());
-        let res = hasher.finalize();
-        format!("0x{:x}", res)
+        Hashable::hash_string(self)
     }
 
     pub fn hash(&self) -> Vec<u8> {
-        let mut hasher = Keccak256::new();
-        hasher.update(&self.as_bytes());
-        let res = hasher.finalize();
-        tracing::info!("transaction hash: 0x{:x}", res);
-        res.to_vec()
+        Hashable::hash(self)
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
-        let transaction_json = serde_json::json!({
-            "transactionType": self.transaction_type().to_json(),
-            "from": Address::from(self.from()).to_full_string(),
-            "to": Address::from(self.to()).to_full_string(),
-            "programId": Address::from(self.program_id()).to_full_string(),
-            "op": self.op.clone(),
-            "transactionInputs": self.inputs().clone(),
-            "value": format!("0x{:064x}", self.value()),
-            "nonce": format!("0x{:064x}", self.nonce())
-        })
-        .to_string();
+        Hashable::as_bytes(self)
+    }
+
+    /// Checks the payload is well formed before it is signed: the sender is
+    /// never the zero address, a `Send` must have a non-zero recipient, and
+    /// `value` must equal the amount embedded in the transaction type.
+    pub fn validate(&self) -> Result<(), PayloadError> {
+        if Address::from(self.from).is_zero() {
+            return Err(PayloadError::ZeroSender);
+        }
+        if self.transaction_type.is_send() && Address::from(self.to).is_zero() {
+            return Err(PayloadError::ZeroRecipient);
+        }
+        let amount = self.transaction_type.amount();
+        if amount != self.value {
+            return Err(PayloadError::ValueMismatch {
+                value: self.value,
+                amount,
+            });
+        }
+        Ok(())
+    }
+
+    /// Appends the bytes returned by [`Payload::as_bytes`] to `out` without
+    /// any intermediate allocations.
+    pub fn write_bytes(&self, out: &mut Vec<u8>) {
+        write_payload_bytes(
+            out,
+            &self.transaction_type,
+            &Address::from(self.from),
+            &Address::from(self.to),
+            &Address::from(self.program_id),
+            &self.op,
+            &self.inputs,
+            &self.value,
+            &self.nonce,
+            &self.fee,
+            self.valid_until,
+        )
+        .expect("writing to a Vec<u8> cannot fail")
+    }
+}
+
+/// Writes the JSON object signed over for a payload, the equivalent of
+/// serializing `{"transactionType", "from", "to", "programId", "op",
+/// "transactionInputs", "value", "nonce", "fee", "validUntil"}` with
+/// `serde_json`. `validUntil` is omitted when it is not set, so payloads
+/// without an expiry keep the bytes they were signed over before it existed.
+#[allow(clippy::too_many_arguments)]
+fn write_payload_bytes(
+    out: &mut Vec<u8>,
+    transaction_type: &TransactionType,
+    from: &Address,
+    to: &Address,
+    program_id: &Address,
+    op: &str,
+    inputs: &str,
+    value: &crate::U256,
+    nonce: &crate::U256,
+    fee: &crate::U256,
+    valid_until: Option<u64>,
+) -> std::io::Result<()> {
+    use std::io::Write;
 
-        tracing::info!("converted payload to json: {}", &transaction_json);
-        transaction_json.as_bytes().to_vec()
+    let (type_key, amount) = transaction_type.json_entry();
+    write!(
+        out,
+        r#"{{"transactionType":{{"{type_key}":"0x{amount:064x}"}},"from":"0x{from:x}","to":"0x{to:x}","programId":"0x{program_id:x}","op":"#
+    )?;
+    serde_json::to_writer(&mut *out, op)?;
+    out.extend_from_slice(br#","transactionInputs":"#);
+    serde_json::to_writer(&mut *out, inputs)?;
+    write!(
+        out,
+        r#","value":"0x{value:064x}","nonce":"0x{nonce:064x}","fee":"0x{fee:064x}""#
+    )?;
+    if let Some(valid_until) = valid_until {
+        write!(out, r#","validUntil":{valid_until}"#)?;
     }
+    out.push(b'}');
+    Ok(())
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -286,6 +471,12 @@ pub struct Transaction {
     inputs: String,
     value: crate::U256,
     nonce: crate::U256,
+    #[builder(default)]
+    #[serde(default)]
+    fee: crate::U256,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    valid_until: Option<u64>,
     v: i32,
     #[serde(
         serialize_with = "serialize_as_hex",
@@ -297,6 +488,11 @@ pub struct Transaction {
         deserialize_with = "deserialize_sig_bytes_or_string"
     )]
     s: [u8; 32],
+    /// Signatures over the transaction hash from co-signers of a multisig
+    /// account, on top of the one in `v`, `r` and `s`.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    additional_sigs: Vec<RecoverableSignature>,
 }
 
 impl Default for Transaction {
@@ -310,9 +506,12 @@ impl Default for Transaction {
             inputs: String::from(""),
             value: crate::U256::from(0),
             nonce: crate::U256::from(0),
+            fee: crate::U256::from(0),
+            valid_until: None,
             v: 0,
             r: [0u8; 32],
             s: [0u8; 32],
+            additional_sigs: Vec::new(),
         }
     }
 }
@@ -330,6 +529,8 @@ pub enum TransactionFields {
     Inputs,
     Value,
     Nonce,
+    Fee,
+    ValidUntil,
     V,
     R,
     S,
@@ -368,17 +569,48 @@ impl Transaction {
         self.nonce
     }
 
-    pub fn sig(&self) -> Result<RecoverableSignature, Box<dyn std::error::Error>> {
+    pub fn fee(&self) -> crate::U256 {
+        self.fee
+    }
+
+    pub fn valid_until(&self) -> Option<u64> {
+        self.valid_until
+    }
+
+    /// Returns true once `now` (unix seconds) is past `valid_until`. A
+    /// transaction is still valid at exactly `valid_until`, and one without
+    /// an expiry never expires.
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.valid_until, Some(valid_until) if now > valid_until)
+    }
+
+    /// Builds the signature from `v`, `r` and `s`, reporting values that do
+    /// not form a secp256k1 recoverable signature, such as a `v` that is not
+    /// a recovery id, as [`TxError::Signature`].
+    pub fn sig(&self) -> Result<RecoverableSignature, TxError> {
         let sig = RecoverableSignatureBuilder::default()
             .r(self.r)
             .s(self.s)
             .v(self.v)
             .build()
-            .map_err(Box::new)?;
+            .map_err(|e| TxError::Signature(e.to_string()))?;
+        secp256k1::ecdsa::RecoverableSignature::try_from(&sig)
+            .map_err(|e| TxError::Signature(format!("{e} (v = {})", self.v)))?;
 
         Ok(sig)
     }
 
+    /// The raw recovery id encoded in `v`, normalized from the legacy
+    /// (`27`/`28`) or EIP-155 (`chain_id * 2 + 35 + recovery_id`) forms.
+    pub fn recovery_id(&self) -> i32 {
+        crate::normalize_recovery_id(self.v)
+    }
+
+    /// The EIP-155 chain id encoded in `v`, if the signature carries one.
+    pub fn chain_id(&self) -> Option<u64> {
+        crate::chain_id_from_v(self.v)
+    }
+
     pub fn recover(&self) -> Result<Address, Box<dyn std::error::Error>> {
         let r = self.r;
         let s = self.s;
@@ -398,54 +630,172 @@ impl Transaction {
         Ok(addr)
     }
 
+    /// Recovers the address of the signer, reporting a malformed signature
+    /// as [`TxError::Signature`] and a failed recovery as
+    /// [`TxError::InvalidSignature`].
+    pub fn recover_address(&self) -> Result<Address, TxError> {
+        self.sig()?;
+        self.recover()
+            .map_err(|e| TxError::InvalidSignature(e.to_string()))
+    }
+
     pub fn message(&self) -> String {
         format!("{:02x}", self)
     }
 
     pub fn hash_string(&self) -> String {
-        let mut hasher = Keccak256::new();
-        hasher.update(&self.as_bytes());
-        let res = hasher.finalize();
-        format!("0x{:x}", res)
+        Hashable::hash_string(self)
     }
 
     pub fn hash(&self) -> Vec<u8> {
-        let mut hasher = Keccak256::new();
-        hasher.update(&self.as_bytes());
-        let res = hasher.finalize();
-        tracing::info!("transaction hash: 0x{:x}", res);
-        res.to_vec()
+        Hashable::hash(self)
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
-        let transaction_json = serde_json::json!({
-            "transactionType": self.transaction_type().to_json(),
-            "from": self.from().to_full_string(),
-            "to": self.to().to_full_string(),
-            "programId": self.program_id().to_full_string(),
-            "op": self.op.clone(),
-            "transactionInputs": self.inputs().clone(),
-            "value": format!("0x{:064x}", self.value()),
-            "nonce": format!("0x{:064x}", self.nonce())
-        })
-        .to_string();
+        Hashable::as_bytes(self)
+    }
+
+    /// Appends the bytes returned by [`Transaction::as_bytes`] to `out`
+    /// without any intermediate allocations.
+    pub fn write_bytes(&self, out: &mut Vec<u8>) {
+        write_payload_bytes(
+            out,
+            &self.transaction_type,
+            &self.from(),
+            &self.to(),
+            &self.program_id(),
+            &self.op,
+            &self.inputs,
+            &self.value,
+            &self.nonce,
+            &self.fee,
+            self.valid_until,
+        )
+        .expect("writing to a Vec<u8> cannot fail")
+    }
+
+    /// Signs the hash of `payload` with `key` and builds the resulting
+    /// transaction, so that it recovers to the address of `key`.
+    pub fn sign(payload: Payload, key: &secp256k1::SecretKey) -> Result<Transaction, TxError> {
+        let message = secp256k1::Message::from_digest_slice(&payload.hash())
+            .map_err(|e| TxError::InvalidSignature(e.to_string()))?;
+        let sig: RecoverableSignature = secp256k1::SECP256K1
+            .sign_ecdsa_recoverable(&message, key)
+            .into();
+        Ok((payload, sig).into())
+    }
+
+    /// The co-signer signatures carried alongside the primary signature.
+    pub fn additional_sigs(&self) -> &[RecoverableSignature] {
+        &self.additional_sigs
+    }
+
+    /// Signs the transaction hash with `key` and adds the signature to the
+    /// co-signer signatures.
+    pub fn cosign(&mut self, key: &secp256k1::SecretKey) -> Result<(), TxError> {
+        let message = secp256k1::Message::from_digest_slice(&self.hash())
+            .map_err(|e| TxError::InvalidSignature(e.to_string()))?;
+        self.additional_sigs.push(
+            secp256k1::SECP256K1
+                .sign_ecdsa_recoverable(&message, key)
+                .into(),
+        );
+        Ok(())
+    }
+
+    /// Checks that at least `required` distinct addresses in `signers`
+    /// signed the transaction, counting the primary signature and every
+    /// co-signer signature. Signatures from addresses outside `signers`
+    /// are ignored, but any signature that is malformed or cannot be
+    /// recovered fails the check.
+    pub fn verify_multisig(&self, required: usize, signers: &[Address]) -> Result<(), TxError> {
+        let hash = self.hash();
+        let mut authorized = BTreeSet::new();
+        for sig in std::iter::once(self.sig()?).chain(self.additional_sigs.iter().cloned()) {
+            secp256k1::ecdsa::RecoverableSignature::try_from(&sig)
+                .map_err(|e| TxError::Signature(format!("{e} (v = {})", sig.get_v())))?;
+            let signer = sig
+                .recover(&hash)
+                .map_err(|e| TxError::InvalidSignature(e.to_string()))?;
+            if signers.contains(&signer) {
+                authorized.insert(signer);
+            }
+        }
+        if authorized.len() < required {
+            return Err(TxError::InsufficientSigners {
+                required,
+                found: authorized.len(),
+            });
+        }
+
+        Ok(())
+    }
 
-        tracing::info!("converted payload to json: {}", &transaction_json);
-        transaction_json.as_bytes().to_vec()
+    pub fn verify_signature(&self) -> Result<(), TxError> {
+        self.verify_signature_with(SigningScheme::Payload)
     }
 
-    pub fn verify_signature(&self) -> Result<(), secp256k1::Error> {
+    /// Checks the signature recovers to the sender when it was made over the
+    /// digest `scheme` selects.
+    pub fn verify_signature_with(&self, scheme: SigningScheme) -> Result<(), TxError> {
+        let digest = match scheme {
+            SigningScheme::Payload => self.hash(),
+            SigningScheme::CanonicalJson => self.canonical_hash(),
+        };
         let addr = self
-            .sig()
-            .map_err(|_| secp256k1::Error::InvalidMessage)?
-            .recover(&self.hash())?;
+            .sig()?
+            .recover(&digest)
+            .map_err(|e| TxError::InvalidSignature(e.to_string()))?;
         if self.from() != addr {
             tracing::error!(
                 "self.from() {} != addr {}",
                 self.from().to_full_string(),
                 addr.to_full_string()
             );
-            return Err(secp256k1::Error::InvalidSignature);
+            return Err(TxError::SenderMismatch {
+                from: self.from(),
+                recovered: addr,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Validates the transaction against the sender's `account`.
+    ///
+    /// Verifies the signature, checks the recovered signer is `from`, that
+    /// the nonce is the account's next nonce and, for sends and for calls
+    /// carrying value or a fee, that the account holds
+    /// [`Transaction::required_balance`] of `program_id`.
+    /// Returns the validated sender.
+    pub fn validate(&self, account: &Account) -> Result<Address, TxError> {
+        self.verify_sender()?;
+
+        account.validate_nonce(self.nonce())?;
+        let transaction_type = self.transaction_type();
+        let required = self.required_balance();
+        if transaction_type.is_send() || (transaction_type.is_call() && !required.is_zero()) {
+            account.validate_balance(&self.program_id(), required)?;
+        }
+
+        Ok(self.from())
+    }
+
+    /// The balance of `program_id` the sender needs to cover both the value
+    /// sent and the fee. The sum saturates at `U256::MAX`.
+    pub fn required_balance(&self) -> crate::U256 {
+        self.value.saturating_add(self.fee)
+    }
+
+    /// Recovers the signer of the transaction and checks that it is the
+    /// address the transaction claims to be sent `from`.
+    pub fn verify_sender(&self) -> Result<(), TxError> {
+        let recovered = self.recover_address()?;
+        if recovered != self.from() {
+            return Err(TxError::SenderMismatch {
+                from: self.from(),
+                recovered,
+            });
         }
 
         Ok(())
@@ -454,6 +804,179 @@ impl Transaction {
     pub fn get_accounts_involved(&self) -> Vec<Address> {
         vec![self.from(), self.to()]
     }
+
+    /// Every address the transaction touches, `from`, `to` and `program_id`
+    /// in that order, with repeats after their first occurrence removed.
+    pub fn touched_addresses(&self) -> Vec<Address> {
+        let mut addresses = Vec::with_capacity(3);
+        for address in [self.from(), self.to(), self.program_id()] {
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+        addresses
+    }
+
+    /// A key that orders transactions by fee, highest first, then by nonce
+    /// and finally by hash, so mempool order does not depend on the derived
+    /// `Ord` over every field including the signature.
+    pub fn mempool_key(&self) -> (std::cmp::Reverse<crate::U256>, crate::U256, Vec<u8>) {
+        (std::cmp::Reverse(self.fee), self.nonce, self.hash())
+    }
+
+    /// The settlement record for a bridge-out transaction, or `None` for any
+    /// other transaction type. The transaction should already be validated.
+    pub fn bridge_out_record(&self) -> Option<BridgeOutRecord> {
+        if !self.transaction_type.is_bridge_out() {
+            return None;
+        }
+
+        Some(BridgeOutRecord {
+            account: self.from(),
+            program: self.program_id(),
+            amount: self.transaction_type.amount(),
+            l1_recipient: self.to(),
+        })
+    }
+
+    /// Serializes the transaction to JSON, with addresses and signature
+    /// components rendered as `0x` prefixed hex strings.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("transaction fields always serialize to json")
+    }
+
+    /// Parses a transaction from the JSON produced by [`Transaction::to_json`].
+    pub fn from_json(s: &str) -> Result<Transaction, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// The payload fields as compact JSON with keys in sorted order, for
+    /// clients that sign a canonical JSON document rather than
+    /// [`Transaction::as_bytes`]. Addresses and amounts are `0x` prefixed hex,
+    /// amounts padded to 64 digits, and `validUntil` is omitted when unset.
+    pub fn canonical_json(&self) -> String {
+        let (type_key, amount) = self.transaction_type.json_entry();
+        let hex_address = |address: [u8; 20]| format!("0x{}", hex::encode(address));
+        let hex_amount = |amount: &crate::U256| format!("0x{amount:064x}");
+        let mut fields: BTreeMap<&str, serde_json::Value> = BTreeMap::from([
+            ("fee", hex_amount(&self.fee).into()),
+            ("from", hex_address(self.from).into()),
+            ("nonce", hex_amount(&self.nonce).into()),
+            ("op", self.op.clone().into()),
+            ("programId", hex_address(self.program_id).into()),
+            ("to", hex_address(self.to).into()),
+            ("transactionInputs", self.inputs.clone().into()),
+            (
+                "transactionType",
+                serde_json::json!({ type_key: hex_amount(amount) }),
+            ),
+            ("value", hex_amount(&self.value).into()),
+        ]);
+        if let Some(valid_until) = self.valid_until {
+            fields.insert("validUntil", valid_until.into());
+        }
+        serde_json::to_string(&fields).expect("json values always serialize")
+    }
+
+    /// The Keccak256 digest of [`Transaction::canonical_json`], which is what
+    /// a [`SigningScheme::CanonicalJson`] signature is made over.
+    pub fn canonical_hash(&self) -> Vec<u8> {
+        Keccak256::digest(self.canonical_json().as_bytes()).to_vec()
+    }
+
+    /// Previews the effect of this transaction on the sender's `account`
+    /// without changing it. The transaction is checked with
+    /// [`Transaction::validate`] first, then the fee is debited and
+    /// [`Account::apply_transaction`] run on a copy. Returns the sender's
+    /// delta for every program whose balance changed, fee included, followed
+    /// by the delta credited to the recipient.
+    pub fn simulate(&self, account: &Account) -> Result<Vec<TokenDelta>, TxError> {
+        self.validate(account)?;
+        let mut preview = account.clone();
+        if !self.fee.is_zero() {
+            preview.deduct_fee(&self.program_id(), self.fee)?;
+        }
+        let received = preview.apply_transaction(self)?;
+        let mut deltas: Vec<TokenDelta> = account.diff(&preview).into_values().collect();
+        deltas.push(received);
+        Ok(deltas)
+    }
+}
+
+/// The digest a transaction signature is made over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SigningScheme {
+    /// [`Transaction::hash`], as signed by the LASR wallet and CLI.
+    #[default]
+    Payload,
+    /// [`Transaction::canonical_hash`], as signed by JSON based clients.
+    CanonicalJson,
+}
+
+/// Domain tag prepended to the bytes of a payload or transaction before they
+/// are hashed. The version suffix is bumped whenever the signed bytes change.
+pub const TX_HASH_DOMAIN: &[u8] = b"LASR_TX_V1";
+
+/// Types whose signed hash is the Keccak256 digest of their canonical bytes,
+/// prefixed with a domain tag.
+///
+/// `Payload` and `Transaction` also expose these as inherent methods, so
+/// callers don't need to import the trait.
+pub trait Hashable {
+    /// Separates this type's hashes from those of other hashed types.
+    const DOMAIN: &'static [u8];
+
+    /// The canonical bytes that are hashed and signed over.
+    fn as_bytes(&self) -> Vec<u8>;
+
+    /// The exact bytes fed to the hasher: [`Hashable::DOMAIN`] followed by
+    /// [`Hashable::as_bytes`].
+    fn preimage(&self) -> Vec<u8> {
+        let mut bytes = Self::DOMAIN.to_vec();
+        bytes.extend(self.as_bytes());
+        bytes
+    }
+
+    fn hash(&self) -> Vec<u8> {
+        let res = Keccak256::digest(self.preimage());
+        tracing::info!("transaction hash: 0x{:x}", res);
+        res.to_vec()
+    }
+
+    fn hash_string(&self) -> String {
+        format!("0x{:x}", Keccak256::digest(self.preimage()))
+    }
+}
+
+fn log_payload_bytes(bytes: &[u8]) {
+    tracing::info!(
+        "converted payload to json: {}",
+        String::from_utf8_lossy(bytes)
+    );
+}
+
+impl Hashable for Payload {
+    const DOMAIN: &'static [u8] = TX_HASH_DOMAIN;
+
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_bytes(&mut bytes);
+        log_payload_bytes(&bytes);
+        bytes
+    }
+}
+
+// A transaction is verified against the signature over its payload, so both
+// must hash identically and share a domain.
+impl Hashable for Transaction {
+    const DOMAIN: &'static [u8] = TX_HASH_DOMAIN;
+
+    fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_bytes(&mut bytes);
+        log_payload_bytes(&bytes);
+        bytes
+    }
 }
 
 impl LowerHex for Transaction {
@@ -465,6 +988,125 @@ impl LowerHex for Transaction {
     }
 }
 
+/// The JSON view of a [`Transaction`] returned to RPC clients, with its
+/// hash and with amounts written as JSON-RPC quantities.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransaction {
+    hash: String,
+    transaction_type: TransactionType,
+    from: Address,
+    to: Address,
+    program_id: Address,
+    op: String,
+    #[serde(rename = "transactionInputs")]
+    inputs: String,
+    #[serde(with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
+    value: crate::U256,
+    #[serde(with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
+    nonce: crate::U256,
+    #[serde(with = "crate::u256_hex")]
+    #[schemars(with = "crate::U256")]
+    fee: crate::U256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    valid_until: Option<u64>,
+}
+
+impl From<&Transaction> for RpcTransaction {
+    fn from(value: &Transaction) -> Self {
+        RpcTransaction {
+            hash: value.hash_string(),
+            transaction_type: value.transaction_type(),
+            from: value.from(),
+            to: value.to(),
+            program_id: value.program_id(),
+            op: value.op(),
+            inputs: value.inputs(),
+            value: value.value(),
+            nonce: value.nonce(),
+            fee: value.fee(),
+            valid_until: value.valid_until(),
+        }
+    }
+}
+
+/// A compact view of a [`Transaction`] for logs and indexes, leaving out
+/// the payload inputs and the signature.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSummary {
+    hash: String,
+    from: Address,
+    to: Address,
+    kind: TxKind,
+    value: crate::U256,
+}
+
+impl TransactionSummary {
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn from(&self) -> Address {
+        self.from
+    }
+
+    pub fn to(&self) -> Address {
+        self.to
+    }
+
+    pub fn kind(&self) -> TxKind {
+        self.kind
+    }
+
+    pub fn value(&self) -> crate::U256 {
+        self.value
+    }
+}
+
+impl From<&Transaction> for TransactionSummary {
+    fn from(value: &Transaction) -> Self {
+        TransactionSummary {
+            hash: value.hash_string(),
+            from: value.from(),
+            to: value.to(),
+            kind: value.transaction_type().kind(),
+            value: value.value(),
+        }
+    }
+}
+
+/// What a bridge-out transaction moves back to L1: `amount` of `program`
+/// leaves `account` and is released to `l1_recipient` on settlement.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeOutRecord {
+    account: Address,
+    program: Address,
+    amount: crate::U256,
+    l1_recipient: Address,
+}
+
+impl BridgeOutRecord {
+    pub fn account(&self) -> Address {
+        self.account
+    }
+
+    pub fn program(&self) -> Address {
+        self.program
+    }
+
+    pub fn amount(&self) -> crate::U256 {
+        self.amount
+    }
+
+    pub fn l1_recipient(&self) -> Address {
+        self.l1_recipient
+    }
+}
+
 impl From<(Payload, RecoverableSignature)> for Transaction {
     fn from(value: (Payload, RecoverableSignature)) -> Self {
         Transaction {
@@ -476,9 +1118,12 @@ impl From<(Payload, RecoverableSignature)> for Transaction {
             inputs: value.0.inputs(),
             value: value.0.value(),
             nonce: value.0.nonce(),
+            fee: value.0.fee(),
+            valid_until: value.0.valid_until(),
             v: value.1.get_v(),
             r: value.1.get_r(),
             s: value.1.get_s(),
+            additional_sigs: Vec::new(),
         }
     }
 }
@@ -494,11 +1139,30 @@ impl From<Payload> for Transaction {
             inputs: value.inputs(),
             value: value.value(),
             nonce: value.nonce(),
+            fee: value.fee(),
+            valid_until: value.valid_until(),
             ..Default::default()
         }
     }
 }
 
+impl From<&Transaction> for Payload {
+    fn from(value: &Transaction) -> Self {
+        Payload {
+            transaction_type: value.transaction_type(),
+            from: value.from,
+            to: value.to,
+            program_id: value.program_id,
+            op: value.op(),
+            inputs: value.inputs(),
+            value: value.value(),
+            nonce: value.nonce(),
+            fee: value.fee(),
+            valid_until: value.valid_until(),
+        }
+    }
+}
+
 impl From<Transaction> for Token {
     fn from(value: Transaction) -> Self {
         TokenBuilder::default()
@@ -569,3 +1233,865 @@ impl TryFrom<(Token, Transaction)> for Token {
         )))
     }
 }
+
+#[cfg(test)]
+mod transaction_tests {
+    use super::{
+        Hashable, Payload, PayloadBuilder, PayloadError, RpcTransaction, SigningScheme,
+        Transaction, TransactionSummary, TransactionType, TxError, TxKind, TX_HASH_DOMAIN,
+    };
+    use crate::{
+        Account, AccountError, AccountType, Address, RecoverableSignature,
+        RecoverableSignatureBuilder, Token, TokenDeltaBuilder, U256,
+    };
+    use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+    use sha3::{Digest, Keccak256};
+
+    fn test_payload(from: [u8; 20], nonce: U256) -> Payload {
+        PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from(from)
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(5))
+            .nonce(nonce)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_payload_byte_layout_includes_nonce() {
+        let payload = test_payload([1u8; 20], U256::from(1));
+        let expected = format!(
+            r#"{{"transactionType":{{"send":"0x{value}"}},"from":"0x{from}","to":"0x{to}","programId":"0x{program_id}","op":"","transactionInputs":"","value":"0x{value}","nonce":"0x{nonce}","fee":"0x{fee}"}}"#,
+            value = format!("{:064x}", 5),
+            from = "01".repeat(20),
+            to = "02".repeat(20),
+            program_id = "00".repeat(20),
+            nonce = format!("{:064x}", 1),
+            fee = format!("{:064x}", 0),
+        );
+
+        let transaction: Transaction = payload.clone().into();
+        assert_eq!(payload.as_bytes(), expected.as_bytes());
+        assert_eq!(transaction.as_bytes(), payload.as_bytes());
+        assert_ne!(
+            test_payload([1u8; 20], U256::from(2)).hash(),
+            payload.hash()
+        );
+    }
+
+    #[test]
+    fn test_fee_is_signed_over() {
+        let mut builder = PayloadBuilder::default();
+        builder
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(5))
+            .nonce(U256::from(1));
+        let payload = builder.fee(U256::from(3)).build().unwrap();
+        let expected = format!(
+            r#"{{"transactionType":{{"send":"0x{value}"}},"from":"0x{from}","to":"0x{to}","programId":"0x{program_id}","op":"","transactionInputs":"","value":"0x{value}","nonce":"0x{nonce}","fee":"0x{fee}"}}"#,
+            value = format!("{:064x}", 5),
+            from = "01".repeat(20),
+            to = "02".repeat(20),
+            program_id = "00".repeat(20),
+            nonce = format!("{:064x}", 1),
+            fee = format!("{:064x}", 3),
+        );
+
+        assert_eq!(payload.as_bytes(), expected.as_bytes());
+        assert_eq!(
+            payload.hash_string(),
+            "0x33e08f19487bbf4ebc481f687ae043a1c13afb970c3585d7bcae8af6f95ec5aa"
+        );
+        let transaction: Transaction = payload.clone().into();
+        assert_eq!(transaction.fee(), U256::from(3));
+        assert_eq!(transaction.hash_string(), payload.hash_string());
+        assert_ne!(
+            builder.fee(U256::from(0)).build().unwrap().hash(),
+            payload.hash()
+        );
+    }
+
+    #[test]
+    fn test_valid_until_is_signed_over() {
+        let mut builder = PayloadBuilder::default();
+        builder
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(5))
+            .nonce(U256::from(1));
+        let payload = builder.valid_until(Some(1_700_000_000)).build().unwrap();
+        let expected = format!(
+            r#"{{"transactionType":{{"send":"0x{value}"}},"from":"0x{from}","to":"0x{to}","programId":"0x{program_id}","op":"","transactionInputs":"","value":"0x{value}","nonce":"0x{nonce}","fee":"0x{fee}","validUntil":1700000000}}"#,
+            value = format!("{:064x}", 5),
+            from = "01".repeat(20),
+            to = "02".repeat(20),
+            program_id = "00".repeat(20),
+            nonce = format!("{:064x}", 1),
+            fee = format!("{:064x}", 0),
+        );
+
+        assert_eq!(payload.as_bytes(), expected.as_bytes());
+        assert_eq!(
+            payload.hash_string(),
+            "0xba10ac27d6007e7a0a9569b78472100b5a673d38e85065e040e6b5acbe83fdf4"
+        );
+        let transaction: Transaction = payload.clone().into();
+        assert_eq!(transaction.valid_until(), Some(1_700_000_000));
+        assert_eq!(transaction.hash_string(), payload.hash_string());
+        assert_ne!(
+            builder.valid_until(None).build().unwrap().hash(),
+            payload.hash()
+        );
+    }
+
+    #[test]
+    fn test_is_expired_boundary() {
+        let payload = PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(5))
+            .nonce(U256::from(1))
+            .valid_until(Some(100))
+            .build()
+            .unwrap();
+        let transaction: Transaction = payload.into();
+
+        assert!(!transaction.is_expired(99));
+        assert!(!transaction.is_expired(100));
+        assert!(transaction.is_expired(101));
+
+        let transaction: Transaction = test_payload([1u8; 20], U256::from(1)).into();
+        assert!(!transaction.is_expired(u64::MAX));
+    }
+
+    /// Signs the digest produced from the payload hash with a fixed key and
+    /// returns the signer's address along with the resulting transaction,
+    /// whose `v` is produced by `encode_v`.
+    fn signed_transaction(
+        digest: impl Fn(Vec<u8>) -> Vec<u8>,
+        encode_v: impl Fn(i32) -> i32,
+    ) -> (Address, Transaction) {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let signer = Address::from(PublicKey::from_secret_key_global(&secret_key));
+        let payload = test_payload(signer.into(), U256::from(1));
+        let message = Message::from_digest_slice(&digest(payload.hash())).unwrap();
+        let sig: RecoverableSignature = SECP256K1
+            .sign_ecdsa_recoverable(&message, &secret_key)
+            .into();
+        let sig = RecoverableSignatureBuilder::default()
+            .r(sig.get_r())
+            .s(sig.get_s())
+            .v(encode_v(sig.get_v()))
+            .build()
+            .unwrap();
+
+        (signer, (payload, sig).into())
+    }
+
+    #[test]
+    fn test_recover_eip155_signature() {
+        const CHAIN_ID: i32 = 1;
+        let (signer, transaction) = signed_transaction(|hash| hash, |rec| CHAIN_ID * 2 + 35 + rec);
+
+        assert_eq!(transaction.chain_id(), Some(1));
+        assert!((0..=1).contains(&transaction.recovery_id()));
+        assert_eq!(transaction.recover().unwrap(), signer);
+        transaction.verify_signature().unwrap();
+    }
+
+    #[test]
+    fn test_recover_legacy_signature() {
+        // Legacy `v = 27/28` signatures are electrum style, signed over the
+        // prefixed personal message of the transaction hash.
+        let (signer, transaction) = signed_transaction(
+            |hash| ethers_core::utils::hash_message(hash).as_bytes().to_vec(),
+            |rec| rec + 27,
+        );
+
+        assert_eq!(transaction.chain_id(), None);
+        assert_eq!(transaction.recover().unwrap(), signer);
+        transaction.verify_signature().unwrap();
+    }
+
+    #[test]
+    fn test_transaction_json_round_trip() {
+        let (signer, transaction) = signed_transaction(|hash| hash, |rec| rec);
+        let json = transaction.to_json();
+
+        assert!(json.contains(&format!("\"from\":\"0x{}\"", hex::encode(signer.inner()))));
+        assert!(json.contains(&format!("\"to\":\"0x{}\"", hex::encode([2u8; 20]))));
+        assert!(json.contains(&format!(
+            "\"r\":\"0x{}\"",
+            hex::encode(transaction.sig().unwrap().get_r())
+        )));
+
+        let parsed = Transaction::from_json(&json).unwrap();
+        assert_eq!(parsed, transaction);
+        assert_eq!(parsed.as_bytes(), transaction.as_bytes());
+        assert!(Transaction::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_touched_addresses_are_deduplicated() {
+        let transaction = |from: u8, to: u8, program_id: u8| -> Transaction {
+            PayloadBuilder::default()
+                .transaction_type(TransactionType::Send(U256::from(1)))
+                .from([from; 20])
+                .to([to; 20])
+                .program_id([program_id; 20])
+                .op(String::new())
+                .inputs(String::new())
+                .value(U256::from(1))
+                .nonce(U256::from(1))
+                .build()
+                .unwrap()
+                .into()
+        };
+        let address = |n: u8| Address::new([n; 20]);
+
+        assert_eq!(
+            transaction(1, 2, 3).touched_addresses(),
+            vec![address(1), address(2), address(3)]
+        );
+        assert_eq!(
+            transaction(1, 1, 3).touched_addresses(),
+            vec![address(1), address(3)]
+        );
+        assert_eq!(
+            transaction(2, 1, 2).touched_addresses(),
+            vec![address(2), address(1)]
+        );
+        assert_eq!(transaction(4, 4, 4).touched_addresses(), vec![address(4)]);
+    }
+
+    #[test]
+    fn test_transaction_type_kind() {
+        let n = U256::from(3);
+        let cases = [
+            (TransactionType::BridgeIn(n), TxKind::BridgeIn, true),
+            (TransactionType::Send(n), TxKind::Send, false),
+            (TransactionType::Call(n), TxKind::Call, false),
+            (TransactionType::BridgeOut(n), TxKind::BridgeOut, true),
+            (
+                TransactionType::RegisterProgram(n),
+                TxKind::RegisterProgram,
+                false,
+            ),
+        ];
+        for (transaction_type, kind, is_bridge) in cases {
+            assert_eq!(transaction_type.kind(), kind);
+            assert_eq!(transaction_type.is_bridge(), is_bridge);
+        }
+    }
+
+    #[test]
+    fn test_mempool_key_orders_by_fee_then_nonce() {
+        let transaction = |fee: u64, nonce: u64| -> Transaction {
+            PayloadBuilder::default()
+                .transaction_type(TransactionType::Send(U256::from(5)))
+                .from([1u8; 20])
+                .to([2u8; 20])
+                .program_id([0u8; 20])
+                .op(String::new())
+                .inputs(String::new())
+                .value(U256::from(5))
+                .nonce(U256::from(nonce))
+                .fee(U256::from(fee))
+                .build()
+                .unwrap()
+                .into()
+        };
+        let mut transactions = vec![
+            transaction(1, 1),
+            transaction(5, 3),
+            transaction(3, 2),
+            transaction(5, 2),
+        ];
+
+        transactions.sort_by_key(Transaction::mempool_key);
+        let order: Vec<(U256, U256)> = transactions
+            .iter()
+            .map(|transaction| (transaction.fee(), transaction.nonce()))
+            .collect();
+        assert_eq!(
+            order,
+            [(5u64, 2u64), (5, 3), (3, 2), (1, 1)]
+                .map(|(fee, nonce)| (U256::from(fee), U256::from(nonce)))
+                .to_vec()
+        );
+    }
+
+    #[test]
+    fn test_rpc_values_are_quantities() {
+        let mut builder = PayloadBuilder::default();
+        builder
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(255))
+            .nonce(U256::from(0));
+        let payload = builder.build().unwrap();
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["value"], "0xff");
+        assert_eq!(json["nonce"], "0x0");
+        assert_eq!(json["fee"], "0x0");
+        assert_eq!(serde_json::from_value::<Payload>(json).unwrap(), payload);
+
+        let transaction: Transaction = payload.into();
+        let json = serde_json::to_value(RpcTransaction::from(&transaction)).unwrap();
+        assert_eq!(json["hash"], transaction.hash_string());
+        assert_eq!(json["value"], "0xff");
+        assert_eq!(json["nonce"], "0x0");
+    }
+
+    #[test]
+    fn test_transaction_summary() {
+        let transaction: Transaction = test_payload([1u8; 20], U256::from(3)).into();
+        let summary = TransactionSummary::from(&transaction);
+        assert_eq!(summary.hash(), transaction.hash_string());
+        assert_eq!(summary.from(), transaction.from());
+        assert_eq!(summary.to(), transaction.to());
+        assert_eq!(summary.kind(), TxKind::Send);
+        assert_eq!(summary.value(), U256::from(5));
+    }
+
+    #[test]
+    fn test_bridge_out_record() {
+        let transaction: Transaction = PayloadBuilder::default()
+            .transaction_type(TransactionType::BridgeOut(U256::from(9)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([3u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(9))
+            .nonce(U256::from(1))
+            .build()
+            .unwrap()
+            .into();
+
+        let record = transaction.bridge_out_record().unwrap();
+        assert_eq!(record.account(), Address::from([1u8; 20]));
+        assert_eq!(record.program(), Address::from([3u8; 20]));
+        assert_eq!(record.amount(), U256::from(9));
+        assert_eq!(record.l1_recipient(), Address::from([2u8; 20]));
+
+        let send: Transaction = test_payload([1u8; 20], U256::from(1)).into();
+        assert_eq!(send.bridge_out_record(), None);
+    }
+
+    #[test]
+    fn test_transaction_type_amount() {
+        let cases = [
+            TransactionType::BridgeIn(U256::from(1)),
+            TransactionType::Send(U256::from(2)),
+            TransactionType::Call(U256::from(3)),
+            TransactionType::BridgeOut(U256::from(4)),
+            TransactionType::RegisterProgram(U256::from(5)),
+        ];
+        for (expected, transaction_type) in (1u64..).zip(cases) {
+            assert_eq!(transaction_type.amount(), U256::from(expected));
+        }
+    }
+
+    #[test]
+    fn test_transaction_type_from_u64() {
+        assert_eq!(
+            TransactionType::bridge_in_from_u64(1),
+            TransactionType::BridgeIn(U256::from(1))
+        );
+        assert_eq!(
+            TransactionType::send_from_u64(2),
+            TransactionType::Send(U256::from(2))
+        );
+        assert_eq!(
+            TransactionType::call_from_u64(3),
+            TransactionType::Call(U256::from(3))
+        );
+        assert_eq!(
+            TransactionType::bridge_out_from_u64(4),
+            TransactionType::BridgeOut(U256::from(4))
+        );
+        assert_eq!(
+            TransactionType::register_program_from_u64(u64::MAX),
+            TransactionType::RegisterProgram(U256::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_transaction_type_strings_include_amount() {
+        let n = U256::from(5);
+        let amount = format!("0x{:064x}", n);
+        let cases = [
+            (TransactionType::BridgeIn(n), "bridgeIn5", "bridgeIn"),
+            (TransactionType::Send(n), "send5", "send"),
+            (TransactionType::Call(n), "call5", "call"),
+            (TransactionType::BridgeOut(n), "bridgeOut5", "bridgeOut"),
+            (
+                TransactionType::RegisterProgram(n),
+                "deploy5",
+                "registerProgram",
+            ),
+        ];
+        for (transaction_type, string, json_key) in cases {
+            assert_eq!(transaction_type.to_string(), string);
+            assert_eq!(
+                transaction_type.to_json(),
+                serde_json::json!({ json_key: amount })
+            );
+        }
+    }
+
+    #[test]
+    fn test_bridge_out_amount_changes_payload_hash() {
+        let payload = |n: u64| {
+            PayloadBuilder::default()
+                .transaction_type(TransactionType::BridgeOut(U256::from(n)))
+                .from([1u8; 20])
+                .to([2u8; 20])
+                .program_id([0u8; 20])
+                .op(String::new())
+                .inputs(String::new())
+                .value(U256::from(5))
+                .nonce(U256::from(1))
+                .build()
+                .unwrap()
+        };
+        assert_ne!(payload(1).as_bytes(), payload(2).as_bytes());
+        assert_ne!(payload(1).hash(), payload(2).hash());
+    }
+
+    #[test]
+    fn test_transaction_validate() {
+        let (signer, transaction) = signed_transaction(|hash| hash, |rec| rec);
+        let mut account = Account::new(AccountType::User, None, signer, None);
+        assert_eq!(
+            transaction.validate(&account),
+            Err(TxError::Account(AccountError::UnknownProgram(
+                transaction.program_id()
+            )))
+        );
+
+        let mut token = Token::empty(transaction.program_id(), signer);
+        *token.balance_mut() = transaction.value();
+        account
+            .insert_program(&transaction.program_id(), token)
+            .unwrap();
+        assert_eq!(transaction.validate(&account), Ok(signer));
+
+        account.increment_nonce().unwrap();
+        assert!(matches!(
+            transaction.validate(&account),
+            Err(TxError::Account(AccountError::InvalidNonce { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_validate_requires_value_and_fee() {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let signer = Address::from(PublicKey::from_secret_key_global(&secret_key));
+        let mut payload = test_payload(signer.into(), U256::from(1));
+        payload.fee = U256::from(1);
+        let message = Message::from_digest_slice(&payload.hash()).unwrap();
+        let sig: RecoverableSignature = SECP256K1
+            .sign_ecdsa_recoverable(&message, &secret_key)
+            .into();
+        let transaction: Transaction = (payload, sig).into();
+        assert_eq!(transaction.required_balance(), U256::from(6));
+
+        let mut account = Account::new(AccountType::User, None, signer, None);
+        let mut token = Token::empty(transaction.program_id(), signer);
+        *token.balance_mut() = transaction.value();
+        account
+            .insert_program(&transaction.program_id(), token.clone())
+            .unwrap();
+        assert_eq!(
+            transaction.validate(&account),
+            Err(TxError::Account(AccountError::InsufficientBalance {
+                program: transaction.program_id(),
+                needed: U256::from(6),
+                available: U256::from(5),
+            }))
+        );
+
+        *token.balance_mut() = transaction.required_balance();
+        account
+            .insert_program(&transaction.program_id(), token)
+            .unwrap();
+        assert_eq!(transaction.validate(&account), Ok(signer));
+    }
+
+    #[test]
+    fn test_verify_sender() {
+        let (_, transaction) = signed_transaction(|hash| hash, |rec| rec);
+        transaction.verify_sender().unwrap();
+
+        let mut spoofed = transaction.clone();
+        spoofed.from = [9u8; 20];
+        assert!(matches!(
+            spoofed.verify_sender(),
+            Err(TxError::SenderMismatch { from, .. }) if from == Address::new([9u8; 20])
+        ));
+    }
+
+    #[test]
+    fn test_write_bytes_matches_json_encoding() {
+        let payload = PayloadBuilder::default()
+            .transaction_type(TransactionType::Call(U256::from(7)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([3u8; 20])
+            .op("say \"hi\"\n".to_string())
+            .inputs(r#"{"key":"value"}"#.to_string())
+            .value(U256::from(9))
+            .nonce(U256::from(4))
+            .fee(U256::from(2))
+            .build()
+            .unwrap();
+        let expected = serde_json::json!({
+            "transactionType": payload.transaction_type().to_json(),
+            "from": Address::from(payload.from()).to_full_string(),
+            "to": Address::from(payload.to()).to_full_string(),
+            "programId": Address::from(payload.program_id()).to_full_string(),
+            "op": payload.op(),
+            "transactionInputs": payload.inputs(),
+            "value": format!("0x{:064x}", payload.value()),
+            "nonce": format!("0x{:064x}", payload.nonce()),
+            "fee": format!("0x{:064x}", payload.fee())
+        })
+        .to_string();
+
+        let mut bytes = b"prefix".to_vec();
+        payload.write_bytes(&mut bytes);
+        assert_eq!(&bytes[..6], b"prefix");
+        assert_eq!(&bytes[6..], expected.as_bytes());
+
+        let transaction: Transaction = payload.clone().into();
+        assert_eq!(transaction.as_bytes(), expected.as_bytes());
+    }
+
+    fn payload(transaction_type: TransactionType, from: u8, to: u8, value: u64) -> Payload {
+        PayloadBuilder::default()
+            .transaction_type(transaction_type)
+            .from([from; 20])
+            .to([to; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(value))
+            .nonce(U256::from(0))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_payload_validate_accepts_well_formed_payloads() {
+        assert_eq!(
+            payload(TransactionType::Send(U256::from(5)), 1, 2, 5).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            payload(TransactionType::Call(U256::from(0)), 1, 0, 0).validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_payload_validate_rejects_zero_sender() {
+        assert_eq!(
+            payload(TransactionType::Send(U256::from(5)), 0, 2, 5).validate(),
+            Err(PayloadError::ZeroSender)
+        );
+        assert_eq!(
+            payload(TransactionType::Call(U256::from(0)), 0, 2, 0).validate(),
+            Err(PayloadError::ZeroSender)
+        );
+    }
+
+    #[test]
+    fn test_payload_validate_rejects_zero_send_recipient() {
+        assert_eq!(
+            payload(TransactionType::Send(U256::from(5)), 1, 0, 5).validate(),
+            Err(PayloadError::ZeroRecipient)
+        );
+    }
+
+    #[test]
+    fn test_payload_validate_rejects_value_mismatch() {
+        let cases = [
+            TransactionType::BridgeIn(U256::from(5)),
+            TransactionType::Send(U256::from(5)),
+            TransactionType::Call(U256::from(5)),
+            TransactionType::BridgeOut(U256::from(5)),
+            TransactionType::RegisterProgram(U256::from(5)),
+        ];
+        for transaction_type in cases {
+            assert_eq!(
+                payload(transaction_type, 1, 2, 6).validate(),
+                Err(PayloadError::ValueMismatch {
+                    value: U256::from(6),
+                    amount: U256::from(5),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_transaction_round_trips_to_payload() {
+        let (signer, transaction) = signed_transaction(|hash| hash, |rec| rec);
+        let payload = Payload::from(&transaction);
+
+        assert_eq!(payload, test_payload(signer.into(), U256::from(1)));
+        assert_eq!(payload.as_bytes(), transaction.as_bytes());
+        assert_eq!(payload.hash(), transaction.hash());
+    }
+
+    #[test]
+    fn test_sign_produces_verifiable_transaction() {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let signer = Address::from(PublicKey::from_secret_key_global(&secret_key));
+        let transaction =
+            Transaction::sign(test_payload(signer.into(), U256::from(1)), &secret_key).unwrap();
+
+        assert_eq!(transaction.from(), signer);
+        transaction.verify_signature().unwrap();
+        transaction.verify_sender().unwrap();
+        assert_eq!(transaction.recover().unwrap(), signer);
+    }
+
+    #[test]
+    fn test_recover_address_matches_known_signer() {
+        let mut key = [0u8; 32];
+        key[31] = 1;
+        let secret_key = SecretKey::from_slice(&key).unwrap();
+        let signer = Address::from_hex("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf").unwrap();
+        let transaction =
+            Transaction::sign(test_payload(signer.into(), U256::from(1)), &secret_key).unwrap();
+
+        assert_eq!(transaction.recover_address(), Ok(signer));
+
+        let mut tampered = transaction.clone();
+        tampered.r = [0u8; 32];
+        assert!(matches!(
+            tampered.recover_address(),
+            Err(TxError::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_multisig_threshold() {
+        let keys: Vec<SecretKey> = (1u8..=3)
+            .map(|n| SecretKey::from_slice(&[n; 32]).unwrap())
+            .collect();
+        let signers: Vec<Address> = keys
+            .iter()
+            .map(|key| Address::from(PublicKey::from_secret_key(SECP256K1, key)))
+            .collect();
+        let mut transaction =
+            Transaction::sign(test_payload(signers[0].into(), U256::from(1)), &keys[0]).unwrap();
+        assert!(transaction.additional_sigs().is_empty());
+        transaction.verify_multisig(1, &signers).unwrap();
+
+        transaction.cosign(&keys[1]).unwrap();
+        transaction.cosign(&keys[1]).unwrap();
+        transaction.verify_multisig(2, &signers).unwrap();
+        assert_eq!(
+            transaction.verify_multisig(3, &signers),
+            Err(TxError::InsufficientSigners {
+                required: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            transaction.verify_multisig(2, &signers[1..]),
+            Err(TxError::InsufficientSigners {
+                required: 2,
+                found: 1
+            })
+        );
+
+        transaction.cosign(&keys[2]).unwrap();
+        transaction.verify_multisig(3, &signers).unwrap();
+        let parsed = Transaction::from_json(&transaction.to_json()).unwrap();
+        parsed.verify_multisig(3, &signers).unwrap();
+        parsed.verify_sender().unwrap();
+    }
+
+    #[test]
+    fn test_out_of_range_v_is_a_signature_error() {
+        let mut key = [0u8; 32];
+        key[31] = 1;
+        let secret_key = SecretKey::from_slice(&key).unwrap();
+        let mut transaction =
+            Transaction::sign(test_payload([1u8; 20], U256::from(1)), &secret_key).unwrap();
+        transaction.v = 7;
+
+        assert!(matches!(transaction.sig(), Err(TxError::Signature(_))));
+        assert!(matches!(
+            transaction.verify_signature(),
+            Err(TxError::Signature(_))
+        ));
+        assert!(matches!(
+            transaction.recover_address(),
+            Err(TxError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_simulate_send_leaves_account_unchanged() {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let sender = Address::from(PublicKey::from_secret_key_global(&secret_key));
+        let program_id = Address::new([0u8; 20]);
+        let delta = |receive: u64, send: u64| {
+            TokenDeltaBuilder::default()
+                .receive(U256::from(receive))
+                .send(U256::from(send))
+                .build()
+                .unwrap()
+        };
+        let mut account = Account::new(AccountType::User, None, sender, None);
+        account.apply_delta(&program_id, &delta(10, 0)).unwrap();
+        let before = account.clone();
+
+        let send = |value: u64, fee: u64| {
+            PayloadBuilder::default()
+                .transaction_type(TransactionType::Send(U256::from(value)))
+                .from(sender.into())
+                .to([2u8; 20])
+                .program_id(program_id.into())
+                .op(String::new())
+                .inputs(String::new())
+                .value(U256::from(value))
+                .nonce(U256::from(1))
+                .fee(U256::from(fee))
+                .build()
+                .unwrap()
+        };
+        let transaction = Transaction::sign(send(3, 2), &secret_key).unwrap();
+        assert_eq!(
+            transaction.simulate(&account).unwrap(),
+            vec![delta(0, 5), delta(3, 0)]
+        );
+        assert_eq!(account, before);
+
+        let overdraft = Transaction::sign(send(9, 2), &secret_key).unwrap();
+        assert!(matches!(
+            overdraft.simulate(&account),
+            Err(TxError::Account(_))
+        ));
+        assert_eq!(account, before);
+
+        let unsigned = Transaction::from(send(3, 2));
+        assert!(unsigned.simulate(&account).is_err());
+        assert_eq!(account, before);
+    }
+
+    #[test]
+    fn test_canonical_json_signature() {
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let signer = Address::from(PublicKey::from_secret_key_global(&secret_key));
+        let payload = PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from(signer.into())
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op("transfer".to_string())
+            .inputs("{}".to_string())
+            .value(U256::from(5))
+            .nonce(U256::from(1))
+            .fee(U256::from(3))
+            .build()
+            .unwrap();
+        let unsigned = Transaction::from(payload.clone());
+        let expected = format!(
+            r#"{{"fee":"0x{fee}","from":"0x{from}","nonce":"0x{nonce}","op":"transfer","programId":"0x{program_id}","to":"0x{to}","transactionInputs":"{{}}","transactionType":{{"send":"0x{value}"}},"value":"0x{value}"}}"#,
+            fee = format!("{:064x}", 3),
+            from = hex::encode(signer.inner()),
+            nonce = format!("{:064x}", 1),
+            program_id = "00".repeat(20),
+            to = "02".repeat(20),
+            value = format!("{:064x}", 5),
+        );
+        assert_eq!(unsigned.canonical_json(), expected);
+
+        let message = Message::from_digest_slice(&unsigned.canonical_hash()).unwrap();
+        let sig: RecoverableSignature = SECP256K1
+            .sign_ecdsa_recoverable(&message, &secret_key)
+            .into();
+        let transaction: Transaction = (payload, sig).into();
+        assert_eq!(transaction.canonical_json(), expected);
+        transaction
+            .verify_signature_with(SigningScheme::CanonicalJson)
+            .unwrap();
+        assert!(transaction.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_hash_preimage_is_domain_separated() {
+        let (_, transaction) = signed_transaction(|hash| hash, |rec| rec);
+        let payload = Payload::from(&transaction);
+
+        for preimage in [payload.preimage(), transaction.preimage()] {
+            assert!(preimage.starts_with(b"LASR_TX_V1"));
+            assert_eq!(&preimage[TX_HASH_DOMAIN.len()..], transaction.as_bytes());
+        }
+        assert_eq!(
+            transaction.hash(),
+            Keccak256::digest(transaction.preimage()).to_vec()
+        );
+        assert_ne!(
+            transaction.hash(),
+            Keccak256::digest(transaction.as_bytes()).to_vec()
+        );
+    }
+
+    #[test]
+    fn test_hashable_matches_fixed_hash() {
+        fn hash_of<T: Hashable>(value: &T) -> (Vec<u8>, String) {
+            (value.hash(), value.hash_string())
+        }
+
+        let payload = PayloadBuilder::default()
+            .transaction_type(TransactionType::Send(U256::from(5)))
+            .from([1u8; 20])
+            .to([2u8; 20])
+            .program_id([0u8; 20])
+            .op(String::new())
+            .inputs(String::new())
+            .value(U256::from(5))
+            .nonce(U256::from(1))
+            .fee(U256::from(3))
+            .build()
+            .unwrap();
+        let transaction: Transaction = payload.clone().into();
+        let expected = "0x33e08f19487bbf4ebc481f687ae043a1c13afb970c3585d7bcae8af6f95ec5aa";
+
+        for (hash, hash_string) in [hash_of(&payload), hash_of(&transaction)] {
+            assert_eq!(hash_string, expected);
+            assert_eq!(format!("0x{}", hex::encode(&hash)), expected);
+        }
+        assert_eq!(transaction.hash(), payload.hash());
+        assert_eq!(transaction.hash_string(), expected);
+    }
+}
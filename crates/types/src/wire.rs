@@ -0,0 +1,114 @@
+//! Length-prefixed framing for messages sent between nodes.
+//!
+//! Every frame is a 4 byte big-endian length followed by that many bytes of
+//! bincode encoded body, so a stream of frames can be split back into
+//! messages as bytes arrive.
+use bytes::{Buf, BytesMut};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// The size of the length prefix at the start of every frame.
+pub const FRAME_HEADER_LEN: usize = 4;
+
+/// The largest frame body `decode_framed` accepts.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum WireError {
+    #[error("frame of {0} bytes exceeds the maximum frame length")]
+    FrameTooLarge(usize),
+
+    #[error(transparent)]
+    Bincode(#[from] bincode::Error),
+}
+
+/// Encodes `msg` with bincode and prepends the length of the body.
+///
+/// Panics if `msg` cannot be encoded by bincode or its encoding does not fit
+/// in a `u32` length.
+pub fn encode_framed<T: Serialize>(msg: &T) -> Vec<u8> {
+    let body = bincode::serialize(msg).expect("message must be bincode serializable");
+    let len = u32::try_from(body.len()).expect("message body must fit in a u32 length");
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + body.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(&body);
+    frame
+}
+
+/// Decodes the first frame in `buf`, consuming it.
+///
+/// Returns `Ok(None)` and leaves `buf` untouched if a full frame has not been
+/// buffered yet.
+pub fn decode_framed<T: DeserializeOwned>(buf: &mut BytesMut) -> Result<Option<T>, WireError> {
+    if buf.len() < FRAME_HEADER_LEN {
+        return Ok(None);
+    }
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    header.copy_from_slice(&buf[..FRAME_HEADER_LEN]);
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(WireError::FrameTooLarge(len));
+    }
+    if buf.len() < FRAME_HEADER_LEN + len {
+        return Ok(None);
+    }
+
+    buf.advance(FRAME_HEADER_LEN);
+    let body = buf.split_to(len);
+    Ok(Some(bincode::deserialize(&body)?))
+}
+
+#[cfg(test)]
+mod wire_tests {
+    use super::{decode_framed, encode_framed, WireError, FRAME_HEADER_LEN, MAX_FRAME_LEN};
+    use crate::{Account, AccountType, Address};
+    use bytes::BytesMut;
+
+    fn account(n: u8) -> Account {
+        Account::new(AccountType::User, None, Address::new([n; 20]), None)
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = encode_framed(&account(1));
+        let len = u32::from_be_bytes(frame[..FRAME_HEADER_LEN].try_into().unwrap());
+        assert_eq!(len as usize, frame.len() - FRAME_HEADER_LEN);
+
+        let mut buf = BytesMut::from(&frame[..]);
+        let decoded: Option<Account> = decode_framed(&mut buf).unwrap();
+        assert_eq!(decoded, Some(account(1)));
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_partial_frame_waits_for_more_bytes() {
+        let frame = encode_framed(&account(1));
+        let mut buf = BytesMut::new();
+        for chunk in frame.chunks(3) {
+            assert!(decode_framed::<Account>(&mut buf).unwrap().is_none());
+            buf.extend_from_slice(chunk);
+        }
+        assert_eq!(decode_framed(&mut buf).unwrap(), Some(account(1)));
+    }
+
+    #[test]
+    fn test_concatenated_frames() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&encode_framed(&account(1)));
+        buf.extend_from_slice(&encode_framed(&account(2)));
+        buf.extend_from_slice(&encode_framed(&account(3))[..FRAME_HEADER_LEN + 1]);
+
+        assert_eq!(decode_framed(&mut buf).unwrap(), Some(account(1)));
+        assert_eq!(decode_framed(&mut buf).unwrap(), Some(account(2)));
+        assert!(decode_framed::<Account>(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_oversized_frame_is_rejected() {
+        let mut buf = BytesMut::from(&((MAX_FRAME_LEN as u32) + 1).to_be_bytes()[..]);
+        assert!(matches!(
+            decode_framed::<Account>(&mut buf),
+            Err(WireError::FrameTooLarge(len)) if len == MAX_FRAME_LEN + 1
+        ));
+    }
+}
@@ -121,7 +121,9 @@ impl<L: LasrRpcClient + Send + Sync> Wallet<L> {
         let account = self.account();
         let address = self.address();
 
-        account.validate_balance(program_id, value)?;
+        account
+            .validate_balance(program_id, value)
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
 
         let tx_nonce = account.nonce() + U256::from(1);
         let payload = self
@@ -190,7 +192,9 @@ impl<L: LasrRpcClient + Send + Sync> Wallet<L> {
         dbg!("validating balance");
 
         if value > U256::from(0) {
-            account.validate_balance(program_id, value)?;
+            account
+                .validate_balance(program_id, value)
+                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
         }
 
         dbg!("building transaciton payload");
@@ -307,8 +311,10 @@ impl<L: LasrRpcClient + Send + Sync> Wallet<L> {
         &mut self.account
     }
 
-    fn increment_nonce(&mut self) {
-        self.account_mut().increment_nonce();
+    fn increment_nonce(&mut self) -> WalletResult<()> {
+        self.account_mut()
+            .increment_nonce()
+            .map_err(|e| Box::new(e) as WalletError)
     }
 
     pub(crate) fn account(&self) -> Account {